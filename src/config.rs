@@ -0,0 +1,773 @@
+//! Settings from the TOML config file, merged with the command line.
+//!
+//! Top-level keys are the defaults, `[profile.<name>]` tables override them when
+//! the profile is selected, and `[[cluster]]` tables define the clusters of the
+//! multi-cluster dashboard:
+//!
+//! ```toml
+//! interval = 10
+//! color = "never"
+//! theme = "color-blind"
+//!
+//! [[cluster]]
+//! name = "prod"
+//! prefix_command = "ssh prod-admin sudo"
+//!
+//! [profile.lab]
+//! rest_url = "https://lab-mgr:8003"
+//! rest_user = "doctor"
+//!
+//! [alerts]
+//! stall_minutes = 30
+//!
+//! [osd_movement]
+//! sort = "excess"
+//! columns = ["host", "excess_waiting", "excess_active", "excess_eta", "excess_bytes"]
+//!
+//! [keys]
+//! pause = ["space"]
+//!
+//! [[alerts.sink]]
+//! type = "slack"
+//! url = "https://hooks.slack.com/services/..."
+//! ```
+
+use crate::monitor::alerts::AlertSettings;
+use crate::monitor::keymap::{Action, KeyBinding};
+use crate::monitor::mon::{MonClient, MonClientConfig};
+use crate::monitor::prefix::PrefixCommand;
+use crate::monitor::rest::{RestClient, RestConfig};
+use crate::monitor::source::DataSource;
+use crate::monitor::state::OsdMovementSettings;
+use crate::monitor::ui::ThemeName;
+use crate::monitor::{DEFAULT_INTERVAL_SECS, DEFAULT_PING_THRESHOLD_MS, DEFAULT_TIMEOUT_SECS};
+use crate::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+use std::env;
+use std::path::{Path, PathBuf};
+
+pub const DEFAULT_CLIENT_NAME: &str = "client.admin";
+const REDACTED: &str = "<redacted>";
+
+/// When to use colours in the terminal interface
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum ColorMode {
+    /// Colours unless the `NO_COLOR` environment variable is set
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+impl ColorMode {
+    pub fn use_colors(self) -> bool {
+        match self {
+            ColorMode::Auto => env::var("NO_COLOR").is_err(),
+            ColorMode::Always => true,
+            ColorMode::Never => false,
+        }
+    }
+}
+
+/// How to reach a cluster; the same settings as the backend options of `monitor`.
+#[derive(Debug, Default, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct SourceConfig {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub prefix_command: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub prefix_args: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rest_url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rest_user: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rest_key: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rest_token: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rest_insecure: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rest_ca_cert: Option<PathBuf>,
+    #[serde(skip_serializing_if = "is_false")]
+    pub mon_client: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ceph_conf: Option<PathBuf>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub keyring: Option<PathBuf>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub client_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mon_host: Option<String>,
+}
+
+fn is_false(value: &bool) -> bool {
+    !value
+}
+
+impl SourceConfig {
+    /// Whether any of the settings that pick the backend is present
+    pub fn selects_backend(&self) -> bool {
+        self.prefix_command.is_some()
+            || !self.prefix_args.is_empty()
+            || self.rest_url.is_some()
+            || self.mon_client
+    }
+
+    /// Settings of `over` win. When `over` picks a backend, the backend choice
+    /// of `self` is dropped, so a `--rest-url` replaces a configured prefix command.
+    pub fn merge(self, over: SourceConfig) -> SourceConfig {
+        let base = if over.selects_backend() {
+            SourceConfig {
+                prefix_command: None,
+                prefix_args: Vec::new(),
+                rest_url: None,
+                mon_client: false,
+                ..self
+            }
+        } else {
+            self
+        };
+
+        SourceConfig {
+            prefix_command: over.prefix_command.or(base.prefix_command),
+            prefix_args: if over.prefix_args.is_empty() {
+                base.prefix_args
+            } else {
+                over.prefix_args
+            },
+            rest_url: over.rest_url.or(base.rest_url),
+            rest_user: over.rest_user.or(base.rest_user),
+            rest_key: over.rest_key.or(base.rest_key),
+            rest_token: over.rest_token.or(base.rest_token),
+            rest_insecure: over.rest_insecure.or(base.rest_insecure),
+            rest_ca_cert: over.rest_ca_cert.or(base.rest_ca_cert),
+            mon_client: over.mon_client || base.mon_client,
+            ceph_conf: over.ceph_conf.or(base.ceph_conf),
+            keyring: over.keyring.or(base.keyring),
+            client_name: over.client_name.or(base.client_name),
+            mon_host: over.mon_host.or(base.mon_host),
+        }
+    }
+
+    fn redacted(self) -> SourceConfig {
+        SourceConfig {
+            rest_key: self.rest_key.map(|_| REDACTED.to_string()),
+            rest_token: self.rest_token.map(|_| REDACTED.to_string()),
+            ..self
+        }
+    }
+
+    pub fn data_source(&self) -> Result<DataSource> {
+        let cli = self.prefix_command.is_some() || !self.prefix_args.is_empty();
+        let backends = [cli, self.rest_url.is_some(), self.mon_client];
+        if backends.iter().filter(|&&chosen| chosen).count() > 1 {
+            return Err(
+                "prefix_command/prefix_args, rest_url and mon_client are mutually exclusive".into(),
+            );
+        }
+        if self.prefix_command.is_some() && !self.prefix_args.is_empty() {
+            return Err("prefix_command and prefix_args are mutually exclusive".into());
+        }
+
+        if self.mon_client {
+            return Ok(DataSource::Mon(MonClient::new(&MonClientConfig {
+                conf: self.ceph_conf.clone(),
+                keyring: self.keyring.clone(),
+                name: self
+                    .client_name
+                    .clone()
+                    .unwrap_or_else(|| DEFAULT_CLIENT_NAME.to_string()),
+                mon_host: self.mon_host.clone(),
+            })?));
+        }
+        if let Some(url) = &self.rest_url {
+            return Ok(DataSource::Rest(RestClient::new(&RestConfig {
+                url: url.clone(),
+                user: self.rest_user.clone(),
+                key: self.rest_key.clone(),
+                token: self.rest_token.clone(),
+                insecure: self.rest_insecure.unwrap_or(false),
+                ca_cert: self.rest_ca_cert.clone(),
+            })?));
+        }
+        match &self.prefix_command {
+            Some(command) => Ok(DataSource::Cli(
+                PrefixCommand::parse(command)
+                    .map_err(|e| format!("Invalid prefix command '{command}': {e}"))?,
+            )),
+            None => Ok(DataSource::Cli(PrefixCommand::from_args(
+                self.prefix_args.clone(),
+            ))),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct ClusterConfig {
+    pub name: String,
+    #[serde(flatten)]
+    pub source: SourceConfig,
+}
+
+/// One layer of settings: the top level of the config file, a profile, or the command line
+#[derive(Debug, Default, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Settings {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub interval: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timeout: Option<u64>,
+    /// Heartbeat ping time in milliseconds above which a network link is slow
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ping_threshold: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub color: Option<ColorMode>,
+    /// Colour theme: dark, light, high-contrast or color-blind
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub theme: Option<ThemeName>,
+    /// Use the mouse for clicks and scrolling (default: true)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mouse: Option<bool>,
+    /// Record samples in the history database (default: true)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub history: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub history_db: Option<PathBuf>,
+    /// File every event of the event view is appended to
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub event_log: Option<PathBuf>,
+    #[serde(flatten)]
+    pub source: SourceConfig,
+    #[serde(rename = "cluster", skip_serializing_if = "Vec::is_empty")]
+    pub clusters: Vec<ClusterConfig>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub alerts: Option<AlertSettings>,
+    /// Sort order and columns the OSD data movement table starts with
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub osd_movement: Option<OsdMovementSettings>,
+    /// Keys replacing the defaults of these actions
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub keys: BTreeMap<Action, Vec<KeyBinding>>,
+    /// Keys no other field took; serde cannot deny them next to the flattened source
+    #[serde(flatten, skip_serializing)]
+    pub unknown: BTreeMap<String, toml::Value>,
+}
+
+impl Settings {
+    /// Settings of `over` win. A backend replaces the clusters of `self` and
+    /// clusters replace its backend, so the result describes one mode.
+    pub fn merge(self, over: Settings) -> Settings {
+        let (source, clusters) = if !over.clusters.is_empty() {
+            (SourceConfig::default(), over.clusters)
+        } else if over.source.selects_backend() {
+            (self.source.merge(over.source), Vec::new())
+        } else {
+            (self.source.merge(over.source), self.clusters)
+        };
+
+        Settings {
+            interval: over.interval.or(self.interval),
+            timeout: over.timeout.or(self.timeout),
+            ping_threshold: over.ping_threshold.or(self.ping_threshold),
+            color: over.color.or(self.color),
+            theme: over.theme.or(self.theme),
+            mouse: over.mouse.or(self.mouse),
+            history: over.history.or(self.history),
+            history_db: over.history_db.or(self.history_db),
+            event_log: over.event_log.or(self.event_log),
+            source,
+            clusters,
+            alerts: match (self.alerts, over.alerts) {
+                (Some(alerts), Some(over)) => Some(alerts.merge(over)),
+                (alerts, over) => over.or(alerts),
+            },
+            osd_movement: match (self.osd_movement, over.osd_movement) {
+                (Some(table), Some(over)) => Some(table.merge(over)),
+                (table, over) => over.or(table),
+            },
+            keys: {
+                let mut keys = self.keys;
+                keys.extend(over.keys);
+                keys
+            },
+            unknown: BTreeMap::new(),
+        }
+    }
+
+    pub fn interval(&self) -> u64 {
+        self.interval.unwrap_or(DEFAULT_INTERVAL_SECS)
+    }
+
+    pub fn timeout(&self) -> u64 {
+        self.timeout.unwrap_or(DEFAULT_TIMEOUT_SECS)
+    }
+
+    pub fn ping_threshold(&self) -> f64 {
+        self.ping_threshold.unwrap_or(DEFAULT_PING_THRESHOLD_MS)
+    }
+
+    pub fn color(&self) -> ColorMode {
+        self.color.unwrap_or_default()
+    }
+
+    pub fn theme(&self) -> ThemeName {
+        self.theme.unwrap_or_default()
+    }
+
+    pub fn mouse(&self) -> bool {
+        self.mouse.unwrap_or(true)
+    }
+
+    /// The history database to use, None when the history is turned off
+    pub fn history_db(&self) -> Option<PathBuf> {
+        if self.history == Some(false) {
+            return None;
+        }
+        self.history_db.clone().or_else(default_history_path)
+    }
+
+    /// The alert settings, None when there is nowhere to send alerts
+    pub fn alerts(&self) -> Option<&AlertSettings> {
+        self.alerts
+            .as_ref()
+            .filter(|alerts| !alerts.sinks.is_empty())
+    }
+
+    /// The same settings with the built-in defaults filled in
+    pub fn with_defaults(self) -> Settings {
+        Settings {
+            interval: Some(self.interval()),
+            timeout: Some(self.timeout()),
+            ping_threshold: Some(self.ping_threshold()),
+            color: Some(self.color()),
+            theme: Some(self.theme()),
+            mouse: Some(self.mouse()),
+            history: Some(self.history.unwrap_or(true)),
+            history_db: self.history_db(),
+            alerts: self.alerts.map(AlertSettings::with_defaults),
+            osd_movement: Some(self.osd_movement.unwrap_or_default().with_defaults()),
+            ..self
+        }
+    }
+
+    /// The same settings with API keys and tokens replaced, for printing
+    pub fn redacted(self) -> Settings {
+        Settings {
+            source: self.source.redacted(),
+            clusters: self
+                .clusters
+                .into_iter()
+                .map(|cluster| ClusterConfig {
+                    name: cluster.name,
+                    source: cluster.source.redacted(),
+                })
+                .collect(),
+            alerts: self.alerts.map(|alerts| alerts.redacted(REDACTED)),
+            ..self
+        }
+    }
+
+    fn validate(&self) -> std::result::Result<(), String> {
+        if let Some(key) = self.unknown.keys().next() {
+            return Err(format!("unknown setting '{key}'"));
+        }
+        if !self.clusters.is_empty() && self.source.selects_backend() {
+            return Err(
+                "[[cluster]] entries and backend settings cannot be combined at the same level"
+                    .to_string(),
+            );
+        }
+        let mut names = HashSet::new();
+        for cluster in &self.clusters {
+            if cluster.name.trim().is_empty() {
+                return Err("cluster with an empty name".to_string());
+            }
+            if !names.insert(cluster.name.as_str()) {
+                return Err(format!("cluster '{}' is defined twice", cluster.name));
+            }
+        }
+        if let Some(alerts) = &self.alerts {
+            alerts.validate()?;
+        }
+        Ok(())
+    }
+}
+
+/// The config file: default settings at the top level plus named profiles
+#[derive(Debug, Default, Clone, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct ConfigFile {
+    #[serde(flatten)]
+    pub settings: Settings,
+    pub profile: BTreeMap<String, Settings>,
+}
+
+impl ConfigFile {
+    pub fn parse(text: &str) -> std::result::Result<Self, String> {
+        let file: ConfigFile = toml::from_str(text).map_err(|e| e.to_string())?;
+        file.settings.validate()?;
+        for (name, profile) in &file.profile {
+            profile
+                .validate()
+                .map_err(|e| format!("profile '{name}': {e}"))?;
+        }
+        Ok(file)
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| format!("Cannot read {}: {e}", path.display()))?;
+        Ok(Self::parse(&text).map_err(|e| format!("{}: {e}", path.display()))?)
+    }
+
+    /// The top-level settings, overridden by the given profile
+    pub fn settings(&self, profile: Option<&str>) -> std::result::Result<Settings, String> {
+        let Some(name) = profile else {
+            return Ok(self.settings.clone());
+        };
+        match self.profile.get(name) {
+            Some(profile) => Ok(self.settings.clone().merge(profile.clone())),
+            None if self.profile.is_empty() => Err(format!("Unknown profile '{name}'")),
+            None => Err(format!(
+                "Unknown profile '{name}', available: {}",
+                self.profile.keys().cloned().collect::<Vec<_>>().join(", ")
+            )),
+        }
+    }
+}
+
+/// `$XDG_CONFIG_HOME/ceph-doctor/config.toml`, falling back to `~/.config`
+pub fn default_config_path() -> Option<PathBuf> {
+    let config_home = env::var_os("XDG_CONFIG_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+    Some(config_home.join("ceph-doctor").join("config.toml"))
+}
+
+/// `$XDG_DATA_HOME/ceph-doctor/history.db`, falling back to `~/.local/share`
+pub fn default_history_path() -> Option<PathBuf> {
+    let data_home = env::var_os("XDG_DATA_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/share")))?;
+    Some(data_home.join("ceph-doctor").join("history.db"))
+}
+
+pub fn load_clusters(path: &Path) -> Result<Vec<ClusterConfig>> {
+    let text = std::fs::read_to_string(path)
+        .map_err(|e| format!("Cannot read {}: {e}", path.display()))?;
+    Ok(parse_clusters(&text).map_err(|e| format!("{}: {e}", path.display()))?)
+}
+
+/// The top-level `[[cluster]]` tables of a config file; names must be present and unique
+pub fn parse_clusters(text: &str) -> std::result::Result<Vec<ClusterConfig>, String> {
+    let clusters = ConfigFile::parse(text)?.settings.clusters;
+    if clusters.is_empty() {
+        return Err("no [[cluster]] entries".to_string());
+    }
+    Ok(clusters)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::monitor::state::{OsdMovementColumn, OsdMovementSortColumn, OsdMovementTable};
+
+    #[test]
+    fn test_parse_clusters() {
+        let clusters = parse_clusters(
+            r#"
+            [[cluster]]
+            name = "prod"
+            prefix_command = "ssh prod-admin sudo"
+
+            [[cluster]]
+            name = "lab"
+            rest_url = "https://lab-mgr:8003"
+            rest_token = "secret"
+            rest_insecure = true
+
+            [[cluster]]
+            name = "local"
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(clusters.len(), 3);
+        assert_eq!(clusters[0].name, "prod");
+        assert_eq!(
+            clusters[0].source.prefix_command.as_deref(),
+            Some("ssh prod-admin sudo")
+        );
+        assert_eq!(
+            clusters[1].source.rest_url.as_deref(),
+            Some("https://lab-mgr:8003")
+        );
+        assert_eq!(clusters[1].source.rest_insecure, Some(true));
+        assert_eq!(clusters[2].source, SourceConfig::default());
+
+        assert!(matches!(
+            clusters[0].source.data_source().unwrap(),
+            DataSource::Cli(_)
+        ));
+        assert!(matches!(
+            clusters[1].source.data_source().unwrap(),
+            DataSource::Rest(_)
+        ));
+    }
+
+    #[test]
+    fn test_parse_clusters_errors() {
+        assert!(parse_clusters("").is_err());
+        assert!(parse_clusters("[[cluster]]\nprefix_command = \"sudo\"").is_err());
+        assert!(
+            parse_clusters("[[cluster]]\nname = \"a\"\n[[cluster]]\nname = \"a\"")
+                .unwrap_err()
+                .contains("defined twice")
+        );
+    }
+
+    #[test]
+    fn test_conflicting_backends() {
+        let source = SourceConfig {
+            prefix_command: Some("sudo".to_string()),
+            rest_url: Some("https://mgr:8003".to_string()),
+            ..Default::default()
+        };
+        assert!(source.data_source().is_err());
+    }
+
+    #[test]
+    fn test_profiles_override_top_level() {
+        let file = ConfigFile::parse(
+            r#"
+            interval = 10
+            prefix_command = "ssh admin sudo"
+            rest_user = "doctor"
+
+            [profile.lab]
+            rest_url = "https://lab-mgr:8003"
+            color = "never"
+            theme = "high-contrast"
+
+            [profile.fleet]
+            [[profile.fleet.cluster]]
+            name = "prod"
+            "#,
+        )
+        .unwrap();
+
+        let top = file.settings(None).unwrap();
+        assert_eq!(top.interval(), 10);
+        assert_eq!(top.timeout(), DEFAULT_TIMEOUT_SECS);
+        assert_eq!(top.color(), ColorMode::Auto);
+        assert_eq!(top.theme(), ThemeName::Dark);
+
+        // The profile's backend replaces the prefix command, other settings are kept
+        let lab = file.settings(Some("lab")).unwrap();
+        assert_eq!(lab.interval(), 10);
+        assert_eq!(lab.color(), ColorMode::Never);
+        assert_eq!(lab.theme(), ThemeName::HighContrast);
+        assert_eq!(lab.source.prefix_command, None);
+        assert_eq!(lab.source.rest_url.as_deref(), Some("https://lab-mgr:8003"));
+        assert_eq!(lab.source.rest_user.as_deref(), Some("doctor"));
+
+        let fleet = file.settings(Some("fleet")).unwrap();
+        assert_eq!(fleet.clusters.len(), 1);
+        assert!(!fleet.source.selects_backend());
+
+        assert!(file
+            .settings(Some("nope"))
+            .unwrap_err()
+            .contains("available: fleet, lab"));
+    }
+
+    #[test]
+    fn test_command_line_overrides_config() {
+        let config = Settings {
+            interval: Some(10),
+            clusters: vec![ClusterConfig {
+                name: "prod".to_string(),
+                source: SourceConfig::default(),
+            }],
+            ..Default::default()
+        };
+        let cli = Settings {
+            interval: Some(2),
+            source: SourceConfig {
+                rest_url: Some("https://mgr:8003".to_string()),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        let merged = config.merge(cli);
+        assert_eq!(merged.interval(), 2);
+        assert!(merged.clusters.is_empty());
+        assert_eq!(merged.source.rest_url.as_deref(), Some("https://mgr:8003"));
+    }
+
+    #[test]
+    fn test_clusters_and_backend_at_the_same_level() {
+        let error =
+            ConfigFile::parse("prefix_command = \"sudo\"\n[[cluster]]\nname = \"a\"").unwrap_err();
+        assert!(error.contains("cannot be combined"));
+    }
+
+    #[test]
+    fn test_unknown_settings() {
+        let error = ConfigFile::parse("intervall = 10").unwrap_err();
+        assert!(error.contains("unknown setting 'intervall'"));
+        let error = ConfigFile::parse("[profile.lab]\nrest_insecur = true").unwrap_err();
+        assert!(error.contains("profile 'lab': unknown setting 'rest_insecur'"));
+    }
+
+    #[test]
+    fn test_profile_turns_rest_insecure_off() {
+        let file = ConfigFile::parse(
+            "rest_url = \"https://mgr:8003\"\nrest_insecure = true\n[profile.prod]\nrest_insecure = false",
+        )
+        .unwrap();
+        assert_eq!(
+            file.settings(None).unwrap().source.rest_insecure,
+            Some(true)
+        );
+        let prod = file.settings(Some("prod")).unwrap();
+        assert_eq!(prod.source.rest_insecure, Some(false));
+        assert_eq!(
+            prod.merge(Settings::default()).source.rest_insecure,
+            Some(false)
+        );
+    }
+
+    #[test]
+    fn test_redacted() {
+        let settings = Settings {
+            source: SourceConfig {
+                rest_key: Some("secret".to_string()),
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+        .redacted();
+        assert_eq!(settings.source.rest_key.as_deref(), Some(REDACTED));
+        assert_eq!(settings.source.rest_token, None);
+    }
+
+    #[test]
+    fn test_history_db() {
+        let disabled = Settings {
+            history: Some(false),
+            history_db: Some(PathBuf::from("/tmp/history.db")),
+            ..Default::default()
+        };
+        assert_eq!(disabled.history_db(), None);
+
+        let enabled = disabled.merge(Settings {
+            history: Some(true),
+            ..Default::default()
+        });
+        assert_eq!(enabled.history_db(), Some(PathBuf::from("/tmp/history.db")));
+    }
+
+    #[test]
+    fn test_mouse() {
+        let file = ConfigFile::parse("mouse = false").unwrap();
+        let settings = file.settings(None).unwrap();
+        assert!(!settings.mouse());
+        assert!(Settings::default().mouse());
+    }
+
+    #[test]
+    fn test_osd_movement() {
+        let file = ConfigFile::parse(
+            r#"
+            [osd_movement]
+            sort = "excess"
+            columns = ["host", "excess_active", "excess_bytes"]
+
+            [profile.asc.osd_movement]
+            descending = false
+            "#,
+        )
+        .unwrap();
+        let settings = file.settings(Some("asc")).unwrap();
+        let table = settings.osd_movement.unwrap();
+        assert_eq!(table.sort, Some(OsdMovementSortColumn::Excess));
+        assert_eq!(table.descending, Some(false));
+        assert_eq!(table.columns.as_ref().unwrap().len(), 3);
+
+        // The OSD column is always shown, the others in table order
+        let table = OsdMovementTable::new(&table);
+        assert_eq!(
+            table.columns,
+            [
+                OsdMovementColumn::Osd,
+                OsdMovementColumn::Host,
+                OsdMovementColumn::ExcessActive,
+                OsdMovementColumn::ExcessBytes
+            ]
+        );
+
+        assert!(ConfigFile::parse("[osd_movement]\ncolumns = [\"nope\"]").is_err());
+        let shown = toml::to_string(&Settings::default().with_defaults()).unwrap();
+        assert!(shown.contains("sort = \"missing\""));
+    }
+
+    #[test]
+    fn test_keys() {
+        let file = ConfigFile::parse(
+            r#"
+            [keys]
+            pause = ["space"]
+            quit = ["q"]
+
+            [profile.vim.keys]
+            quit = ["ctrl+d"]
+            "#,
+        )
+        .unwrap();
+        let settings = file.settings(Some("vim")).unwrap();
+        assert_eq!(settings.keys[&Action::Pause], ["space".parse().unwrap()]);
+        assert_eq!(settings.keys[&Action::Quit], ["ctrl+d".parse().unwrap()]);
+
+        let shown = toml::to_string(&settings).unwrap();
+        assert!(shown.contains("pause = [\"Space\"]"));
+        assert!(ConfigFile::parse(&shown).is_ok());
+
+        assert!(ConfigFile::parse("[keys]\npause = [\"hyper\"]").is_err());
+        assert!(ConfigFile::parse("[keys]\nfly = [\"x\"]").is_err());
+    }
+
+    #[test]
+    fn test_alerts() {
+        let file = ConfigFile::parse(
+            r#"
+            [alerts]
+            stall_minutes = 30
+            [[alerts.sink]]
+            type = "webhook"
+            url = "https://alerts.example.com/hook"
+
+            [profile.quiet.alerts]
+            max_per_hour = 2
+            "#,
+        )
+        .unwrap();
+        let settings = file.settings(Some("quiet")).unwrap();
+        let alerts = settings.alerts().unwrap();
+        assert_eq!((alerts.stall_minutes(), alerts.max_per_hour()), (30, 2));
+        assert_eq!(alerts.sinks.len(), 1);
+
+        let shown = toml::to_string(&settings.with_defaults().redacted()).unwrap();
+        assert!(shown.contains("[[alerts.sink]]"));
+        assert!(!shown.contains("alerts.example.com"));
+
+        assert_eq!(Settings::default().alerts(), None);
+        assert!(ConfigFile::parse("[[alerts.sink]]\ntype = \"command\"\ncommand = \"\"").is_err());
+    }
+}
@@ -0,0 +1,360 @@
+use std::fmt;
+use std::time::Duration;
+
+/// Errors that can occur while fetching data from the cluster.
+#[derive(Debug)]
+pub enum CephError {
+    /// The command ran but exited with a non-zero status
+    CommandFailed {
+        command: String,
+        exit_code: i32,
+        stdout: String,
+        stderr: String,
+    },
+    /// The command could not be started at all
+    Spawn {
+        command: String,
+        source: std::io::Error,
+    },
+    /// The command did not finish in time and was killed
+    Timeout { command: String, timeout: Duration },
+    /// The command output is not the JSON we expected
+    Json {
+        command: String,
+        line: usize,
+        column: usize,
+        message: String,
+        context: String,
+        stderr: String,
+    },
+    /// The command output is not valid UTF-8
+    Utf8 {
+        command: String,
+        source: std::string::FromUtf8Error,
+    },
+    /// An HTTP request to the REST API failed, or returned an error status
+    Http {
+        url: String,
+        status: Option<u16>,
+        message: String,
+    },
+    /// Talking to a monitor with the native client failed
+    Mon {
+        addr: String,
+        class: ErrorClass,
+        message: String,
+    },
+}
+
+/// Rough classification of an error, used to give the user a hint on what to fix.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorClass {
+    AuthFailure,
+    /// The prefix command (ssh and the like) could not reach the host that runs ceph
+    HostUnreachable,
+    MonUnreachable,
+    MgrUnreachable,
+    PermissionDenied,
+    CommandNotFound,
+    Timeout,
+    InvalidOutput,
+    Unknown,
+}
+
+impl ErrorClass {
+    pub fn label(self) -> &'static str {
+        match self {
+            ErrorClass::AuthFailure => "Authentication failure",
+            ErrorClass::HostUnreachable => "Host unreachable",
+            ErrorClass::MonUnreachable => "Monitor unreachable",
+            ErrorClass::MgrUnreachable => "Manager unreachable",
+            ErrorClass::PermissionDenied => "Permission denied",
+            ErrorClass::CommandNotFound => "Command not found",
+            ErrorClass::Timeout => "Timeout",
+            ErrorClass::InvalidOutput => "Invalid output",
+            ErrorClass::Unknown => "Command failed",
+        }
+    }
+
+    pub fn hint(self) -> &'static str {
+        match self {
+            ErrorClass::AuthFailure => {
+                "The cluster rejected our credentials. Check the keyring and the client name (--id/--name) used by the ceph command."
+            }
+            ErrorClass::HostUnreachable => {
+                "The prefix command could not reach the host that runs ceph. Check the host name, DNS and the SSH connection by running the prefix command on its own."
+            }
+            ErrorClass::MonUnreachable => {
+                "No monitor could be reached. Check mon_host in ceph.conf, the network and whether the monitors are running."
+            }
+            ErrorClass::MgrUnreachable => {
+                "The REST API did not answer. Check the URL and that the restful module is enabled (ceph mgr module enable restful)."
+            }
+            ErrorClass::PermissionDenied => {
+                "The ceph command is not allowed to read its configuration or keyring. Try running it with sudo."
+            }
+            ErrorClass::CommandNotFound => {
+                "The ceph command (or the prefix command) was not found. Check the PATH on the host that runs it."
+            }
+            ErrorClass::Timeout => {
+                "The command did not answer in time. The cluster may be overloaded, or the connection is hanging."
+            }
+            ErrorClass::InvalidOutput => {
+                "The command ran, but its output could not be understood. Check for warnings printed to stdout."
+            }
+            ErrorClass::Unknown => {
+                "This error suggests a problem with your Ceph configuration or connectivity."
+            }
+        }
+    }
+}
+
+impl CephError {
+    pub fn command(&self) -> &str {
+        match self {
+            CephError::CommandFailed { command, .. }
+            | CephError::Spawn { command, .. }
+            | CephError::Timeout { command, .. }
+            | CephError::Json { command, .. }
+            | CephError::Utf8 { command, .. } => command,
+            CephError::Http { url, .. } => url,
+            CephError::Mon { addr, .. } => addr,
+        }
+    }
+
+    pub fn class(&self) -> ErrorClass {
+        match self {
+            CephError::CommandFailed {
+                exit_code, stderr, ..
+            } => classify_command_failure(*exit_code, stderr),
+            CephError::Spawn { source, .. } => match source.kind() {
+                std::io::ErrorKind::NotFound => ErrorClass::CommandNotFound,
+                std::io::ErrorKind::PermissionDenied => ErrorClass::PermissionDenied,
+                _ => ErrorClass::Unknown,
+            },
+            CephError::Timeout { .. } => ErrorClass::Timeout,
+            CephError::Json { .. } | CephError::Utf8 { .. } => ErrorClass::InvalidOutput,
+            CephError::Http { status, .. } => match status {
+                Some(401 | 403) => ErrorClass::AuthFailure,
+                Some(_) => ErrorClass::Unknown,
+                None => ErrorClass::MgrUnreachable,
+            },
+            CephError::Mon { class, .. } => *class,
+        }
+    }
+
+    /// Build a JSON error, keeping the offending line of the output for context
+    pub fn json(command: &str, error: &serde_json::Error, output: &str, stderr: &str) -> Self {
+        CephError::Json {
+            command: command.to_string(),
+            line: error.line(),
+            column: error.column(),
+            message: error.to_string(),
+            context: json_error_context(output, error.line(), error.column()),
+            stderr: stderr.trim().to_string(),
+        }
+    }
+}
+
+impl fmt::Display for CephError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CephError::CommandFailed {
+                command,
+                exit_code,
+                stderr,
+                ..
+            } => {
+                write!(f, "Command failed with exit code {exit_code}: {command}")?;
+                if !stderr.trim().is_empty() {
+                    write!(f, "\n\n{}", stderr.trim())?;
+                }
+                Ok(())
+            }
+            CephError::Spawn { command, source } => {
+                write!(f, "Failed to start command: {command}: {source}")
+            }
+            CephError::Timeout { command, timeout } => write!(
+                f,
+                "Command timed out after {}s and was killed: {command}",
+                timeout.as_secs()
+            ),
+            CephError::Json {
+                command,
+                line,
+                column,
+                message,
+                context,
+                stderr,
+            } => {
+                write!(
+                    f,
+                    "Failed to parse output of '{command}' as JSON at line {line}, column {column}: {message}"
+                )?;
+                if !context.is_empty() {
+                    write!(f, "\n\n{context}")?;
+                }
+                if !stderr.is_empty() {
+                    write!(f, "\n\nStderr output:\n{stderr}")?;
+                }
+                Ok(())
+            }
+            CephError::Utf8 { command, source } => {
+                write!(f, "Output of '{command}' is not valid UTF-8: {source}")
+            }
+            CephError::Http {
+                url,
+                status: Some(status),
+                message,
+            } => write!(
+                f,
+                "HTTP request to {url} failed with status {status}: {message}"
+            ),
+            CephError::Http {
+                url,
+                status: None,
+                message,
+            } => write!(f, "HTTP request to {url} failed: {message}"),
+            CephError::Mon { addr, message, .. } => write!(f, "Monitor {addr}: {message}"),
+        }
+    }
+}
+
+impl std::error::Error for CephError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            CephError::Spawn { source, .. } => Some(source),
+            CephError::Utf8 { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+fn classify_command_failure(exit_code: i32, stderr: &str) -> ErrorClass {
+    let stderr = stderr.to_lowercase();
+    let contains_any = |patterns: &[&str]| patterns.iter().any(|p| stderr.contains(p));
+
+    // ssh exits with 255 on its own errors, and prefixes its messages with
+    // "ssh:"; a failed name lookup is always about the host in the prefix
+    if (exit_code == 255 && stderr.contains("ssh"))
+        || contains_any(&[
+            "could not resolve hostname",
+            "name or service not known",
+            "temporary failure in name resolution",
+        ])
+    {
+        ErrorClass::HostUnreachable
+    } else if exit_code == 127 || contains_any(&["command not found", "no such file or directory"])
+    {
+        ErrorClass::CommandNotFound
+    } else if contains_any(&[
+        "authenticationerror",
+        "auth_bad_method",
+        "unable to find a keyring",
+        "keyring not found",
+        "failed to fetch mon config",
+        "operation not permitted",
+    ]) {
+        ErrorClass::AuthFailure
+    } else if contains_any(&["permission denied", "sudo:"]) {
+        ErrorClass::PermissionDenied
+    } else if contains_any(&[
+        "timed out",
+        "connection refused",
+        "no route to host",
+        "error connecting to the cluster",
+        "monclient(hunting)",
+        "unable to get monitor info",
+    ]) {
+        ErrorClass::MonUnreachable
+    } else {
+        ErrorClass::Unknown
+    }
+}
+
+/// Show the line the JSON parser choked on, clipped around the column, with a caret below
+fn json_error_context(output: &str, line: usize, column: usize) -> String {
+    const WIDTH: usize = 60;
+
+    let Some(text) = line.checked_sub(1).and_then(|i| output.lines().nth(i)) else {
+        return String::new();
+    };
+    let chars: Vec<char> = text.chars().collect();
+    let column = column.saturating_sub(1).min(chars.len());
+    let start = column.saturating_sub(WIDTH / 2);
+    let end = (start + WIDTH).min(chars.len());
+    let excerpt: String = chars[start..end].iter().collect();
+
+    format!("{excerpt}\n{}^", " ".repeat(column - start))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classify_command_failure() {
+        assert_eq!(
+            classify_command_failure(
+                1,
+                "[errno 13] RADOS permission denied (error connecting to the cluster)\nAuthenticationError"
+            ),
+            ErrorClass::AuthFailure
+        );
+        assert_eq!(
+            classify_command_failure(
+                1,
+                "monclient(hunting): authenticate timed out after 300\n[errno 110] RADOS timed out (error connecting to the cluster)"
+            ),
+            ErrorClass::MonUnreachable
+        );
+        assert_eq!(
+            classify_command_failure(
+                255,
+                "ssh: Could not resolve hostname ceph-host: Name or service not known"
+            ),
+            ErrorClass::HostUnreachable
+        );
+        assert_eq!(
+            classify_command_failure(
+                255,
+                "ssh: connect to host ceph-host port 22: Connection refused"
+            ),
+            ErrorClass::HostUnreachable
+        );
+        assert_eq!(
+            classify_command_failure(
+                1,
+                "Unable to connect to the server: dial tcp: lookup k8s-api: Temporary failure in name resolution"
+            ),
+            ErrorClass::HostUnreachable
+        );
+        assert_eq!(
+            classify_command_failure(127, "bash: ceph: command not found"),
+            ErrorClass::CommandNotFound
+        );
+        assert_eq!(classify_command_failure(1, "boom"), ErrorClass::Unknown);
+    }
+
+    #[test]
+    fn test_json_error_keeps_position_and_context() {
+        let output = "{\n  \"pg_map\": oops\n}";
+        let error = serde_json::from_str::<serde_json::Value>(output).unwrap_err();
+        let ceph_error = CephError::json("ceph pg dump", &error, output, "");
+
+        match &ceph_error {
+            CephError::Json {
+                line,
+                column,
+                context,
+                ..
+            } => {
+                assert_eq!(*line, 2);
+                assert_eq!(*column, 13);
+                assert_eq!(context, "  \"pg_map\": oops\n            ^");
+            }
+            _ => panic!("expected a JSON error"),
+        }
+        assert_eq!(ceph_error.class(), ErrorClass::InvalidOutput);
+    }
+}
@@ -1,4 +1,7 @@
+pub mod config;
+pub mod error;
 pub mod monitor;
+pub mod report;
 
 pub type Result<T> = std::result::Result<T, Box<dyn std::error::Error + Send + Sync>>;
 
@@ -382,6 +385,68 @@ pub mod common {
         pub fifteen_min: f64,
     }
 
+    /// The parts of `ceph status` we use
+    #[derive(Debug, Default, Deserialize, Serialize)]
+    pub struct CephStatus {
+        pub fsid: String,
+        pub health: CephHealth,
+        #[serde(default)]
+        pub pgmap: StatusPgMap,
+    }
+
+    #[derive(Debug, Default, Deserialize, Serialize)]
+    pub struct CephHealth {
+        pub status: String,
+        #[serde(default)]
+        pub checks: std::collections::BTreeMap<String, HealthCheck>,
+    }
+
+    #[derive(Debug, Default, Clone, Deserialize, Serialize)]
+    pub struct HealthCheck {
+        pub severity: String,
+        #[serde(default)]
+        pub summary: HealthCheckSummary,
+    }
+
+    #[derive(Debug, Default, Clone, Deserialize, Serialize)]
+    pub struct HealthCheckSummary {
+        #[serde(default)]
+        pub message: String,
+    }
+
+    #[derive(Debug, Default, Deserialize, Serialize)]
+    pub struct StatusPgMap {
+        #[serde(default)]
+        pub num_pgs: u64,
+        #[serde(default)]
+        pub degraded_objects: u64,
+        #[serde(default)]
+        pub misplaced_objects: u64,
+        #[serde(default)]
+        pub recovering_bytes_per_sec: u64,
+    }
+
+    #[derive(Debug, Default, Deserialize, Serialize)]
+    pub struct CephOsdTree {
+        pub nodes: Vec<OsdTreeNode>,
+        #[serde(default)]
+        pub stray: Vec<OsdTreeNode>,
+    }
+
+    #[derive(Debug, Deserialize, Serialize)]
+    pub struct OsdTreeNode {
+        pub id: i64,
+        pub name: String,
+        #[serde(rename = "type")]
+        pub node_type: String,
+        #[serde(default)]
+        pub children: Vec<i64>,
+        #[serde(default)]
+        pub status: Option<String>,
+        #[serde(default)]
+        pub device_class: Option<String>,
+    }
+
     #[derive(Debug, Default, Deserialize, Serialize)]
     pub struct OpQueueAgeHist {
         pub histogram: Vec<serde_json::Value>,
@@ -407,6 +472,8 @@ pub mod common {
         pub excess_objects_waiting: i64,  // Objects waiting to be moved (backfill_wait)
         pub missing_objects_active: i64,  // Objects actively being moved (backfilling)
         pub excess_objects_active: i64,   // Objects actively being moved (backfilling)
+        pub missing_bytes: i64,           // Estimated from the average object size of each PG
+        pub excess_bytes: i64,
         pub incoming_predicted_time_secs: Option<u64>,
         pub outgoing_predicted_time_secs: Option<u64>,
         pub missing_objects_history: Vec<i64>, // Historical missing objects counts
@@ -427,4 +494,90 @@ pub mod common {
         pub scrub_rate: Option<f64>,    // Objects per second
         pub eta_seconds: Option<u64>,   // Estimated seconds to completion
     }
+
+    /// One row of the recovery progress panel
+    #[derive(Debug, Default, Clone, PartialEq)]
+    pub struct RecoveryProgress {
+        pub category: &'static str,
+        pub objects: i64,
+        pub object_rate: f64, // Objects per second, negative while recovering
+        pub data_rate: f64,   // Estimated bytes per second
+        pub eta_seconds: Option<u64>, // None until the count is going down
+    }
+
+    /// One row of the PG browser
+    #[derive(Debug, Default, Clone, PartialEq)]
+    pub struct PgListEntry {
+        pub pgid: String,
+        pub pool: u64,
+        pub state: String,
+        pub up: Vec<u32>,
+        pub acting: Vec<u32>,
+        pub primary: u32, // Acting primary
+        pub objects: i64,
+        pub misplaced: i64,
+        pub degraded: i64,
+        pub state_secs: Option<u64>, // Since the last state change; None if unknown
+    }
+
+    #[derive(Debug, Default, Clone)]
+    pub struct OsdOverview {
+        pub osd_id: u32,
+        pub utilization: f64, // Percent of raw capacity in use
+        pub total_bytes: u64,
+        pub used_bytes: u64,
+        pub num_pgs: u64,
+        pub commit_latency_ms: f64,
+        pub apply_latency_ms: f64,
+        pub snap_trim_queue_len: u64,
+        pub num_shards_repaired: u64,
+        pub alerts: Vec<String>,
+        pub utilization_outlier: bool, // Well above the cluster median
+        pub pgs_outlier: bool,
+        pub commit_latency_outlier: bool,
+        pub apply_latency_outlier: bool,
+    }
+
+    #[derive(Debug, Default, Clone)]
+    pub struct OsdOverviewMedians {
+        pub utilization: f64,
+        pub num_pgs: f64,
+        pub commit_latency_ms: f64,
+        pub apply_latency_ms: f64,
+    }
+
+    #[derive(Debug, Default, Clone)]
+    pub struct SlowLink {
+        pub from_osd: u32,
+        pub to_osd: u32,
+        pub from_host: Option<String>,
+        pub to_host: Option<String>,
+        pub interface: String,
+        pub one_min_ms: f64,
+        pub five_min_ms: f64,
+        pub fifteen_min_ms: f64,
+    }
+
+    #[derive(Debug, Default, Clone)]
+    pub struct HostLinkSummary {
+        pub from_host: String,
+        pub to_host: String,
+        pub slow_links: usize,
+        pub worst_ms: f64,
+        pub average_ms: f64,
+    }
+
+    /// One row of the multi-cluster summary
+    #[derive(Debug, Default, Clone)]
+    pub struct ClusterSummary {
+        pub name: String,
+        pub health: Option<String>, // From `ceph status`; None until it was fetched
+        pub degraded_objects: i64,
+        pub misplaced_objects: i64,
+        pub eta_seconds: Option<u64>,
+        pub inconsistent_pgs: usize,
+        pub has_data: bool,
+        pub error: Option<String>,
+        pub fetch_elapsed: Option<std::time::Duration>,
+    }
 }
@@ -1,13 +1,50 @@
+use ceph_doctor::config::{
+    default_config_path, default_history_path, load_clusters, ColorMode, ConfigFile, Settings,
+    SourceConfig,
+};
+use ceph_doctor::monitor::alerts::{AlertSettings, SinkConfig, SinkTarget};
+use ceph_doctor::monitor::history::{history_report, HistoryStore, RETENTION_DAYS};
+use ceph_doctor::monitor::keymap::Keymap;
+use ceph_doctor::monitor::multi::{Cluster, MultiMonitorOptions};
+use ceph_doctor::monitor::ui::{Theme, ThemeName};
+use ceph_doctor::report::{Report, ReportFormat, Snapshots};
 use ceph_doctor::Result;
-use clap::{CommandFactory, Parser, Subcommand};
+use clap::{Args, CommandFactory, Parser, Subcommand};
+use std::path::PathBuf;
 
 #[derive(Parser)]
 #[command(author, version, about, long_about = None)]
 #[command(after_help = "EXAMPLES:
     ceph-doctor monitor                           Monitor cluster with default 5s interval
     ceph-doctor monitor --interval 10            Monitor with 10s interval
-    ceph-doctor monitor --prefix-command 'ssh host sudo'  Monitor remote cluster")]
+    ceph-doctor monitor --prefix-command 'ssh host sudo'  Monitor remote cluster
+    ceph-doctor monitor --prefix-command 'cephadm shell -- {}'  Run ceph inside a cephadm shell
+    ceph-doctor monitor --rest-url https://mgr:8003 --rest-user doctor --rest-key KEY  Use the mgr restful API
+    ceph-doctor monitor --mon-client --ceph-conf ./ceph.conf --keyring ./ceph.client.admin.keyring  No ceph CLI needed
+    ceph-doctor monitor --clusters clusters.toml  Summary of several clusters
+    ceph-doctor monitor --plain >> recovery.log   One status line per interval, no full-screen view
+    ceph-doctor --profile prod monitor            Use the [profile.prod] settings from the config file
+    ceph-doctor monitor --alert-slack https://hooks.slack.com/services/...  Post events to Slack
+    ceph-doctor config show                       Print the effective settings
+    ceph-doctor history                           List past recoveries of the monitored clusters
+    ceph-doctor report --from dumps/ -o report.html  Post-mortem report from saved pg dumps")]
 struct Cli {
+    #[arg(
+        long,
+        global = true,
+        value_name = "PATH",
+        env = "CEPH_DOCTOR_CONFIG",
+        help = "Config file [default: ~/.config/ceph-doctor/config.toml]"
+    )]
+    config: Option<PathBuf>,
+    #[arg(
+        long,
+        global = true,
+        value_name = "NAME",
+        env = "CEPH_DOCTOR_PROFILE",
+        help = "Profile from the config file to apply on top of its top-level settings"
+    )]
+    profile: Option<String>,
     #[command(subcommand)]
     command: Option<Commands>,
 }
@@ -15,15 +52,344 @@ struct Cli {
 #[derive(Subcommand)]
 enum Commands {
     /// Monitor Ceph cluster health and recovery progress in real-time
-    Monitor {
-        #[arg(long, default_value = "5", help = "Update interval in seconds")]
-        interval: u64,
-        #[arg(
-            long,
-            help = "Command prefix for remote execution (e.g., 'ssh host sudo' or 'kubectl exec pod --')"
-        )]
-        prefix_command: Option<String>,
+    Monitor(MonitorArgs),
+    /// Inspect the configuration
+    Config {
+        #[command(subcommand)]
+        command: ConfigCommands,
     },
+    /// List past recoveries recorded in the history database
+    History(HistoryArgs),
+    /// Write a post-mortem report of a recovery from saved pg dump JSON files
+    Report(ReportArgs),
+}
+
+#[derive(Args)]
+struct ReportArgs {
+    #[arg(
+        long,
+        value_name = "DIR",
+        help = "Directory of timestamped 'ceph pg dump --format json' files, optionally with 'ceph status --format json' files for the health checks"
+    )]
+    from: PathBuf,
+    #[arg(
+        long,
+        value_enum,
+        help = "Report format [default: html for .html/.htm output files, else markdown]"
+    )]
+    format: Option<ReportFormat>,
+    #[arg(
+        short,
+        long,
+        value_name = "FILE",
+        help = "Write the report to FILE instead of standard output"
+    )]
+    output: Option<PathBuf>,
+}
+
+/// Longest period `history --days` accepts, about a hundred years
+const MAX_HISTORY_DAYS: u64 = 36_500;
+
+#[derive(Args)]
+struct HistoryArgs {
+    #[arg(
+        long,
+        value_name = "PATH",
+        help = "History database [default: ~/.local/share/ceph-doctor/history.db]"
+    )]
+    history_db: Option<PathBuf>,
+    #[arg(long, help = "Only show the cluster with this fsid")]
+    fsid: Option<String>,
+    #[arg(
+        long,
+        default_value_t = RETENTION_DAYS as u64,
+        value_parser = clap::value_parser!(u64).range(..=MAX_HISTORY_DAYS),
+        help = "Only show recoveries of the last DAYS days"
+    )]
+    days: u64,
+}
+
+#[derive(Subcommand)]
+enum ConfigCommands {
+    /// Print the effective settings: defaults, config file, profile, environment and options
+    Show(MonitorArgs),
+}
+
+/// Options of `monitor`; settings left out come from the config file or the defaults
+#[derive(Args)]
+struct MonitorArgs {
+    #[arg(
+        long,
+        env = "CEPH_DOCTOR_INTERVAL",
+        help = "Update interval in seconds [default: 5]"
+    )]
+    interval: Option<u64>,
+    #[arg(
+        long,
+        help = "Command prefix for remote execution, split with shell quoting rules (e.g., 'ssh host sudo' or 'cephadm shell -- {}'); '{}' marks where the ceph command goes"
+    )]
+    prefix_command: Option<String>,
+    #[arg(
+        long = "prefix-arg",
+        value_name = "ARG",
+        conflicts_with = "prefix_command",
+        allow_hyphen_values = true,
+        help = "Command prefix given one argument at a time (repeat for each argument)"
+    )]
+    prefix_args: Vec<String>,
+    #[arg(
+        long,
+        value_name = "URL",
+        conflicts_with_all = ["prefix_command", "prefix_args"],
+        help = "Fetch data from the ceph-mgr restful module at this URL instead of running the ceph command (e.g., https://mgr-host:8003)"
+    )]
+    rest_url: Option<String>,
+    #[arg(long, value_name = "USER", help = "User name for the restful API key")]
+    rest_user: Option<String>,
+    #[arg(
+        long,
+        value_name = "KEY",
+        env = "CEPH_DOCTOR_REST_KEY",
+        hide_env_values = true,
+        help = "API key for the restful module, as printed by 'ceph restful create-key <user>'"
+    )]
+    rest_key: Option<String>,
+    #[arg(
+        long,
+        value_name = "TOKEN",
+        env = "CEPH_DOCTOR_REST_TOKEN",
+        hide_env_values = true,
+        help = "Bearer token for the REST API, used instead of user and key"
+    )]
+    rest_token: Option<String>,
+    #[arg(
+        long,
+        help = "Accept invalid or self-signed TLS certificates from the REST API"
+    )]
+    rest_insecure: bool,
+    #[arg(
+        long,
+        value_name = "PEM",
+        help = "Additional CA certificate to trust for the REST API"
+    )]
+    rest_ca_cert: Option<PathBuf>,
+    #[arg(
+        long,
+        conflicts_with_all = ["prefix_command", "prefix_args", "rest_url"],
+        help = "Talk to the monitors directly (msgr2 with cephx) instead of running the ceph command"
+    )]
+    mon_client: bool,
+    #[arg(
+        long,
+        value_name = "PATH",
+        help = "ceph.conf with mon_host, fsid and keyring for --mon-client [default: /etc/ceph/ceph.conf]"
+    )]
+    ceph_conf: Option<PathBuf>,
+    #[arg(
+        long,
+        value_name = "PATH",
+        help = "Keyring for --mon-client (default: keyring from ceph.conf or the standard locations)"
+    )]
+    keyring: Option<PathBuf>,
+    #[arg(
+        long,
+        value_name = "NAME",
+        help = "Client name used with --mon-client [default: client.admin]"
+    )]
+    client_name: Option<String>,
+    #[arg(
+        long,
+        value_name = "ADDRS",
+        help = "Monitor addresses for --mon-client, overriding mon_host from ceph.conf"
+    )]
+    mon_host: Option<String>,
+    #[arg(
+        long,
+        value_name = "FILE",
+        conflicts_with_all = ["prefix_command", "prefix_args", "rest_url", "mon_client"],
+        help = "TOML file with [[cluster]] entries (name plus backend options) to monitor several clusters at once"
+    )]
+    clusters: Option<PathBuf>,
+    #[arg(
+        long,
+        env = "CEPH_DOCTOR_PING_THRESHOLD",
+        help = "Heartbeat ping time in milliseconds above which a network link is flagged as slow [default: 1000]"
+    )]
+    ping_threshold: Option<f64>,
+    #[arg(
+        long,
+        env = "CEPH_DOCTOR_TIMEOUT",
+        help = "Seconds after which a hanging ceph command is killed [default: 60]"
+    )]
+    timeout: Option<u64>,
+    #[arg(
+        long,
+        value_enum,
+        env = "CEPH_DOCTOR_COLOR",
+        help = "When to use colours; 'auto' honours NO_COLOR [default: auto]"
+    )]
+    color: Option<ColorMode>,
+    #[arg(
+        long,
+        value_enum,
+        env = "CEPH_DOCTOR_THEME",
+        help = "Colour theme; the colour-blind theme tells states apart without red and green [default: dark]"
+    )]
+    theme: Option<ThemeName>,
+    #[arg(
+        long,
+        help = "Leave the mouse to the terminal, e.g. to select text, instead of using it for clicks and scrolling"
+    )]
+    no_mouse: bool,
+    #[arg(
+        long,
+        help = "Do not record samples in, or restore rate history from, the history database"
+    )]
+    no_history: bool,
+    #[arg(
+        long,
+        value_name = "PATH",
+        conflicts_with = "no_history",
+        help = "History database [default: ~/.local/share/ceph-doctor/history.db]"
+    )]
+    history_db: Option<PathBuf>,
+    #[arg(
+        long,
+        value_name = "PATH",
+        help = "Append every event of the event view (PG state changes, OSDs down or up, recovery) to this file"
+    )]
+    event_log: Option<PathBuf>,
+    #[arg(
+        long,
+        value_name = "URL",
+        help = "POST events (recovery finished or stalled, inconsistent PG, unfound objects, OSD down) as JSON to this URL; repeatable"
+    )]
+    alert_webhook: Vec<String>,
+    #[arg(
+        long,
+        value_name = "URL",
+        help = "Post events to a Slack, Mattermost or Matrix (hookshot) incoming webhook; repeatable"
+    )]
+    alert_slack: Vec<String>,
+    #[arg(
+        long,
+        value_name = "CMD",
+        help = "Run CMD for each event, with the event as JSON on standard input; repeatable"
+    )]
+    alert_command: Vec<String>,
+    #[arg(
+        long,
+        conflicts_with = "clusters",
+        help = "Print one status line per interval instead of the full-screen view, for pipes, log files and services"
+    )]
+    plain: bool,
+    #[arg(
+        long,
+        value_name = "N",
+        requires = "plain",
+        help = "Exit after N status lines of --plain (e.g. 1 under 'watch')"
+    )]
+    count: Option<u64>,
+}
+
+impl MonitorArgs {
+    /// The options given on the command line or through the environment
+    fn settings(&self) -> Result<Settings> {
+        let clusters = match &self.clusters {
+            Some(path) => load_clusters(path)?,
+            None => Vec::new(),
+        };
+        Ok(Settings {
+            interval: self.interval,
+            timeout: self.timeout,
+            ping_threshold: self.ping_threshold,
+            color: self.color,
+            theme: self.theme,
+            mouse: self.no_mouse.then_some(false),
+            // Naming a database turns the history back on when the config file turned it off
+            history: match (self.no_history, &self.history_db) {
+                (true, _) => Some(false),
+                (false, Some(_)) => Some(true),
+                (false, None) => None,
+            },
+            history_db: self.history_db.clone(),
+            event_log: self.event_log.clone(),
+            source: SourceConfig {
+                prefix_command: self.prefix_command.clone(),
+                prefix_args: self.prefix_args.clone(),
+                rest_url: self.rest_url.clone(),
+                rest_user: self.rest_user.clone(),
+                rest_key: self.rest_key.clone(),
+                rest_token: self.rest_token.clone(),
+                rest_insecure: self.rest_insecure.then_some(true),
+                rest_ca_cert: self.rest_ca_cert.clone(),
+                mon_client: self.mon_client,
+                ceph_conf: self.ceph_conf.clone(),
+                keyring: self.keyring.clone(),
+                client_name: self.client_name.clone(),
+                mon_host: self.mon_host.clone(),
+            },
+            clusters,
+            alerts: self.alerts(),
+            // The table layout and the keys are only set in the config file
+            osd_movement: None,
+            keys: Default::default(),
+            unknown: Default::default(),
+        })
+    }
+
+    /// Alert sinks given as options; they replace the sinks of the config file
+    fn alerts(&self) -> Option<AlertSettings> {
+        let sinks: Vec<SinkConfig> = self
+            .alert_webhook
+            .iter()
+            .map(|url| SinkTarget::Webhook { url: url.clone() })
+            .chain(
+                self.alert_slack
+                    .iter()
+                    .map(|url| SinkTarget::Slack { url: url.clone() }),
+            )
+            .chain(
+                self.alert_command
+                    .iter()
+                    .map(|command| SinkTarget::Command {
+                        command: command.clone(),
+                    }),
+            )
+            .map(SinkConfig::new)
+            .collect();
+        if sinks.is_empty() {
+            return None;
+        }
+        Some(AlertSettings {
+            sinks,
+            ..Default::default()
+        })
+    }
+}
+
+/// The config file given with `--config`, or the default one if it exists
+fn load_config(cli: &Cli) -> Result<(Option<PathBuf>, ConfigFile)> {
+    let path = match &cli.config {
+        Some(path) => Some(path.clone()),
+        None => default_config_path().filter(|path| path.exists()),
+    };
+    match path {
+        Some(path) => {
+            let file = ConfigFile::load(&path)?;
+            Ok((Some(path), file))
+        }
+        None => Ok((None, ConfigFile::default())),
+    }
+}
+
+/// Config file, then profile, then environment and command line
+fn effective_settings(cli: &Cli, args: &MonitorArgs) -> Result<(Option<PathBuf>, Settings)> {
+    let (path, file) = load_config(cli)?;
+    let settings = file
+        .settings(cli.profile.as_deref())?
+        .merge(args.settings()?);
+    Ok((path, settings))
 }
 
 #[tokio::main]
@@ -31,14 +397,116 @@ async fn main() -> Result<()> {
     let cli = Cli::parse();
 
     match &cli.command {
-        Some(Commands::Monitor {
-            interval,
-            prefix_command,
+        Some(Commands::Monitor(args)) => {
+            let (_, settings) = effective_settings(&cli, args)?;
+
+            if args.plain && !settings.clusters.is_empty() {
+                return Err(
+                    "--plain monitors a single cluster, but the config file lists several".into(),
+                );
+            }
+
+            if !settings.clusters.is_empty() {
+                let clusters = settings
+                    .clusters
+                    .iter()
+                    .map(|cluster| {
+                        let source = cluster
+                            .source
+                            .data_source()
+                            .map_err(|e| format!("Cluster '{}': {e}", cluster.name))?;
+                        Ok(Cluster {
+                            name: cluster.name.clone(),
+                            source,
+                        })
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+                ceph_doctor::monitor::multi::run(MultiMonitorOptions {
+                    interval: settings.interval(),
+                    clusters,
+                    ping_threshold_ms: settings.ping_threshold(),
+                    timeout: settings.timeout(),
+                    theme: Theme::new(settings.theme(), settings.color().use_colors()),
+                    history_db: settings.history_db(),
+                    event_log: settings.event_log.clone(),
+                    alerts: settings.alerts().cloned(),
+                    osd_movement: settings.osd_movement.clone().unwrap_or_default(),
+                    keymap: Keymap::new(&settings.keys),
+                    mouse: settings.mouse(),
+                })
+                .await?;
+                return Ok(());
+            }
+
+            let options = ceph_doctor::monitor::MonitorOptions {
+                interval: settings.interval(),
+                source: settings.source.data_source()?,
+                ping_threshold_ms: settings.ping_threshold(),
+                timeout: settings.timeout(),
+                theme: Theme::new(settings.theme(), settings.color().use_colors()),
+                history_db: settings.history_db(),
+                event_log: settings.event_log.clone(),
+                alerts: settings.alerts().cloned(),
+                osd_movement: settings.osd_movement.clone().unwrap_or_default(),
+                keymap: Keymap::new(&settings.keys),
+                mouse: settings.mouse(),
+            };
+            if args.plain {
+                ceph_doctor::monitor::plain::run(options, args.count).await?;
+            } else {
+                ceph_doctor::monitor::run(options).await?;
+            }
+        }
+        Some(Commands::Config {
+            command: ConfigCommands::Show(args),
         }) => {
-            let prefix_args: Option<Vec<String>> = prefix_command
-                .as_ref()
-                .map(|p| p.split_whitespace().map(|s| s.to_string()).collect());
-            ceph_doctor::monitor::run(*interval, prefix_args.as_deref()).await?;
+            let (path, settings) = effective_settings(&cli, args)?;
+            match path {
+                Some(path) => println!("# Config file: {}", path.display()),
+                None => println!("# Config file: none"),
+            }
+            if let Some(profile) = &cli.profile {
+                println!("# Profile: {profile}");
+            }
+            print!(
+                "{}",
+                toml::to_string(&settings.with_defaults().redacted())
+                    .map_err(|e| format!("Cannot format the settings: {e}"))?
+            );
+        }
+        Some(Commands::History(args)) => {
+            let (_, file) = load_config(&cli)?;
+            let settings = file.settings(cli.profile.as_deref())?;
+            let path = args
+                .history_db
+                .clone()
+                .or(settings.history_db)
+                .or_else(default_history_path)
+                .ok_or("Cannot find the history database, use --history-db")?;
+            if !path.exists() {
+                return Err(format!("No history database at {}", path.display()).into());
+            }
+            let store = HistoryStore::open(&path)?;
+            let seconds = i64::try_from(args.days)
+                .ok()
+                .and_then(|days| days.checked_mul(24 * 60 * 60))
+                .ok_or("--days is too large")?;
+            let since = chrono::Utc::now().timestamp().saturating_sub(seconds);
+            print!("{}", history_report(&store, args.fsid.as_deref(), since)?);
+        }
+        Some(Commands::Report(args)) => {
+            let report = Report::new(&Snapshots::load_dir(&args.from)?);
+            let format = args.format.unwrap_or_else(|| {
+                args.output
+                    .as_deref()
+                    .map_or(ReportFormat::Markdown, ReportFormat::from_path)
+            });
+            let text = report.render(format);
+            match &args.output {
+                Some(path) => std::fs::write(path, text)
+                    .map_err(|e| format!("Cannot write {}: {e}", path.display()))?,
+                None => print!("{text}"),
+            }
         }
         None => {
             // Print comprehensive help when no subcommand is provided
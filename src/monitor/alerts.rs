@@ -0,0 +1,499 @@
+//! Delivery of events to webhooks, chat rooms and commands, with dedup and
+//! rate limiting so a flapping cluster does not flood anyone.
+
+use crate::monitor::events::{Event, EventKind};
+use crate::monitor::prefix::split_shell_words;
+use crate::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+use tokio::sync::mpsc;
+
+pub const DEFAULT_DEDUP_MINUTES: u64 = 60;
+pub const DEFAULT_MAX_PER_HOUR: usize = 20;
+const SEND_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Where to send events
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum SinkTarget {
+    /// POST the event as JSON
+    Webhook { url: String },
+    /// POST `{"text": ...}`, understood by Slack, Mattermost and the Matrix hookshot bridge
+    Slack { url: String },
+    /// Run a command with the event as JSON on standard input
+    Command { command: String },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct SinkConfig {
+    #[serde(flatten)]
+    pub target: SinkTarget,
+    /// Only these events; all but the per-PG changes when empty
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub events: Vec<EventKind>,
+}
+
+impl SinkConfig {
+    pub fn new(target: SinkTarget) -> Self {
+        Self {
+            target,
+            events: Vec::new(),
+        }
+    }
+
+    fn redacted(self, redacted: &str) -> Self {
+        let target = match self.target {
+            // Webhook URLs usually contain the secret
+            SinkTarget::Webhook { .. } => SinkTarget::Webhook {
+                url: redacted.to_string(),
+            },
+            SinkTarget::Slack { .. } => SinkTarget::Slack {
+                url: redacted.to_string(),
+            },
+            command => command,
+        };
+        Self { target, ..self }
+    }
+}
+
+/// The `[alerts]` table of the config file
+#[derive(Debug, Default, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct AlertSettings {
+    /// Minutes without progress before a recovery counts as stalled
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stall_minutes: Option<u64>,
+    /// The same event about the same PG or OSD is sent once in this many minutes
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dedup_minutes: Option<u64>,
+    /// Events per sink and hour; more are dropped and counted
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_per_hour: Option<usize>,
+    #[serde(rename = "sink", skip_serializing_if = "Vec::is_empty")]
+    pub sinks: Vec<SinkConfig>,
+}
+
+impl AlertSettings {
+    /// Settings of `over` win; its sinks replace ours when it has any
+    pub fn merge(self, over: AlertSettings) -> AlertSettings {
+        AlertSettings {
+            stall_minutes: over.stall_minutes.or(self.stall_minutes),
+            dedup_minutes: over.dedup_minutes.or(self.dedup_minutes),
+            max_per_hour: over.max_per_hour.or(self.max_per_hour),
+            sinks: if over.sinks.is_empty() {
+                self.sinks
+            } else {
+                over.sinks
+            },
+        }
+    }
+
+    pub fn stall_minutes(&self) -> u64 {
+        self.stall_minutes
+            .unwrap_or(crate::monitor::events::DEFAULT_STALL_MINUTES)
+    }
+
+    pub fn dedup_minutes(&self) -> u64 {
+        self.dedup_minutes.unwrap_or(DEFAULT_DEDUP_MINUTES)
+    }
+
+    pub fn max_per_hour(&self) -> usize {
+        self.max_per_hour.unwrap_or(DEFAULT_MAX_PER_HOUR)
+    }
+
+    pub fn with_defaults(self) -> AlertSettings {
+        AlertSettings {
+            stall_minutes: Some(self.stall_minutes()),
+            dedup_minutes: Some(self.dedup_minutes()),
+            max_per_hour: Some(self.max_per_hour()),
+            ..self
+        }
+    }
+
+    pub fn redacted(self, redacted: &str) -> AlertSettings {
+        AlertSettings {
+            sinks: self
+                .sinks
+                .into_iter()
+                .map(|sink| sink.redacted(redacted))
+                .collect(),
+            ..self
+        }
+    }
+
+    pub fn validate(&self) -> std::result::Result<(), String> {
+        for sink in &self.sinks {
+            match &sink.target {
+                SinkTarget::Webhook { url } | SinkTarget::Slack { url } => {
+                    if !url.starts_with("http://") && !url.starts_with("https://") {
+                        return Err(format!("alert sink URL '{url}' is not http(s)"));
+                    }
+                }
+                SinkTarget::Command { command } => {
+                    if split_shell_words(command)?.is_empty() {
+                        return Err("alert sink with an empty command".to_string());
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The JSON sent to webhooks and commands
+#[derive(Debug, Serialize)]
+struct Payload<'a> {
+    #[serde(flatten)]
+    event: &'a Event,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    cluster: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    fsid: Option<&'a str>,
+    /// Events dropped by the rate limit of this sink since the last one sent
+    suppressed: usize,
+}
+
+struct Sink {
+    config: SinkConfig,
+    /// When the events of the last hour were sent
+    sent: VecDeque<i64>,
+    suppressed: usize,
+}
+
+impl Sink {
+    fn wants(&self, kind: EventKind) -> bool {
+        if self.config.events.is_empty() {
+            kind.is_alert()
+        } else {
+            self.config.events.contains(&kind)
+        }
+    }
+}
+
+/// Sends events to the configured sinks in background tasks
+pub struct Alerter {
+    sinks: Vec<Sink>,
+    client: reqwest::Client,
+    dedup_secs: i64,
+    max_per_hour: usize,
+    /// When each event was last sent, by kind and subject
+    last_sent: HashMap<(EventKind, Option<String>), i64>,
+    error_sender: mpsc::UnboundedSender<String>,
+    error_receiver: mpsc::UnboundedReceiver<String>,
+}
+
+impl Alerter {
+    pub fn new(settings: &AlertSettings) -> Result<Self> {
+        settings.validate()?;
+        let client = reqwest::Client::builder().timeout(SEND_TIMEOUT).build()?;
+        let (error_sender, error_receiver) = mpsc::unbounded_channel();
+        Ok(Self {
+            sinks: settings
+                .sinks
+                .iter()
+                .map(|config| Sink {
+                    config: config.clone(),
+                    sent: VecDeque::new(),
+                    suppressed: 0,
+                })
+                .collect(),
+            client,
+            dedup_secs: settings.dedup_minutes() as i64 * 60,
+            max_per_hour: settings.max_per_hour(),
+            last_sent: HashMap::new(),
+            error_sender,
+            error_receiver,
+        })
+    }
+
+    /// Send the events that are not duplicates to every sink that wants them
+    /// and is within its rate limit
+    pub fn notify(&mut self, events: &[Event], cluster: Option<&str>, fsid: Option<&str>) {
+        for event in events {
+            if !self.sinks.iter().any(|sink| sink.wants(event.kind)) || !self.is_new(event) {
+                continue;
+            }
+            for index in 0..self.sinks.len() {
+                let Some(suppressed) = self.admit(index, event) else {
+                    continue;
+                };
+                let payload = Payload {
+                    event,
+                    cluster,
+                    fsid,
+                    suppressed,
+                };
+                self.send(&self.sinks[index].config.target, &payload);
+            }
+        }
+    }
+
+    /// The next delivery problem, for showing next to the data
+    pub fn take_error(&mut self) -> Option<String> {
+        let mut latest = None;
+        while let Ok(error) = self.error_receiver.try_recv() {
+            latest = Some(error);
+        }
+        latest
+    }
+
+    /// False if the same event was sent within the dedup window
+    fn is_new(&mut self, event: &Event) -> bool {
+        let key = (event.kind, event.subject.clone());
+        match self.last_sent.get(&key) {
+            Some(&last) if event.time - last < self.dedup_secs => false,
+            _ => {
+                self.last_sent.insert(key, event.time);
+                true
+            }
+        }
+    }
+
+    /// Whether sink `index` takes the event, with the number of events it
+    /// dropped since its last one
+    fn admit(&mut self, index: usize, event: &Event) -> Option<usize> {
+        let sink = &mut self.sinks[index];
+        if !sink.wants(event.kind) {
+            return None;
+        }
+        while sink.sent.front().is_some_and(|&t| event.time - t >= 3600) {
+            sink.sent.pop_front();
+        }
+        if sink.sent.len() >= self.max_per_hour {
+            sink.suppressed += 1;
+            return None;
+        }
+        sink.sent.push_back(event.time);
+        Some(std::mem::take(&mut sink.suppressed))
+    }
+
+    fn send(&self, target: &SinkTarget, payload: &Payload) {
+        let errors = self.error_sender.clone();
+        let body = match target {
+            SinkTarget::Slack { .. } => serde_json::json!({ "text": chat_text(payload) }),
+            _ => serde_json::to_value(payload).unwrap_or_default(),
+        };
+        let target = target.clone();
+        let client = self.client.clone();
+        let kind = payload.event.kind;
+        tokio::spawn(async move {
+            let result = match &target {
+                SinkTarget::Webhook { url } | SinkTarget::Slack { url } => {
+                    post(&client, url, &body).await
+                }
+                SinkTarget::Command { command } => run_command(command, kind, &body).await,
+            };
+            if let Err(e) = result {
+                // The receiver is gone when the monitor is shutting down
+                let _ = errors.send(format!("Alert {} not delivered: {e}", kind.label()));
+            }
+        });
+    }
+}
+
+/// One line for chat rooms, e.g. `[prod] WARNING: osd.3 is down`
+fn chat_text(payload: &Payload) -> String {
+    let mut text = String::new();
+    if let Some(cluster) = payload.cluster.or(payload.fsid) {
+        text.push_str(&format!("[{cluster}] "));
+    }
+    let severity = payload.event.severity.label().to_uppercase();
+    text.push_str(&format!("{severity}: {}", payload.event.message));
+    if payload.suppressed > 0 {
+        text.push_str(&format!(
+            " ({} earlier events dropped by the rate limit)",
+            payload.suppressed
+        ));
+    }
+    text
+}
+
+async fn post(
+    client: &reqwest::Client,
+    url: &str,
+    body: &serde_json::Value,
+) -> std::result::Result<(), String> {
+    let response = client
+        .post(url)
+        .json(body)
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+    if !response.status().is_success() {
+        return Err(format!("HTTP {}", response.status()));
+    }
+    Ok(())
+}
+
+/// Run the command with the event as JSON on standard input and as
+/// `CEPH_DOCTOR_EVENT`, `CEPH_DOCTOR_SEVERITY` and `CEPH_DOCTOR_MESSAGE`
+async fn run_command(
+    command: &str,
+    kind: EventKind,
+    body: &serde_json::Value,
+) -> std::result::Result<(), String> {
+    let args = split_shell_words(command)?;
+    let (program, args) = args.split_first().ok_or("empty command")?;
+    let mut child = tokio::process::Command::new(program)
+        .args(args)
+        .env("CEPH_DOCTOR_EVENT", kind.label())
+        .env(
+            "CEPH_DOCTOR_SEVERITY",
+            body["severity"].as_str().unwrap_or_default(),
+        )
+        .env(
+            "CEPH_DOCTOR_MESSAGE",
+            body["message"].as_str().unwrap_or_default(),
+        )
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| format!("cannot run {program}: {e}"))?;
+    if let Some(mut stdin) = child.stdin.take() {
+        // A command that does not read its input is fine
+        let _ = stdin.write_all(format!("{body}\n").as_bytes()).await;
+    }
+    let status = tokio::time::timeout(SEND_TIMEOUT, child.wait())
+        .await
+        .map_err(|_| format!("{program} timed out"))?
+        .map_err(|e| e.to_string())?;
+    if !status.success() {
+        return Err(format!("{program} exited with {status}"));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::monitor::events::Severity;
+
+    fn event(kind: EventKind, subject: &str, time: i64) -> Event {
+        Event {
+            kind,
+            severity: kind.severity(),
+            subject: Some(subject.to_string()),
+            message: format!("{subject} changed"),
+            time,
+        }
+    }
+
+    fn alerter(max_per_hour: usize, events: Vec<EventKind>) -> Alerter {
+        Alerter::new(&AlertSettings {
+            max_per_hour: Some(max_per_hour),
+            sinks: vec![SinkConfig {
+                target: SinkTarget::Webhook {
+                    url: "http://127.0.0.1:9/".to_string(),
+                },
+                events,
+            }],
+            ..Default::default()
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_dedup() {
+        let mut alerter = alerter(100, vec![]);
+        assert!(alerter.is_new(&event(EventKind::OsdDown, "osd.1", 0)));
+        assert!(!alerter.is_new(&event(EventKind::OsdDown, "osd.1", 3599)));
+        assert!(alerter.is_new(&event(EventKind::OsdDown, "osd.2", 10)));
+        assert!(alerter.is_new(&event(EventKind::OsdUp, "osd.1", 10)));
+        assert!(alerter.is_new(&event(EventKind::OsdDown, "osd.1", 3600)));
+    }
+
+    #[test]
+    fn test_rate_limit_and_filter() {
+        let mut alerter = alerter(2, vec![EventKind::OsdDown]);
+        let down = |time| event(EventKind::OsdDown, "osd.1", time);
+        assert_eq!(alerter.admit(0, &event(EventKind::OsdUp, "osd.1", 0)), None);
+        assert_eq!(alerter.admit(0, &down(0)), Some(0));
+        assert_eq!(alerter.admit(0, &down(10)), Some(0));
+        assert_eq!(alerter.admit(0, &down(20)), None);
+        assert_eq!(alerter.admit(0, &down(30)), None);
+        // The first one is older than an hour again
+        assert_eq!(alerter.admit(0, &down(3600)), Some(2));
+    }
+
+    #[test]
+    fn test_payload() {
+        let event = event(EventKind::OsdDown, "osd.3", 1753002000);
+        let payload = Payload {
+            event: &event,
+            cluster: Some("prod"),
+            fsid: None,
+            suppressed: 2,
+        };
+        assert_eq!(
+            serde_json::to_value(&payload).unwrap(),
+            serde_json::json!({
+                "event": "osd_down",
+                "severity": "warning",
+                "subject": "osd.3",
+                "message": "osd.3 changed",
+                "time": 1753002000,
+                "cluster": "prod",
+                "suppressed": 2,
+            })
+        );
+        assert_eq!(event.severity, Severity::Warning);
+        assert_eq!(
+            chat_text(&payload),
+            "[prod] WARNING: osd.3 changed (2 earlier events dropped by the rate limit)"
+        );
+    }
+
+    #[test]
+    fn test_settings() {
+        let settings: AlertSettings = toml::from_str(
+            r#"
+            stall_minutes = 5
+            [[sink]]
+            type = "slack"
+            url = "https://hooks.example.com/T0/B0/secret"
+            events = ["recovery_finished", "osd_down"]
+            [[sink]]
+            type = "command"
+            command = "logger -t ceph-doctor"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(settings.stall_minutes(), 5);
+        assert_eq!(settings.max_per_hour(), DEFAULT_MAX_PER_HOUR);
+        assert_eq!(
+            settings.sinks[0].events,
+            vec![EventKind::RecoveryFinished, EventKind::OsdDown]
+        );
+        assert!(settings.validate().is_ok());
+
+        let redacted = settings.clone().redacted("<redacted>");
+        assert_eq!(
+            redacted.sinks[0].target,
+            SinkTarget::Slack {
+                url: "<redacted>".to_string()
+            }
+        );
+        assert_eq!(redacted.sinks[1], settings.sinks[1]);
+
+        let over = AlertSettings {
+            max_per_hour: Some(1),
+            ..Default::default()
+        };
+        let merged = settings.clone().merge(over);
+        assert_eq!(merged.sinks, settings.sinks);
+        assert_eq!((merged.stall_minutes(), merged.max_per_hour()), (5, 1));
+
+        let bad = AlertSettings {
+            sinks: vec![SinkConfig::new(SinkTarget::Webhook {
+                url: "ftp://example.com".to_string(),
+            })],
+            ..Default::default()
+        };
+        assert!(bad.validate().is_err());
+    }
+}
@@ -0,0 +1,478 @@
+use crate::common::{CephPgDump, CephStatus};
+use crate::error::CephError;
+use crate::monitor::alerts::{AlertSettings, Alerter};
+use crate::monitor::data::{estimate_recovery_eta, osd_host_map};
+use crate::monitor::events::EventDetector;
+use crate::monitor::fetcher::{FetchRequest, Fetcher};
+use crate::monitor::history::{HistoryStore, HistoryWriter, Record};
+use crate::monitor::source::{CephQuery, DataSource};
+use crate::monitor::state::{self, MonitorState};
+use crate::monitor::terminal::RuntimeControl;
+use crate::monitor::ui::Theme;
+use crate::Result;
+use ratatui::backend::Backend;
+use ratatui::Terminal;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+/// One monitored cluster: its fetcher, the latest data and its own `MonitorState`.
+pub struct ClusterMonitor {
+    fetcher: Fetcher,
+    interval: u64,
+    state: MonitorState,
+    last_data: Option<CephPgDump>,
+    last_status: Option<CephStatus>,
+    error_message: Option<String>,
+    next_fetch: Instant,
+    /// Fetch once now, even while paused
+    refresh_requested: bool,
+    /// The next sample does not follow the last one by one interval
+    rates_stale: bool,
+    shown_fetch_secs: Option<u64>,
+    /// Finished fetches, successful or not
+    fetches: u64,
+    fetch_status: bool,
+    /// Degraded plus misplaced objects, one sample per successful fetch
+    remaining_history: Vec<i64>,
+    /// Read once to restore the recent history
+    history: Option<HistoryStore>,
+    history_writer: Option<HistoryWriter>,
+    /// Learned from `ceph status` when the history is enabled
+    fsid: Option<String>,
+    history_error: Option<String>,
+    events: EventDetector,
+    alerter: Option<Alerter>,
+    /// File the events are appended to
+    event_log: Option<PathBuf>,
+    /// The last event that could not be delivered or logged
+    event_error: Option<String>,
+}
+
+const REMAINING_HISTORY_SIZE: usize = 20;
+
+/// The intervals `+` and `-` step through, in seconds
+const INTERVAL_STEPS: [u64; 10] = [1, 2, 5, 10, 15, 30, 60, 120, 300, 600];
+
+/// The next step above or below `interval`, or `interval` itself at the ends
+fn step_interval(interval: u64, slower: bool) -> u64 {
+    if slower {
+        INTERVAL_STEPS
+            .into_iter()
+            .find(|&step| step > interval)
+            .unwrap_or(interval)
+    } else {
+        INTERVAL_STEPS
+            .into_iter()
+            .rev()
+            .find(|&step| step < interval)
+            .unwrap_or(interval)
+    }
+}
+
+impl ClusterMonitor {
+    pub fn new(
+        source: DataSource,
+        interval: u64,
+        timeout: u64,
+        ping_threshold_ms: f64,
+        theme: Theme,
+    ) -> Self {
+        let mut state = MonitorState::new();
+        state.set_ping_threshold_ms(ping_threshold_ms);
+        state.set_theme(theme);
+        state.set_fetch_description(source.describe(CephQuery::PgDump));
+
+        Self {
+            fetcher: Fetcher::new(source, Duration::from_secs(timeout)),
+            interval,
+            state,
+            last_data: None,
+            last_status: None,
+            error_message: None,
+            next_fetch: Instant::now(),
+            refresh_requested: false,
+            rates_stale: false,
+            shown_fetch_secs: None,
+            fetches: 0,
+            fetch_status: false,
+            remaining_history: Vec::new(),
+            history: None,
+            history_writer: None,
+            fsid: None,
+            history_error: None,
+            events: EventDetector::default(),
+            alerter: None,
+            event_log: None,
+            event_error: None,
+        }
+    }
+
+    /// Record every fetch in the history database and restore the recent
+    /// history once the fsid of the cluster is known. Problems with the
+    /// database are shown as an error but do not stop the monitor.
+    pub fn with_history(mut self, path: &Path) -> Self {
+        // The writer gets a connection of its own, as SQLite connections are not shared
+        match HistoryStore::open(path).and_then(|store| Ok((store, HistoryStore::open(path)?))) {
+            Ok((store, writer)) => {
+                self.history = Some(store);
+                self.history_writer = Some(HistoryWriter::new(writer));
+            }
+            Err(e) => self.history_error = Some(format!("History disabled: {e}")),
+        }
+        self
+    }
+
+    /// Send the events found between consecutive fetches to the alert sinks
+    pub fn with_alerts(mut self, settings: &AlertSettings) -> Result<Self> {
+        self.events = EventDetector::new(settings.stall_minutes());
+        self.alerter = Some(Alerter::new(settings)?);
+        Ok(self)
+    }
+
+    /// Append every event to a file as it is seen
+    pub fn with_event_log(mut self, path: &Path) -> Self {
+        self.event_log = Some(path.to_path_buf());
+        self
+    }
+
+    /// Also fetch `ceph status` each cycle, for the cluster health
+    pub fn with_status(mut self) -> Self {
+        self.fetch_status = true;
+        self
+    }
+
+    pub fn state(&self) -> &MonitorState {
+        &self.state
+    }
+
+    pub fn state_mut(&mut self) -> &mut MonitorState {
+        &mut self.state
+    }
+
+    pub fn last_data(&self) -> Option<&CephPgDump> {
+        self.last_data.as_ref()
+    }
+
+    pub fn last_status(&self) -> Option<&CephStatus> {
+        self.last_status.as_ref()
+    }
+
+    pub fn error_message(&self) -> Option<&String> {
+        self.error_message.as_ref()
+    }
+
+    /// Fetches finished so far; a change means a new sample or a new error
+    pub fn fetches(&self) -> u64 {
+        self.fetches
+    }
+
+    pub fn interval(&self) -> u64 {
+        self.interval
+    }
+
+    /// The last fetch failed, either with an error message or the command error popup
+    pub fn has_error(&self) -> bool {
+        self.error_message.is_some() || self.state.has_command_error_popup()
+    }
+
+    /// Estimated seconds until degraded and misplaced objects are recovered
+    pub fn recovery_eta(&self) -> Option<u64> {
+        estimate_recovery_eta(&self.remaining_history, self.interval)
+    }
+
+    pub fn fetch_elapsed(&self) -> Option<Duration> {
+        self.fetcher.elapsed()
+    }
+
+    /// Pause or resume polling, fetch now or change the interval. Paused, the
+    /// last snapshot stays on screen and a refresh fetches a single new one.
+    pub fn control(&mut self, control: RuntimeControl) {
+        match control {
+            RuntimeControl::TogglePause => {
+                let paused = !self.state.paused();
+                self.state.set_paused(paused);
+                if paused {
+                    self.fetcher.cancel();
+                    self.state.set_fetch_elapsed(None);
+                    self.shown_fetch_secs = None;
+                } else {
+                    self.next_fetch = Instant::now();
+                }
+                self.rates_stale = true;
+            }
+            RuntimeControl::RefreshNow => self.refresh_requested = true,
+            RuntimeControl::SlowerInterval | RuntimeControl::FasterInterval => {
+                let interval =
+                    step_interval(self.interval, control == RuntimeControl::SlowerInterval);
+                if interval != self.interval {
+                    self.interval = interval;
+                    self.next_fetch = Instant::now() + Duration::from_secs(interval);
+                    self.rates_stale = true;
+                }
+            }
+        }
+    }
+
+    /// Start a due fetch and collect finished ones. Returns true when the
+    /// screen needs a redraw: new data arrived or the fetch timer ticked.
+    pub fn poll(&mut self) -> bool {
+        // Start the next fetch when it is due, but not while paused or the error popup is open
+        let due = !self.state.paused() && Instant::now() >= self.next_fetch;
+        if !self.fetcher.is_fetching()
+            && !self.state.has_command_error_popup()
+            && (due || self.refresh_requested)
+        {
+            // A refresh between two samples does not follow the last one by an interval
+            if !due {
+                self.rates_stale = true;
+            }
+            self.refresh_requested = false;
+            self.fetcher.start(FetchRequest {
+                osd_tree: self.state.active_view() == state::View::Network,
+                status: self.fetch_status || (self.history.is_some() && self.fsid.is_none()),
+            });
+        }
+        self.state.set_fetch_elapsed(self.fetcher.elapsed());
+
+        let alert_failed = self.take_alert_error() | self.take_history_error();
+        if let Some(result) = self.fetcher.try_recv() {
+            if let (Some(Ok(status)), None) = (&result.status, &self.fsid) {
+                self.fsid = Some(status.fsid.clone());
+                self.restore_history();
+            }
+
+            match result.pg_dump {
+                Ok(data) => {
+                    self.record_history(&data);
+                    self.record_events(&data);
+                    // A sample taken after a pause, a refresh or an interval change restarts the rates
+                    if self.rates_stale || self.state.paused() {
+                        self.state.restart_rates();
+                        self.remaining_history.clear();
+                        self.rates_stale = false;
+                    }
+                    self.state.update(&data, self.interval);
+                    let stats_sum = &data.pg_map.pg_stats_sum.stat_sum;
+                    self.remaining_history
+                        .push(stats_sum.num_objects_degraded + stats_sum.num_objects_misplaced);
+                    if self.remaining_history.len() > REMAINING_HISTORY_SIZE {
+                        self.remaining_history.remove(0);
+                    }
+                    self.last_data = Some(data);
+                    self.error_message = None;
+                }
+                Err(e) => {
+                    // Failed commands get the detailed popup, everything else the error box
+                    let class = e.class();
+                    if let CephError::CommandFailed {
+                        command,
+                        exit_code,
+                        stdout,
+                        stderr,
+                    } = e
+                    {
+                        self.state.set_command_error_popup(state::CommandError {
+                            command,
+                            stdout,
+                            stderr,
+                            exit_code,
+                            class,
+                            scroll_offset: 0,
+                        });
+                        self.error_message = None; // Clear regular error message
+                    } else {
+                        self.error_message = Some(format!("{}: {e}", class.label()));
+                    }
+                }
+            }
+            // Host names are only needed by the network view; failing to get them is not fatal
+            if let Some(Ok(tree)) = result.osd_tree {
+                self.state.set_osd_hosts(osd_host_map(&tree));
+            }
+            // Same for the health shown in the cluster summary
+            if let Some(status) = result.status {
+                self.last_status = status.ok();
+            }
+
+            self.next_fetch = Instant::now() + Duration::from_secs(self.interval);
+            self.fetches += 1;
+            self.shown_fetch_secs = None;
+            self.state.set_fetch_elapsed(None);
+            true
+        } else if let Some(elapsed) = self.fetcher.elapsed() {
+            // Keep the fetching indicator ticking once per second
+            if self.shown_fetch_secs != Some(elapsed.as_secs()) {
+                self.shown_fetch_secs = Some(elapsed.as_secs());
+                true
+            } else {
+                alert_failed
+            }
+        } else {
+            alert_failed
+        }
+    }
+
+    /// Compare the new data with the last fetch and hand the changes to the
+    /// event view, the event log file and the alert sinks
+    fn record_events(&mut self, data: &CephPgDump) {
+        let now = chrono::Utc::now().timestamp();
+        let events = self.events.detect(self.last_data.as_ref(), data, now);
+        if events.is_empty() {
+            return;
+        }
+        if let Some(path) = &self.event_log {
+            let cluster = self.state.cluster_name();
+            let text: String = events
+                .iter()
+                .map(|event| format!("{}\n", event.format_line(cluster)))
+                .collect();
+            let written = std::fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)
+                .and_then(|mut file| file.write_all(text.as_bytes()));
+            if let Err(e) = written {
+                self.event_error = Some(format!("Event log {}: {e}", path.display()));
+            }
+        }
+        if let Some(alerter) = &mut self.alerter {
+            alerter.notify(&events, self.state.cluster_name(), self.fsid.as_deref());
+        }
+        self.state.add_events(events);
+    }
+
+    /// Whether a delivery failed since the last poll
+    fn take_alert_error(&mut self) -> bool {
+        match self.alerter.as_mut().and_then(Alerter::take_error) {
+            Some(error) => {
+                self.event_error = Some(error);
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn restore_history(&mut self) {
+        let (Some(store), Some(fsid)) = (&self.history, &self.fsid) else {
+            return;
+        };
+        let now = chrono::Utc::now().timestamp();
+        if let Err(e) = store.restore(&mut self.state, fsid, now, self.interval) {
+            self.disable_history(e);
+        }
+    }
+
+    fn record_history(&mut self, data: &CephPgDump) {
+        let (Some(writer), Some(fsid)) = (&self.history_writer, &self.fsid) else {
+            return;
+        };
+        let now = chrono::Utc::now().timestamp();
+        writer.write(Record::from_pg_dump(fsid, now, data));
+    }
+
+    /// Whether writing the history failed since the last poll
+    fn take_history_error(&mut self) -> bool {
+        match self
+            .history_writer
+            .as_mut()
+            .and_then(HistoryWriter::take_error)
+        {
+            Some(error) => {
+                self.disable_history(error);
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn disable_history(&mut self, error: impl std::fmt::Display) {
+        self.history = None;
+        self.history_writer = None;
+        self.history_error = Some(format!("History disabled: {error}"));
+    }
+
+    /// Draw the monitor and remember where the mouse can click
+    pub fn draw<B: Backend>(&mut self, terminal: &mut Terminal<B>) -> Result<()> {
+        let areas = super::render_current_state(
+            terminal,
+            self.last_data.as_ref(),
+            // A broken history database, alert sink or event log is only worth mentioning next to the data
+            self.error_message.as_ref().or(self
+                .history_error
+                .as_ref()
+                .or(self.event_error.as_ref())
+                .filter(|_| self.last_data.is_some())),
+            self.interval,
+            &self.state,
+        )?;
+        self.state.set_mouse_areas(areas);
+        Ok(())
+    }
+
+    pub fn cancel(&mut self) {
+        self.fetcher.cancel();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::monitor::prefix::PrefixCommand;
+
+    #[test]
+    fn test_step_interval() {
+        assert_eq!(step_interval(5, true), 10);
+        assert_eq!(step_interval(5, false), 2);
+        // Values between the steps go to the neighbouring step
+        assert_eq!(step_interval(7, true), 10);
+        assert_eq!(step_interval(7, false), 5);
+        assert_eq!(step_interval(1, false), 1);
+        assert_eq!(step_interval(600, true), 600);
+        assert_eq!(step_interval(900, false), 600);
+    }
+
+    /// Polls until `fetches` fetches have finished
+    async fn poll_until(cluster: &mut ClusterMonitor, fetches: u64) {
+        for _ in 0..500 {
+            cluster.poll();
+            if cluster.fetches() >= fetches {
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        panic!("fetch {fetches} did not finish");
+    }
+
+    #[tokio::test]
+    async fn test_refresh_restarts_rates() {
+        // Each fetch prints the next pg dump, 2000, 1950 and then 1850 misplaced objects
+        let dir = std::env::temp_dir().join(format!("ceph-doctor-refresh-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let dump = std::fs::read_to_string("tests/fixtures/pg_dump.json").unwrap();
+        let mut dump: serde_json::Value = serde_json::from_str(&dump).unwrap();
+        for (index, misplaced) in [2000, 1950, 1850].into_iter().enumerate() {
+            dump["pg_map"]["pg_stats_sum"]["stat_sum"]["num_objects_misplaced"] = misplaced.into();
+            std::fs::write(dir.join(format!("{index}.json")), dump.to_string()).unwrap();
+        }
+        let prefix = PrefixCommand::parse(&format!(
+            "sh -c 'cd {} && f=$(ls | head -n 1) && cat $f && \
+             if [ $(ls | wc -l) -gt 1 ]; then rm $f; fi # {{}}'",
+            dir.display()
+        ))
+        .unwrap();
+        let mut cluster =
+            ClusterMonitor::new(DataSource::Cli(prefix), 1, 10, 1000.0, Theme::default());
+
+        poll_until(&mut cluster, 1).await;
+        // Refreshed right after the first sample, long before the next is due
+        cluster.control(RuntimeControl::RefreshNow);
+        poll_until(&mut cluster, 2).await;
+        assert_eq!(cluster.recovery_eta(), None);
+
+        // 100 objects in the one interval after the refresh, not 150 in two
+        poll_until(&mut cluster, 3).await;
+        assert_eq!(cluster.recovery_eta(), Some(18));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,9 +1,21 @@
-use crate::common::{CephPgDump, InconsistentPgProgress, OsdDataMovement};
-use crate::monitor::state::MonitorState;
+use crate::common::{
+    CephPgDump, InconsistentPgProgress, OsdDataMovement, OsdOverview, OsdOverviewMedians,
+    RecoveryProgress,
+};
+use crate::monitor::state::{
+    MonitorState, OsdMovementRow, OsdMovementSort, OsdMovementSortColumn, RecoveryData,
+};
 use std::collections::{HashMap, HashSet};
 
 const HISTORY_SIZE: usize = 20;
 
+// An OSD is flagged as an outlier when a metric exceeds the cluster median by
+// this factor AND by the given absolute margin (avoids flagging 0ms vs 1ms).
+const LATENCY_OUTLIER_FACTOR: f64 = 2.0;
+const LATENCY_OUTLIER_MIN_DELTA_MS: f64 = 5.0;
+const UTILIZATION_OUTLIER_MIN_DELTA: f64 = 10.0; // Percentage points above median
+const PGS_OUTLIER_FACTOR: f64 = 1.3;
+
 pub fn calculate_recovery_progress_height(data: &CephPgDump) -> u16 {
     let stats_sum = &data.pg_map.pg_stats_sum.stat_sum;
 
@@ -28,6 +40,73 @@ pub fn calculate_recovery_progress_height(data: &CephPgDump) -> u16 {
     }
 }
 
+pub fn calculate_recovery_progress(
+    current_data: &CephPgDump,
+    state: &mut MonitorState,
+    interval: u64,
+) -> Vec<RecoveryProgress> {
+    let stats_sum = &current_data.pg_map.pg_stats_sum.stat_sum;
+
+    // Calculate average object size for estimation
+    let avg_object_size = if stats_sum.num_objects > 0 {
+        stats_sum.num_bytes as f64 / stats_sum.num_objects as f64
+    } else {
+        0.0
+    };
+
+    let categories = [
+        ("Missing", stats_sum.num_objects_missing),
+        ("Unfound", stats_sum.num_objects_unfound),
+        ("Misplaced", stats_sum.num_objects_misplaced),
+        ("Degraded", stats_sum.num_objects_degraded),
+    ];
+
+    let mut progress = Vec::new();
+    for (category, current_objects) in categories {
+        // Only categories with objects left are tracked and shown
+        if current_objects <= 0 {
+            continue;
+        }
+        let estimated_bytes = (current_objects as f64 * avg_object_size) as i64;
+        state.add_recovery_data(
+            category,
+            RecoveryData {
+                objects: current_objects,
+                bytes: estimated_bytes,
+            },
+            HISTORY_SIZE,
+        );
+
+        // Rates from the oldest sample in the history to the current one
+        let history = state
+            .get_recovery_history(category)
+            .map_or(&[][..], Vec::as_slice);
+        let time_elapsed = history.len().saturating_sub(1) as f64 * interval as f64;
+        let (object_rate, data_rate) = match history.first() {
+            Some(oldest) if time_elapsed > 0.0 => (
+                (current_objects - oldest.objects) as f64 / time_elapsed,
+                (estimated_bytes - oldest.bytes) as f64 / time_elapsed,
+            ),
+            _ => (0.0, 0.0),
+        };
+
+        let eta_seconds =
+            (object_rate < 0.0).then(|| (current_objects as f64 / -object_rate) as u64);
+
+        progress.push(RecoveryProgress {
+            category,
+            objects: current_objects,
+            object_rate,
+            data_rate,
+            eta_seconds,
+        });
+    }
+
+    state.set_recovery_progress(progress.clone());
+
+    progress
+}
+
 pub fn calculate_osd_data_movement(
     current_data: &CephPgDump,
     state: &mut MonitorState,
@@ -45,7 +124,7 @@ pub fn calculate_osd_data_movement(
             });
     }
 
-    // Reset current counts for all OSDs
+    // Reset current counts for all OSDs, then fill in the ones with misplaced objects
     for movement in osd_movements.values_mut() {
         movement.incoming_objects = 0;
         movement.outgoing_objects = 0;
@@ -55,10 +134,136 @@ pub fn calculate_osd_data_movement(
         movement.excess_objects_waiting = 0;
         movement.missing_objects_active = 0;
         movement.excess_objects_active = 0;
+        movement.missing_bytes = 0;
+        movement.excess_bytes = 0;
+    }
+    for (osd_id, counts) in count_osd_misplaced_objects(current_data) {
+        let entry = osd_movements.entry(osd_id).or_default();
+        entry.osd_id = osd_id;
+        entry.incoming_objects = counts.incoming_objects;
+        entry.outgoing_objects = counts.outgoing_objects;
+        entry.missing_objects = counts.missing_objects;
+        entry.excess_objects = counts.excess_objects;
+        entry.missing_objects_waiting = counts.missing_objects_waiting;
+        entry.excess_objects_waiting = counts.excess_objects_waiting;
+        entry.missing_objects_active = counts.missing_objects_active;
+        entry.excess_objects_active = counts.excess_objects_active;
+        entry.missing_bytes = counts.missing_bytes;
+        entry.excess_bytes = counts.excess_bytes;
+    }
+
+    // Update historical data and calculate ETA
+    for (_osd_id, movement) in osd_movements.iter_mut() {
+        // Add current missing objects count to history
+        movement
+            .missing_objects_history
+            .push(movement.missing_objects);
+        if movement.missing_objects_history.len() > HISTORY_SIZE {
+            movement.missing_objects_history.remove(0);
+        }
+
+        // Add current excess objects count to history
+        movement
+            .excess_objects_history
+            .push(movement.excess_objects);
+        if movement.excess_objects_history.len() > HISTORY_SIZE {
+            movement.excess_objects_history.remove(0);
+        }
+
+        // Calculate ETA using oldest vs current entry (need at least 3 data points)
+        // Only calculate ETA if there are active missing objects being moved
+        if movement.missing_objects_history.len() >= 3 && movement.missing_objects_active > 0 {
+            let oldest_missing = movement.missing_objects_history[0];
+            let current_missing = movement.missing_objects;
+            let time_elapsed =
+                (movement.missing_objects_history.len() - 1) as f64 * interval as f64;
+
+            if oldest_missing > current_missing && time_elapsed > 0.0 {
+                let rate = (oldest_missing - current_missing) as f64 / time_elapsed;
+                movement.incoming_rate = Some(rate);
+                if rate > 0.0 && current_missing > 0 {
+                    let remaining_time_secs = (current_missing as f64 / rate) as u64;
+                    movement.incoming_predicted_time_secs = Some(remaining_time_secs);
+                }
+            }
+        }
+
+        // Only calculate ETA if there are active excess objects being moved
+        if movement.excess_objects_history.len() >= 3 && movement.excess_objects_active > 0 {
+            let oldest_excess = movement.excess_objects_history[0];
+            let current_excess = movement.excess_objects;
+            let time_elapsed = (movement.excess_objects_history.len() - 1) as f64 * interval as f64;
+
+            if oldest_excess > current_excess && time_elapsed > 0.0 {
+                let rate = (oldest_excess - current_excess) as f64 / time_elapsed;
+                movement.outgoing_rate = Some(rate);
+                if rate > 0.0 && current_excess > 0 {
+                    let remaining_time_secs = (current_excess as f64 / rate) as u64;
+                    movement.outgoing_predicted_time_secs = Some(remaining_time_secs);
+                }
+            }
+        }
     }
 
+    // Store back the updated movements
+    state.set_osd_movements(osd_movements.clone());
+
+    osd_movements
+}
+
+/// Sort the rows of the OSD data movement table. The default, most missing
+/// objects first, breaks ties by the excess objects; every order ends with the
+/// OSD ID so rows do not jump between samples.
+pub fn sort_osd_movement_rows(rows: &mut [OsdMovementRow], sort: OsdMovementSort) {
+    let missing = |row: &OsdMovementRow| {
+        row.movement.missing_objects_active + row.movement.missing_objects_waiting
+    };
+    let excess = |row: &OsdMovementRow| {
+        row.movement.excess_objects_active + row.movement.excess_objects_waiting
+    };
+    let by_float = |a: Option<f64>, b: Option<f64>| {
+        a.unwrap_or(f64::NEG_INFINITY)
+            .total_cmp(&b.unwrap_or(f64::NEG_INFINITY))
+    };
+
+    rows.sort_by(|a, b| {
+        let (ma, mb) = (&a.movement, &b.movement);
+        let ordering = match sort.column {
+            OsdMovementSortColumn::Missing => missing(a)
+                .cmp(&missing(b))
+                .then_with(|| excess(a).cmp(&excess(b))),
+            OsdMovementSortColumn::Excess => excess(a)
+                .cmp(&excess(b))
+                .then_with(|| missing(a).cmp(&missing(b))),
+            OsdMovementSortColumn::Osd => ma.osd_id.cmp(&mb.osd_id),
+            OsdMovementSortColumn::Host => a.host.cmp(&b.host),
+            OsdMovementSortColumn::Utilization => by_float(a.utilization, b.utilization),
+            OsdMovementSortColumn::MissingRate => by_float(ma.incoming_rate, mb.incoming_rate),
+            OsdMovementSortColumn::MissingEta => ma
+                .incoming_predicted_time_secs
+                .cmp(&mb.incoming_predicted_time_secs),
+            OsdMovementSortColumn::MissingBytes => ma.missing_bytes.cmp(&mb.missing_bytes),
+            OsdMovementSortColumn::ExcessRate => by_float(ma.outgoing_rate, mb.outgoing_rate),
+            OsdMovementSortColumn::ExcessEta => ma
+                .outgoing_predicted_time_secs
+                .cmp(&mb.outgoing_predicted_time_secs),
+            OsdMovementSortColumn::ExcessBytes => ma.excess_bytes.cmp(&mb.excess_bytes),
+        };
+        let ordering = if sort.descending {
+            ordering.reverse()
+        } else {
+            ordering
+        };
+        ordering.then_with(|| ma.osd_id.cmp(&mb.osd_id))
+    });
+}
+
+/// Misplaced objects per OSD of the current dump, without history or rates
+pub fn count_osd_misplaced_objects(data: &CephPgDump) -> HashMap<u32, OsdDataMovement> {
+    let mut osd_movements: HashMap<u32, OsdDataMovement> = HashMap::new();
+
     // Process ALL PGs to sum up misplaced objects per OSD
-    for current_pg in &current_data.pg_map.pg_stats {
+    for current_pg in &data.pg_map.pg_stats {
         if current_pg.stat_sum.num_objects_misplaced > 0 {
             let current_up_set: HashSet<u32> = current_pg.up.iter().cloned().collect();
             let current_acting_set: HashSet<u32> = current_pg.acting.iter().cloned().collect();
@@ -76,6 +281,12 @@ pub fn calculate_osd_data_movement(
 
             // Use actual misplaced object count from PG stats
             let pg_misplaced_objects = current_pg.stat_sum.num_objects_misplaced;
+            let pg_misplaced_bytes = if current_pg.stat_sum.num_objects > 0 {
+                (current_pg.stat_sum.num_bytes as f64 / current_pg.stat_sum.num_objects as f64
+                    * pg_misplaced_objects as f64) as i64
+            } else {
+                0
+            };
 
             // Determine state for categorization
             let is_actively_moving =
@@ -92,6 +303,7 @@ pub fn calculate_osd_data_movement(
                     });
                 entry.incoming_objects += pg_misplaced_objects;
                 entry.missing_objects += pg_misplaced_objects;
+                entry.missing_bytes += pg_misplaced_bytes;
 
                 if is_actively_moving {
                     entry.missing_objects_active += pg_misplaced_objects;
@@ -110,6 +322,7 @@ pub fn calculate_osd_data_movement(
                     });
                 entry.outgoing_objects += pg_misplaced_objects;
                 entry.excess_objects += pg_misplaced_objects;
+                entry.excess_bytes += pg_misplaced_bytes;
 
                 if is_actively_moving {
                     entry.excess_objects_active += pg_misplaced_objects;
@@ -120,62 +333,6 @@ pub fn calculate_osd_data_movement(
         }
     }
 
-    // Update historical data and calculate ETA
-    for (_osd_id, movement) in osd_movements.iter_mut() {
-        // Add current missing objects count to history
-        movement
-            .missing_objects_history
-            .push(movement.missing_objects);
-        if movement.missing_objects_history.len() > HISTORY_SIZE {
-            movement.missing_objects_history.remove(0);
-        }
-
-        // Add current excess objects count to history
-        movement
-            .excess_objects_history
-            .push(movement.excess_objects);
-        if movement.excess_objects_history.len() > HISTORY_SIZE {
-            movement.excess_objects_history.remove(0);
-        }
-
-        // Calculate ETA using oldest vs current entry (need at least 3 data points)
-        // Only calculate ETA if there are active missing objects being moved
-        if movement.missing_objects_history.len() >= 3 && movement.missing_objects_active > 0 {
-            let oldest_missing = movement.missing_objects_history[0];
-            let current_missing = movement.missing_objects;
-            let time_elapsed =
-                (movement.missing_objects_history.len() - 1) as f64 * interval as f64;
-
-            if oldest_missing > current_missing && time_elapsed > 0.0 {
-                let rate = (oldest_missing - current_missing) as f64 / time_elapsed;
-                movement.incoming_rate = Some(rate);
-                if rate > 0.0 && current_missing > 0 {
-                    let remaining_time_secs = (current_missing as f64 / rate) as u64;
-                    movement.incoming_predicted_time_secs = Some(remaining_time_secs);
-                }
-            }
-        }
-
-        // Only calculate ETA if there are active excess objects being moved
-        if movement.excess_objects_history.len() >= 3 && movement.excess_objects_active > 0 {
-            let oldest_excess = movement.excess_objects_history[0];
-            let current_excess = movement.excess_objects;
-            let time_elapsed = (movement.excess_objects_history.len() - 1) as f64 * interval as f64;
-
-            if oldest_excess > current_excess && time_elapsed > 0.0 {
-                let rate = (oldest_excess - current_excess) as f64 / time_elapsed;
-                movement.outgoing_rate = Some(rate);
-                if rate > 0.0 && current_excess > 0 {
-                    let remaining_time_secs = (current_excess as f64 / rate) as u64;
-                    movement.outgoing_predicted_time_secs = Some(remaining_time_secs);
-                }
-            }
-        }
-    }
-
-    // Store back the updated movements
-    state.set_osd_movements(osd_movements.clone());
-
     osd_movements
 }
 
@@ -257,3 +414,184 @@ pub fn calculate_inconsistent_pg_progress(
 
     pg_progress
 }
+
+/// Number of PGs per state, most common first; equal counts by name, so the
+/// order does not change between samples
+pub fn calculate_pg_states(data: &CephPgDump) -> Vec<(String, usize)> {
+    let mut state_counts: HashMap<&str, usize> = HashMap::new();
+    for pg in &data.pg_map.pg_stats {
+        *state_counts.entry(pg.state.as_str()).or_insert(0) += 1;
+    }
+
+    let mut sorted: Vec<(String, usize)> = state_counts
+        .into_iter()
+        .map(|(state, count)| (state.to_string(), count))
+        .collect();
+    sorted.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    sorted
+}
+
+pub fn calculate_osd_overview(data: &CephPgDump) -> (Vec<OsdOverview>, OsdOverviewMedians) {
+    let mut overviews: Vec<OsdOverview> = data
+        .pg_map
+        .osd_stats
+        .iter()
+        .map(|osd_stat| {
+            // Prefer statfs (bytes), fall back to the legacy kb counters
+            let (total_bytes, used_bytes) = if osd_stat.statfs.total > 0 {
+                (
+                    osd_stat.statfs.total,
+                    osd_stat
+                        .statfs
+                        .total
+                        .saturating_sub(osd_stat.statfs.available),
+                )
+            } else {
+                (osd_stat.kb * 1024, osd_stat.kb_used * 1024)
+            };
+            let utilization = if total_bytes > 0 {
+                used_bytes as f64 / total_bytes as f64 * 100.0
+            } else {
+                0.0
+            };
+
+            OsdOverview {
+                osd_id: osd_stat.osd,
+                utilization,
+                total_bytes,
+                used_bytes,
+                num_pgs: osd_stat.num_pgs,
+                commit_latency_ms: osd_stat.perf_stat.commit_latency_ms,
+                apply_latency_ms: osd_stat.perf_stat.apply_latency_ms,
+                snap_trim_queue_len: osd_stat.snap_trim_queue_len,
+                num_shards_repaired: osd_stat.num_shards_repaired,
+                alerts: osd_stat.alerts.clone(),
+                ..Default::default()
+            }
+        })
+        .collect();
+
+    let medians = OsdOverviewMedians {
+        utilization: median(overviews.iter().map(|o| o.utilization)),
+        num_pgs: median(overviews.iter().map(|o| o.num_pgs as f64)),
+        commit_latency_ms: median(overviews.iter().map(|o| o.commit_latency_ms)),
+        apply_latency_ms: median(overviews.iter().map(|o| o.apply_latency_ms)),
+    };
+
+    for overview in &mut overviews {
+        overview.utilization_outlier =
+            overview.utilization > medians.utilization + UTILIZATION_OUTLIER_MIN_DELTA;
+        overview.pgs_outlier = overview.num_pgs as f64 > medians.num_pgs * PGS_OUTLIER_FACTOR;
+        overview.commit_latency_outlier =
+            is_latency_outlier(overview.commit_latency_ms, medians.commit_latency_ms);
+        overview.apply_latency_outlier =
+            is_latency_outlier(overview.apply_latency_ms, medians.apply_latency_ms);
+    }
+
+    (overviews, medians)
+}
+
+fn is_latency_outlier(value: f64, median: f64) -> bool {
+    value > median * LATENCY_OUTLIER_FACTOR && value - median >= LATENCY_OUTLIER_MIN_DELTA_MS
+}
+
+pub fn median(values: impl Iterator<Item = f64>) -> f64 {
+    let mut values: Vec<f64> = values.collect();
+    if values.is_empty() {
+        return 0.0;
+    }
+    values.sort_by(|a, b| a.total_cmp(b));
+    let mid = values.len() / 2;
+    if values.len().is_multiple_of(2) {
+        (values[mid - 1] + values[mid]) / 2.0
+    } else {
+        values[mid]
+    }
+}
+
+/// Seconds until the remaining objects reach zero at the rate seen over the
+/// history (one sample per interval), or None while the count is not dropping
+pub fn estimate_recovery_eta(history: &[i64], interval: u64) -> Option<u64> {
+    let (oldest, current) = (*history.first()?, *history.last()?);
+    let time_elapsed = (history.len() - 1) as f64 * interval as f64;
+    if current <= 0 || current >= oldest || time_elapsed <= 0.0 {
+        return None;
+    }
+    let rate = (oldest - current) as f64 / time_elapsed;
+    Some((current as f64 / rate) as u64)
+}
+
+pub fn count_inconsistent_pgs(data: &CephPgDump) -> usize {
+    data.pg_map
+        .pg_stats
+        .iter()
+        .filter(|pg| pg.state.contains("inconsistent"))
+        .count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_median() {
+        assert_eq!(median(std::iter::empty()), 0.0);
+        assert_eq!(median([3.0, 1.0, 2.0].into_iter()), 2.0);
+        assert_eq!(median([4.0, 1.0, 3.0, 2.0].into_iter()), 2.5);
+    }
+
+    #[test]
+    fn test_is_latency_outlier() {
+        assert!(is_latency_outlier(40.0, 4.0));
+        assert!(!is_latency_outlier(2.0, 0.5)); // Below the absolute margin
+        assert!(!is_latency_outlier(6.0, 4.0)); // Below the factor
+    }
+
+    #[test]
+    fn test_sort_osd_movement_rows() {
+        let movement = |osd_id, missing, excess| OsdDataMovement {
+            osd_id,
+            missing_objects_waiting: missing,
+            excess_objects_active: excess,
+            ..Default::default()
+        };
+        let movements = [movement(1, 10, 0), movement(2, 0, 30), movement(3, 10, 5)];
+        let mut rows: Vec<OsdMovementRow> = movements
+            .iter()
+            .map(|movement| OsdMovementRow {
+                movement: movement.clone(),
+                host: None,
+                utilization: None,
+            })
+            .collect();
+        let ids = |rows: &[OsdMovementRow]| -> Vec<u32> {
+            rows.iter().map(|row| row.movement.osd_id).collect()
+        };
+
+        sort_osd_movement_rows(&mut rows, OsdMovementSort::default());
+        assert_eq!(ids(&rows), [3, 1, 2]);
+
+        let mut sort = OsdMovementSort {
+            column: OsdMovementSortColumn::Excess,
+            descending: true,
+        };
+        sort_osd_movement_rows(&mut rows, sort);
+        assert_eq!(ids(&rows), [2, 3, 1]);
+
+        sort.column = OsdMovementSortColumn::Osd;
+        sort.descending = false;
+        sort_osd_movement_rows(&mut rows, sort);
+        assert_eq!(ids(&rows), [1, 2, 3]);
+    }
+
+    #[test]
+    fn test_estimate_recovery_eta() {
+        assert_eq!(estimate_recovery_eta(&[], 5), None);
+        assert_eq!(estimate_recovery_eta(&[100], 5), None);
+        assert_eq!(estimate_recovery_eta(&[100, 120], 5), None); // Growing
+        assert_eq!(estimate_recovery_eta(&[100, 100], 5), None); // Stalled
+        assert_eq!(estimate_recovery_eta(&[100, 0], 5), None); // Done
+                                                               // 40 objects in 10 seconds, 60 left
+        assert_eq!(estimate_recovery_eta(&[100, 80, 60], 5), Some(15));
+    }
+}
@@ -44,6 +44,22 @@ pub fn format_bytes_per_second(bytes_per_second: f64) -> String {
     }
 }
 
+pub fn format_bytes(bytes: u64) -> String {
+    let bytes_f = bytes as f64;
+
+    if bytes_f < 1024.0 {
+        format!("{bytes}B")
+    } else if bytes_f < 1024.0 * 1024.0 {
+        format!("{:.1}KB", bytes_f / 1024.0)
+    } else if bytes_f < 1024.0 * 1024.0 * 1024.0 {
+        format!("{:.1}MB", bytes_f / (1024.0 * 1024.0))
+    } else if bytes_f < 1024.0 * 1024.0 * 1024.0 * 1024.0 {
+        format!("{:.1}GB", bytes_f / (1024.0 * 1024.0 * 1024.0))
+    } else {
+        format!("{:.1}TB", bytes_f / (1024.0 * 1024.0 * 1024.0 * 1024.0))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -71,4 +87,12 @@ mod tests {
         assert_eq!(format_bytes_per_second(1572864.0), "1.5MB/s");
         assert_eq!(format_bytes_per_second(1610612736.0), "1.5GB/s");
     }
+
+    #[test]
+    fn test_format_bytes() {
+        assert_eq!(format_bytes(512), "512B");
+        assert_eq!(format_bytes(1536), "1.5KB");
+        assert_eq!(format_bytes(1610612736), "1.5GB");
+        assert_eq!(format_bytes(1649267441664), "1.5TB");
+    }
 }
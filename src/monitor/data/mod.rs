@@ -1,5 +1,9 @@
 pub mod calculator;
 pub mod formatter;
+pub mod network;
+pub mod pgs;
 
 pub use calculator::*;
 pub use formatter::*;
+pub use network::*;
+pub use pgs::*;
@@ -0,0 +1,153 @@
+use crate::common::{CephOsdTree, CephPgDump, HostLinkSummary, SlowLink};
+use std::collections::HashMap;
+
+const UNKNOWN_HOST: &str = "unknown";
+
+/// Map each OSD ID to the name of the CRUSH host bucket that contains it.
+pub fn osd_host_map(tree: &CephOsdTree) -> HashMap<u32, String> {
+    let mut osd_hosts = HashMap::new();
+
+    for node in tree.nodes.iter().filter(|node| node.node_type == "host") {
+        for &child in &node.children {
+            // OSDs have non-negative IDs, buckets negative ones
+            if let Ok(osd_id) = u32::try_from(child) {
+                osd_hosts.insert(osd_id, node.name.clone());
+            }
+        }
+    }
+
+    osd_hosts
+}
+
+/// Collect all heartbeat links whose worst average ping time exceeds the threshold,
+/// slowest first.
+pub fn calculate_slow_links(
+    data: &CephPgDump,
+    osd_hosts: &HashMap<u32, String>,
+    threshold_ms: f64,
+) -> Vec<SlowLink> {
+    let mut slow_links = Vec::new();
+
+    for osd_stat in &data.pg_map.osd_stats {
+        let Some(ping_times) = &osd_stat.network_ping_times else {
+            continue;
+        };
+
+        for peer in ping_times {
+            for interface in &peer.interfaces {
+                let average = &interface.average;
+                let worst = average
+                    .one_min
+                    .max(average.five_min)
+                    .max(average.fifteen_min);
+                if worst <= threshold_ms {
+                    continue;
+                }
+
+                slow_links.push(SlowLink {
+                    from_osd: osd_stat.osd,
+                    to_osd: peer.osd,
+                    from_host: osd_hosts.get(&osd_stat.osd).cloned(),
+                    to_host: osd_hosts.get(&peer.osd).cloned(),
+                    interface: interface.interface.clone(),
+                    one_min_ms: average.one_min,
+                    five_min_ms: average.five_min,
+                    fifteen_min_ms: average.fifteen_min,
+                });
+            }
+        }
+    }
+
+    slow_links.sort_by(|a, b| {
+        worst_average(b)
+            .total_cmp(&worst_average(a))
+            .then_with(|| a.from_osd.cmp(&b.from_osd))
+            .then_with(|| a.to_osd.cmp(&b.to_osd))
+    });
+
+    slow_links
+}
+
+/// Aggregate slow links per (from host, to host) pair, worst pair first.
+pub fn summarize_slow_links_by_host(slow_links: &[SlowLink]) -> Vec<HostLinkSummary> {
+    let mut summaries: HashMap<(String, String), HostLinkSummary> = HashMap::new();
+
+    for link in slow_links {
+        let from_host = link.from_host.as_deref().unwrap_or(UNKNOWN_HOST);
+        let to_host = link.to_host.as_deref().unwrap_or(UNKNOWN_HOST);
+        let worst = worst_average(link);
+
+        let summary = summaries
+            .entry((from_host.to_string(), to_host.to_string()))
+            .or_insert_with(|| HostLinkSummary {
+                from_host: from_host.to_string(),
+                to_host: to_host.to_string(),
+                ..Default::default()
+            });
+        // Running mean of the worst averages of all slow links between the hosts
+        summary.average_ms = (summary.average_ms * summary.slow_links as f64 + worst)
+            / (summary.slow_links + 1) as f64;
+        summary.slow_links += 1;
+        summary.worst_ms = summary.worst_ms.max(worst);
+    }
+
+    let mut summaries: Vec<HostLinkSummary> = summaries.into_values().collect();
+    summaries.sort_by(|a, b| {
+        b.worst_ms
+            .total_cmp(&a.worst_ms)
+            .then_with(|| a.from_host.cmp(&b.from_host))
+            .then_with(|| a.to_host.cmp(&b.to_host))
+    });
+
+    summaries
+}
+
+pub fn worst_average(link: &SlowLink) -> f64 {
+    link.one_min_ms
+        .max(link.five_min_ms)
+        .max(link.fifteen_min_ms)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_osd_host_map() {
+        let tree: CephOsdTree = serde_json::from_str(
+            r#"{"nodes":[
+                {"id":-1,"name":"default","type":"root","children":[-3]},
+                {"id":-3,"name":"node1","type":"host","children":[1,0]},
+                {"id":0,"name":"osd.0","type":"osd","status":"up"},
+                {"id":1,"name":"osd.1","type":"osd","status":"up"}
+            ],"stray":[]}"#,
+        )
+        .unwrap();
+
+        let osd_hosts = osd_host_map(&tree);
+        assert_eq!(osd_hosts.len(), 2);
+        assert_eq!(osd_hosts.get(&0).map(String::as_str), Some("node1"));
+    }
+
+    #[test]
+    fn test_summarize_slow_links_by_host() {
+        let link = |from_osd, to_host: &str, ms| SlowLink {
+            from_osd,
+            from_host: Some("node1".to_string()),
+            to_host: Some(to_host.to_string()),
+            one_min_ms: ms,
+            ..Default::default()
+        };
+        let summaries = summarize_slow_links_by_host(&[
+            link(0, "node2", 3000.0),
+            link(1, "node2", 1000.0),
+            link(0, "node3", 1500.0),
+        ]);
+
+        assert_eq!(summaries.len(), 2);
+        assert_eq!(summaries[0].to_host, "node2");
+        assert_eq!(summaries[0].slow_links, 2);
+        assert_eq!(summaries[0].average_ms, 2000.0);
+        assert_eq!(summaries[1].worst_ms, 1500.0);
+    }
+}
@@ -0,0 +1,248 @@
+use crate::common::{CephPgDump, PgListEntry};
+use crate::monitor::state::{PgSort, PgSortColumn};
+use chrono::DateTime;
+
+/// The rows of the PG browser, in pg dump order
+pub fn calculate_pg_list(data: &CephPgDump) -> Vec<PgListEntry> {
+    let stamp = parse_pg_time(&data.pg_map.stamp);
+    data.pg_map
+        .pg_stats
+        .iter()
+        .map(|pg| {
+            // Older releases leave out acting_primary, which then reads as 0
+            let primary = if pg.acting.contains(&pg.acting_primary) {
+                pg.acting_primary
+            } else {
+                pg.up_primary
+            };
+            let state_secs = stamp
+                .zip(parse_pg_time(&pg.last_change))
+                .map(|(now, since)| now.saturating_sub(since).max(0) as u64);
+
+            PgListEntry {
+                pgid: pg.pgid.clone(),
+                pool: pgid_key(&pg.pgid).0,
+                state: pg.state.clone(),
+                up: pg.up.clone(),
+                acting: pg.acting.clone(),
+                primary,
+                objects: pg.stat_sum.num_objects,
+                misplaced: pg.stat_sum.num_objects_misplaced,
+                degraded: pg.stat_sum.num_objects_degraded,
+                state_secs,
+            }
+        })
+        .collect()
+}
+
+/// `2025-07-20T09:00:00.000000+0000` as a unix timestamp
+fn parse_pg_time(time: &str) -> Option<i64> {
+    DateTime::parse_from_str(time, "%Y-%m-%dT%H:%M:%S%.f%z")
+        .ok()
+        .map(|t| t.timestamp())
+}
+
+/// Pool and placement seed of a PG ID like `3.1f`, so `1.a` sorts before `1.10`
+pub fn pgid_key(pgid: &str) -> (u64, u64) {
+    let (pool, seed) = pgid.split_once('.').unwrap_or((pgid, ""));
+    (
+        pool.parse().unwrap_or(u64::MAX),
+        u64::from_str_radix(seed, 16).unwrap_or(u64::MAX),
+    )
+}
+
+/// One term of the PG filter; all terms must match
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PgFilterTerm {
+    /// `state:backfill_wait` or `state:remapped+backfilling`: all these flags are set
+    State(Vec<String>),
+    /// `osd:12`: the OSD is in the up or the acting set
+    Osd(u32),
+    /// `pool:3`
+    Pool(u64),
+    /// `primary:12`
+    Primary(u32),
+    /// A bare word, found in the PG ID or the state
+    Text(String),
+}
+
+/// Parse a filter like `state:backfill_wait osd:12 pool:3`. Terms without a
+/// value yet are left out, so the filter applies while it is typed.
+pub fn parse_pg_filter(filter: &str) -> Result<Vec<PgFilterTerm>, String> {
+    let mut terms = Vec::new();
+    for word in filter.split_whitespace() {
+        let Some((key, value)) = word.split_once(':') else {
+            terms.push(PgFilterTerm::Text(word.to_lowercase()));
+            continue;
+        };
+        if value.is_empty() {
+            continue;
+        }
+        terms.push(match key {
+            "state" => PgFilterTerm::State(
+                value
+                    .split('+')
+                    .filter(|flag| !flag.is_empty())
+                    .map(str::to_lowercase)
+                    .collect(),
+            ),
+            "osd" => PgFilterTerm::Osd(filter_number(key, value)?),
+            "pool" => PgFilterTerm::Pool(filter_number(key, value)?),
+            "primary" => PgFilterTerm::Primary(filter_number(key, value)?),
+            _ => {
+                return Err(format!(
+                    "unknown filter '{key}:', use state:, osd:, pool: or primary:"
+                ))
+            }
+        });
+    }
+    Ok(terms)
+}
+
+fn filter_number<T: std::str::FromStr>(key: &str, value: &str) -> Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("{key}: needs a number, not '{value}'"))
+}
+
+impl PgFilterTerm {
+    pub fn matches(&self, pg: &PgListEntry) -> bool {
+        match self {
+            PgFilterTerm::State(flags) => flags
+                .iter()
+                .all(|flag| pg.state.split('+').any(|set| set == flag)),
+            PgFilterTerm::Osd(osd) => pg.up.contains(osd) || pg.acting.contains(osd),
+            PgFilterTerm::Pool(pool) => pg.pool == *pool,
+            PgFilterTerm::Primary(osd) => pg.primary == *osd,
+            PgFilterTerm::Text(text) => {
+                pg.pgid.contains(text.as_str()) || pg.state.contains(text.as_str())
+            }
+        }
+    }
+}
+
+pub fn sort_pg_list(pgs: &mut [&PgListEntry], sort: PgSort) {
+    pgs.sort_by(|a, b| compare_pgs(a, b, sort));
+}
+
+/// The order of two PGs in the PG browser
+pub fn compare_pgs(a: &PgListEntry, b: &PgListEntry, sort: PgSort) -> std::cmp::Ordering {
+    let ordering = match sort.column {
+        PgSortColumn::Pgid => pgid_key(&a.pgid).cmp(&pgid_key(&b.pgid)),
+        PgSortColumn::State => a.state.cmp(&b.state),
+        PgSortColumn::Up => a.up.cmp(&b.up),
+        PgSortColumn::Acting => a.acting.cmp(&b.acting),
+        PgSortColumn::Primary => a.primary.cmp(&b.primary),
+        PgSortColumn::Objects => a.objects.cmp(&b.objects),
+        PgSortColumn::Misplaced => a.misplaced.cmp(&b.misplaced),
+        PgSortColumn::Degraded => a.degraded.cmp(&b.degraded),
+        PgSortColumn::TimeInState => a.state_secs.cmp(&b.state_secs),
+    };
+    let ordering = if sort.descending {
+        ordering.reverse()
+    } else {
+        ordering
+    };
+    // Keep a stable order by PG ID for equal values
+    ordering.then_with(|| pgid_key(&a.pgid).cmp(&pgid_key(&b.pgid)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pg(pgid: &str, state: &str, up: &[u32], acting: &[u32]) -> PgListEntry {
+        PgListEntry {
+            pgid: pgid.to_string(),
+            pool: pgid_key(pgid).0,
+            state: state.to_string(),
+            up: up.to_vec(),
+            acting: acting.to_vec(),
+            primary: acting[0],
+            ..Default::default()
+        }
+    }
+
+    fn matching<'a>(pgs: &'a [PgListEntry], filter: &str) -> Vec<&'a str> {
+        let terms = parse_pg_filter(filter).unwrap();
+        pgs.iter()
+            .filter(|pg| terms.iter().all(|term| term.matches(pg)))
+            .map(|pg| pg.pgid.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn test_pg_filter() {
+        let pgs = [
+            pg("1.0", "active+remapped+backfilling", &[0, 1, 2], &[3, 1, 2]),
+            pg(
+                "1.4",
+                "active+remapped+backfill_wait",
+                &[4, 5, 6],
+                &[7, 5, 6],
+            ),
+            pg(
+                "3.1f",
+                "active+remapped+backfill_wait",
+                &[12, 5, 6],
+                &[7, 5, 6],
+            ),
+            pg("3.a", "active+clean", &[12, 0, 1], &[12, 0, 1]),
+        ];
+
+        assert_eq!(matching(&pgs, "").len(), 4);
+        assert_eq!(matching(&pgs, "state:backfill_wait"), ["1.4", "3.1f"]);
+        assert_eq!(matching(&pgs, "state:backfill_wait osd:12"), ["3.1f"]);
+        assert_eq!(matching(&pgs, "state:remapped+backfilling"), ["1.0"]);
+        // Flags match whole, not as a prefix
+        assert!(matching(&pgs, "state:backfill").is_empty());
+        assert_eq!(matching(&pgs, "pool:3"), ["3.1f", "3.a"]);
+        assert_eq!(matching(&pgs, "primary:7"), ["1.4", "3.1f"]);
+        // In the acting set only
+        assert_eq!(matching(&pgs, "osd:3"), ["1.0"]);
+        assert_eq!(matching(&pgs, "clean"), ["3.a"]);
+        // A term still being typed does not filter yet
+        assert_eq!(matching(&pgs, "pool:3 osd:").len(), 2);
+
+        assert!(parse_pg_filter("osd:x").is_err());
+        assert!(parse_pg_filter("host:a").is_err());
+    }
+
+    #[test]
+    fn test_sort_pg_list() {
+        let mut a = pg("1.10", "active+clean", &[0], &[0]);
+        a.misplaced = 5;
+        let mut b = pg("1.a", "active+remapped", &[1], &[1]);
+        b.misplaced = 5;
+        let c = pg("2.0", "active+clean", &[2], &[2]);
+        let mut pgs = vec![&a, &b, &c];
+
+        sort_pg_list(&mut pgs, PgSort::default());
+        let ids: Vec<_> = pgs.iter().map(|pg| pg.pgid.as_str()).collect();
+        assert_eq!(ids, ["1.a", "1.10", "2.0"]);
+
+        sort_pg_list(
+            &mut pgs,
+            PgSort {
+                column: PgSortColumn::Misplaced,
+                descending: true,
+            },
+        );
+        let ids: Vec<_> = pgs.iter().map(|pg| pg.pgid.as_str()).collect();
+        assert_eq!(ids, ["1.a", "1.10", "2.0"]);
+    }
+
+    #[test]
+    fn test_calculate_pg_list() {
+        let path = format!("{}/tests/fixtures/pg_dump.json", env!("CARGO_MANIFEST_DIR"));
+        let data: CephPgDump =
+            serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap();
+        let pgs = calculate_pg_list(&data);
+        assert_eq!(pgs.len(), 40);
+        assert_eq!(pgs[0].pgid, "1.0");
+        assert_eq!(pgs[0].pool, 1);
+        assert_eq!(pgs[0].primary, 3);
+        assert_eq!(pgs[0].misplaced, 200);
+        assert_eq!(pgs[0].state_secs, Some(0));
+    }
+}
@@ -0,0 +1,498 @@
+//! Typed events from the difference between two consecutive pg dumps.
+
+use crate::common::CephPgDump;
+use crate::monitor::data::format_time;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap};
+
+/// Minutes without progress after which a recovery counts as stalled
+pub const DEFAULT_STALL_MINUTES: u64 = 15;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EventKind {
+    RecoveryStarted,
+    RecoveryFinished,
+    RecoveryStalled,
+    InconsistentPg,
+    PgRepaired,
+    UnfoundObjects,
+    OsdDown,
+    OsdUp,
+    PgStateChanged,
+    PgRemapped,
+}
+
+impl EventKind {
+    pub fn label(self) -> &'static str {
+        match self {
+            EventKind::RecoveryStarted => "recovery_started",
+            EventKind::RecoveryFinished => "recovery_finished",
+            EventKind::RecoveryStalled => "recovery_stalled",
+            EventKind::InconsistentPg => "inconsistent_pg",
+            EventKind::PgRepaired => "pg_repaired",
+            EventKind::UnfoundObjects => "unfound_objects",
+            EventKind::OsdDown => "osd_down",
+            EventKind::OsdUp => "osd_up",
+            EventKind::PgStateChanged => "pg_state_changed",
+            EventKind::PgRemapped => "pg_remapped",
+        }
+    }
+
+    /// Whether alert sinks get the event without asking for it; the per-PG
+    /// changes are too many for that and are meant for the event log
+    pub fn is_alert(self) -> bool {
+        !matches!(self, EventKind::PgStateChanged | EventKind::PgRemapped)
+    }
+
+    pub fn severity(self) -> Severity {
+        match self {
+            EventKind::RecoveryStarted
+            | EventKind::RecoveryFinished
+            | EventKind::PgRepaired
+            | EventKind::OsdUp
+            | EventKind::PgStateChanged
+            | EventKind::PgRemapped => Severity::Info,
+            EventKind::RecoveryStalled | EventKind::OsdDown => Severity::Warning,
+            EventKind::InconsistentPg | EventKind::UnfoundObjects => Severity::Critical,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Info,
+    Warning,
+    Critical,
+}
+
+impl Severity {
+    pub fn label(self) -> &'static str {
+        match self {
+            Severity::Info => "info",
+            Severity::Warning => "warning",
+            Severity::Critical => "critical",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Event {
+    #[serde(rename = "event")]
+    pub kind: EventKind,
+    pub severity: Severity,
+    /// The PG or OSD the event is about, e.g. `1.2f` or `osd.3`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub subject: Option<String>,
+    pub message: String,
+    /// Unix seconds
+    pub time: i64,
+}
+
+impl Event {
+    /// One line for the event log file, e.g.
+    /// `2025-07-20 09:00:00 UTC [prod] info pg_state_changed 1.2f PG 1.2f ...`
+    pub fn format_line(&self, cluster: Option<&str>) -> String {
+        let time = chrono::DateTime::from_timestamp(self.time, 0).map_or_else(
+            || self.time.to_string(),
+            |t| t.format("%Y-%m-%d %H:%M:%S UTC").to_string(),
+        );
+        let cluster = cluster.map_or(String::new(), |name| format!(" [{name}]"));
+        format!(
+            "{time}{cluster} {:<8} {:<17} {:<8} {}",
+            self.severity.label(),
+            self.kind.label(),
+            self.subject.as_deref().unwrap_or("-"),
+            self.message
+        )
+    }
+
+    /// Whether every word of `filter` occurs in the event, ignoring case
+    pub fn matches(&self, filter: &str) -> bool {
+        let text = format!(
+            "{} {} {} {}",
+            self.severity.label(),
+            self.kind.label(),
+            self.subject.as_deref().unwrap_or_default(),
+            self.message
+        )
+        .to_lowercase();
+        filter
+            .to_lowercase()
+            .split_whitespace()
+            .all(|word| text.contains(word))
+    }
+
+    fn new(kind: EventKind, subject: Option<String>, message: String, time: i64) -> Self {
+        Self {
+            kind,
+            severity: kind.severity(),
+            subject,
+            message,
+            time,
+        }
+    }
+}
+
+/// Remembers what it takes to notice a stalled recovery across snapshots
+#[derive(Debug, Clone)]
+pub struct EventDetector {
+    stall_secs: i64,
+    /// When the current recovery started
+    recovery_start: Option<i64>,
+    /// Fewest objects left so far in the current recovery, and when
+    best: Option<(i64, i64)>,
+    stalled: bool,
+}
+
+impl Default for EventDetector {
+    fn default() -> Self {
+        Self::new(DEFAULT_STALL_MINUTES)
+    }
+}
+
+impl EventDetector {
+    pub fn new(stall_minutes: u64) -> Self {
+        Self {
+            stall_secs: stall_minutes as i64 * 60,
+            recovery_start: None,
+            best: None,
+            stalled: false,
+        }
+    }
+
+    /// Events between the previous and the current pg dump taken at `now`.
+    /// Without a previous dump there is nothing to compare, so conditions that
+    /// were already present when monitoring started are not reported.
+    pub fn detect(
+        &mut self,
+        previous: Option<&CephPgDump>,
+        current: &CephPgDump,
+        now: i64,
+    ) -> Vec<Event> {
+        let remaining = remaining_objects(current);
+        let mut events = Vec::new();
+
+        let Some(previous) = previous else {
+            if remaining > 0 {
+                self.recovery_start = Some(now);
+                self.best = Some((remaining, now));
+            }
+            return events;
+        };
+
+        let previous_remaining = remaining_objects(previous);
+        if previous_remaining == 0 && remaining > 0 {
+            self.recovery_start = Some(now);
+            events.push(Event::new(
+                EventKind::RecoveryStarted,
+                None,
+                format!("Recovery started: {remaining} objects to recover"),
+                now,
+            ));
+        } else if previous_remaining > 0 && remaining == 0 {
+            let message = match self.recovery_start {
+                Some(start) => format!(
+                    "Recovery finished after {}",
+                    format_time((now - start).max(0) as u64)
+                ),
+                None => "Recovery finished".to_string(),
+            };
+            events.push(Event::new(EventKind::RecoveryFinished, None, message, now));
+        }
+        events.extend(self.check_stall(remaining, now));
+
+        let previous_unfound = previous.pg_map.pg_stats_sum.stat_sum.num_objects_unfound;
+        let unfound = current.pg_map.pg_stats_sum.stat_sum.num_objects_unfound;
+        if previous_unfound == 0 && unfound > 0 {
+            events.push(Event::new(
+                EventKind::UnfoundObjects,
+                None,
+                format!("{unfound} unfound objects"),
+                now,
+            ));
+        }
+
+        let (before, after) = (inconsistent_pgs(previous), inconsistent_pgs(current));
+        for pgid in after.difference(&before) {
+            events.push(Event::new(
+                EventKind::InconsistentPg,
+                Some(pgid.to_string()),
+                format!("PG {pgid} is inconsistent"),
+                now,
+            ));
+        }
+        for pgid in before.difference(&after) {
+            events.push(Event::new(
+                EventKind::PgRepaired,
+                Some(pgid.to_string()),
+                format!("PG {pgid} is no longer inconsistent"),
+                now,
+            ));
+        }
+
+        // OSDs that stop reporting statistics are down
+        let (before, after) = (reporting_osds(previous), reporting_osds(current));
+        for osd in before.difference(&after) {
+            events.push(Event::new(
+                EventKind::OsdDown,
+                Some(format!("osd.{osd}")),
+                format!("osd.{osd} is down"),
+                now,
+            ));
+        }
+        for osd in after.difference(&before) {
+            events.push(Event::new(
+                EventKind::OsdUp,
+                Some(format!("osd.{osd}")),
+                format!("osd.{osd} is up"),
+                now,
+            ));
+        }
+
+        events.extend(pg_changes(previous, current, now));
+        events
+    }
+
+    /// A recovery is stalled when the objects left have not dropped below
+    /// their lowest count for the stall time; reported once until it moves
+    fn check_stall(&mut self, remaining: i64, now: i64) -> Option<Event> {
+        if remaining == 0 {
+            self.recovery_start = None;
+            self.best = None;
+            self.stalled = false;
+            return None;
+        }
+        match self.best {
+            Some((best, _)) if remaining >= best => {}
+            _ => {
+                self.best = Some((remaining, now));
+                self.stalled = false;
+            }
+        }
+        let (_, since) = self.best?;
+        if self.stalled || now - since < self.stall_secs {
+            return None;
+        }
+        self.stalled = true;
+        Some(Event::new(
+            EventKind::RecoveryStalled,
+            None,
+            format!(
+                "Recovery stalled: {remaining} objects left, no progress for {}",
+                format_time((now - since) as u64)
+            ),
+            now,
+        ))
+    }
+}
+
+/// PGs that changed state or moved to other OSDs; created and removed PGs
+/// (splits and merges) show as a change from or to `none`
+fn pg_changes(previous: &CephPgDump, current: &CephPgDump, now: i64) -> Vec<Event> {
+    let before: HashMap<&str, _> = previous
+        .pg_map
+        .pg_stats
+        .iter()
+        .map(|pg| (pg.pgid.as_str(), pg))
+        .collect();
+    let mut events = Vec::new();
+    for pg in &current.pg_map.pg_stats {
+        let old = before.get(pg.pgid.as_str());
+        let old_state = old.map_or("none", |old| old.state.as_str());
+        if old_state != pg.state {
+            events.push(Event::new(
+                EventKind::PgStateChanged,
+                Some(pg.pgid.clone()),
+                format!("PG {} {old_state} -> {}", pg.pgid, pg.state),
+                now,
+            ));
+        }
+        if let Some(old) = old.filter(|old| old.up != pg.up) {
+            events.push(Event::new(
+                EventKind::PgRemapped,
+                Some(pg.pgid.clone()),
+                format!("PG {} up {:?} -> {:?}", pg.pgid, old.up, pg.up),
+                now,
+            ));
+        }
+    }
+    let after: BTreeSet<&str> = current
+        .pg_map
+        .pg_stats
+        .iter()
+        .map(|pg| pg.pgid.as_str())
+        .collect();
+    for pg in &previous.pg_map.pg_stats {
+        if !after.contains(pg.pgid.as_str()) {
+            events.push(Event::new(
+                EventKind::PgStateChanged,
+                Some(pg.pgid.clone()),
+                format!("PG {} {} -> none", pg.pgid, pg.state),
+                now,
+            ));
+        }
+    }
+    events
+}
+
+fn remaining_objects(data: &CephPgDump) -> i64 {
+    let stats_sum = &data.pg_map.pg_stats_sum.stat_sum;
+    stats_sum.num_objects_degraded + stats_sum.num_objects_misplaced + stats_sum.num_objects_unfound
+}
+
+fn inconsistent_pgs(data: &CephPgDump) -> BTreeSet<&str> {
+    data.pg_map
+        .pg_stats
+        .iter()
+        .filter(|pg| pg.state.contains("inconsistent"))
+        .map(|pg| pg.pgid.as_str())
+        .collect()
+}
+
+fn reporting_osds(data: &CephPgDump) -> BTreeSet<u32> {
+    data.pg_map.osd_stats.iter().map(|osd| osd.osd).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pg_dump() -> CephPgDump {
+        serde_json::from_str(include_str!(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/tests/fixtures/pg_dump.json"
+        )))
+        .unwrap()
+    }
+
+    fn clean(mut data: CephPgDump) -> CephPgDump {
+        for pg in &mut data.pg_map.pg_stats {
+            pg.state = "active+clean".to_string();
+            pg.stat_sum.num_objects_misplaced = 0;
+        }
+        data.pg_map.pg_stats_sum.stat_sum.num_objects_misplaced = 0;
+        data
+    }
+
+    /// The kinds of the events that go to alert sinks
+    fn kinds(events: &[Event]) -> Vec<EventKind> {
+        events
+            .iter()
+            .map(|event| event.kind)
+            .filter(|kind| kind.is_alert())
+            .collect()
+    }
+
+    #[test]
+    fn test_first_snapshot_has_no_events() {
+        let mut detector = EventDetector::default();
+        assert!(detector.detect(None, &pg_dump(), 0).is_empty());
+    }
+
+    #[test]
+    fn test_recovery_and_repair() {
+        let mut detector = EventDetector::default();
+        let (recovering, clean) = (pg_dump(), clean(pg_dump()));
+        detector.detect(None, &clean, 0);
+
+        let events = detector.detect(Some(&clean), &recovering, 60);
+        assert_eq!(
+            kinds(&events),
+            vec![EventKind::RecoveryStarted, EventKind::InconsistentPg]
+        );
+        let inconsistent = &events[1];
+        assert_eq!(inconsistent.subject.as_deref(), Some("1.14"));
+        assert_eq!(inconsistent.severity, Severity::Critical);
+
+        let events = detector.detect(Some(&recovering), &clean, 180);
+        assert_eq!(
+            kinds(&events),
+            vec![EventKind::RecoveryFinished, EventKind::PgRepaired]
+        );
+        assert_eq!(events[0].message, "Recovery finished after 2m00s");
+    }
+
+    #[test]
+    fn test_pg_changes() {
+        let previous = pg_dump();
+        let mut current = pg_dump();
+        current.pg_map.pg_stats[0].state = "active+clean".to_string();
+        current.pg_map.pg_stats[1].up = vec![1, 2, 5];
+        current.pg_map.pg_stats.pop();
+
+        let events = pg_changes(&previous, &current, 0);
+        let messages: Vec<&str> = events.iter().map(|e| e.message.as_str()).collect();
+        assert_eq!(
+            messages,
+            vec![
+                "PG 1.0 active+remapped+backfilling -> active+clean",
+                "PG 1.1 up [1, 2, 3] -> [1, 2, 5]",
+                "PG 1.27 active+clean -> none",
+            ]
+        );
+        assert!(events.iter().all(|e| !e.kind.is_alert()));
+    }
+
+    #[test]
+    fn test_format_and_filter() {
+        let event = Event::new(
+            EventKind::PgStateChanged,
+            Some("1.0".to_string()),
+            "PG 1.0 active+remapped+backfilling -> active+clean".to_string(),
+            1753002000,
+        );
+        assert_eq!(
+            event.format_line(Some("prod")),
+            "2025-07-20 09:00:00 UTC [prod] info     pg_state_changed  1.0      \
+             PG 1.0 active+remapped+backfilling -> active+clean"
+        );
+        assert!(event.matches(""));
+        assert!(event.matches("Active+Clean 1.0"));
+        assert!(event.matches("pg_state"));
+        assert!(!event.matches("backfill_toofull"));
+    }
+
+    #[test]
+    fn test_unfound_and_osd_down() {
+        let mut detector = EventDetector::default();
+        let previous = pg_dump();
+        let mut current = pg_dump();
+        current.pg_map.pg_stats_sum.stat_sum.num_objects_unfound = 3;
+        let down = current.pg_map.osd_stats.remove(0).osd;
+        detector.detect(None, &previous, 0);
+
+        let events = detector.detect(Some(&previous), &current, 5);
+        assert_eq!(
+            kinds(&events),
+            vec![EventKind::UnfoundObjects, EventKind::OsdDown]
+        );
+        assert_eq!(events[0].message, "3 unfound objects");
+        assert_eq!(events[1].subject, Some(format!("osd.{down}")));
+
+        let events = detector.detect(Some(&current), &previous, 10);
+        assert_eq!(kinds(&events), vec![EventKind::OsdUp]);
+    }
+
+    #[test]
+    fn test_recovery_stalled() {
+        let mut detector = EventDetector::new(10);
+        let data = pg_dump();
+        detector.detect(None, &data, 0);
+        assert!(detector.detect(Some(&data), &data, 300).is_empty());
+
+        let events = detector.detect(Some(&data), &data, 600);
+        assert_eq!(kinds(&events), vec![EventKind::RecoveryStalled]);
+        // Only once while it stays stalled
+        assert!(detector.detect(Some(&data), &data, 900).is_empty());
+
+        // Progress resets the timer
+        let mut progress = pg_dump();
+        progress.pg_map.pg_stats_sum.stat_sum.num_objects_misplaced -= 100;
+        assert!(detector.detect(Some(&data), &progress, 960).is_empty());
+        assert!(detector.detect(Some(&progress), &progress, 1500).is_empty());
+        let events = detector.detect(Some(&progress), &progress, 1560);
+        assert_eq!(kinds(&events), vec![EventKind::RecoveryStalled]);
+    }
+}
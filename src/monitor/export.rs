@@ -0,0 +1,391 @@
+use crate::Result;
+use ratatui::backend::{Backend, TestBackend};
+use ratatui::buffer::{Buffer, Cell};
+use ratatui::style::{Color, Modifier};
+use ratatui::Terminal;
+use std::fs::File;
+use std::io::{ErrorKind, Write};
+use std::path::Path;
+
+/// Width and height of a cell in the SVG, for a 15px monospace font
+const SVG_CELL_WIDTH: f64 = 9.0;
+const SVG_CELL_HEIGHT: f64 = 18.0;
+const SVG_FOREGROUND: &str = "#d0d0d0";
+const SVG_BACKGROUND: &str = "#1c1c1c";
+
+/// Draw the screen once more into memory, at the size of `terminal`, and save
+/// it as plain text, ANSI text and SVG in the current directory. Returns what
+/// was saved, or why not, for the notice on screen.
+pub fn export_screen<B: Backend>(
+    terminal: &Terminal<B>,
+    draw: impl FnOnce(&mut Terminal<TestBackend>) -> Result<()>,
+) -> String {
+    let saved = terminal
+        .size()
+        .map_err(Into::into)
+        .and_then(|size| Ok(Terminal::new(TestBackend::new(size.width, size.height))?))
+        .and_then(|mut screen| {
+            draw(&mut screen)?;
+            // To the millisecond, so that exports rarely need the counter
+            let stamp = chrono::Utc::now().format("%Y%m%d-%H%M%S-%3f");
+            save_screen(
+                screen.backend().buffer(),
+                Path::new("."),
+                &format!("ceph-doctor-screen-{stamp}"),
+            )
+        });
+    match saved {
+        Ok(message) => message,
+        Err(e) => format!("Export failed: {e}"),
+    }
+}
+
+/// Save the three files as `base`, or with a counter added to `base` when an
+/// earlier export took that name already
+fn save_screen(buffer: &Buffer, dir: &Path, base: &str) -> Result<String> {
+    let mut name = base.to_string();
+    for count in 1.. {
+        match write_new(&dir.join(format!("{name}.txt")), &to_text(buffer)) {
+            Ok(()) => break,
+            Err(e) if e.kind() == ErrorKind::AlreadyExists => name = format!("{base}-{count}"),
+            Err(e) => return Err(format!("cannot write {name}.txt: {e}").into()),
+        }
+    }
+    for (extension, contents) in [("ansi", to_ansi(buffer)), ("svg", to_svg(buffer))] {
+        write_new(&dir.join(format!("{name}.{extension}")), &contents)
+            .map_err(|e| format!("cannot write {name}.{extension}: {e}"))?;
+    }
+    Ok(format!("Saved the screen to {name}.{{txt,ansi,svg}}"))
+}
+
+/// Write a file that does not exist yet
+fn write_new(path: &Path, contents: &str) -> std::io::Result<()> {
+    File::options()
+        .write(true)
+        .create_new(true)
+        .open(path)?
+        .write_all(contents.as_bytes())
+}
+
+/// The cells of each line, without the unstyled blanks at its end
+fn lines(buffer: &Buffer) -> impl Iterator<Item = &[Cell]> {
+    let width = buffer.area.width as usize;
+    buffer.content.chunks(width.max(1)).map(|line| {
+        let end = line
+            .iter()
+            .rposition(|cell| cell.symbol() != " " || !is_plain(cell))
+            .map_or(0, |last| last + 1);
+        &line[..end]
+    })
+}
+
+fn is_plain(cell: &Cell) -> bool {
+    cell.fg == Color::Reset && cell.bg == Color::Reset && cell.modifier.is_empty()
+}
+
+/// The characters only
+pub fn to_text(buffer: &Buffer) -> String {
+    let mut text = String::new();
+    for line in lines(buffer) {
+        line.iter()
+            .filter(|cell| !cell.skip)
+            .for_each(|cell| text.push_str(cell.symbol()));
+        text.push('\n');
+    }
+    text
+}
+
+/// The characters with SGR escape sequences for colours and modifiers, for
+/// `cat` or `less -R`
+pub fn to_ansi(buffer: &Buffer) -> String {
+    let mut text = String::new();
+    for line in lines(buffer) {
+        let mut current = "0".to_string();
+        for cell in line.iter().filter(|cell| !cell.skip) {
+            let sgr = sgr(cell);
+            if sgr != current {
+                text.push_str(&format!("\x1b[{sgr}m"));
+                current = sgr;
+            }
+            text.push_str(cell.symbol());
+        }
+        if current != "0" {
+            text.push_str("\x1b[0m");
+        }
+        text.push('\n');
+    }
+    text
+}
+
+/// The parameters of the escape sequence that sets the style of a cell from scratch
+fn sgr(cell: &Cell) -> String {
+    let mut codes = vec!["0".to_string()];
+    for (modifier, code) in [
+        (Modifier::BOLD, "1"),
+        (Modifier::DIM, "2"),
+        (Modifier::ITALIC, "3"),
+        (Modifier::UNDERLINED, "4"),
+        (Modifier::SLOW_BLINK, "5"),
+        (Modifier::RAPID_BLINK, "6"),
+        (Modifier::REVERSED, "7"),
+        (Modifier::HIDDEN, "8"),
+        (Modifier::CROSSED_OUT, "9"),
+    ] {
+        if cell.modifier.contains(modifier) {
+            codes.push(code.to_string());
+        }
+    }
+    codes.extend(color_sgr(cell.fg, 30));
+    codes.extend(color_sgr(cell.bg, 40));
+    codes.join(";")
+}
+
+/// `base` is 30 for the foreground and 40 for the background
+fn color_sgr(color: Color, base: u8) -> Option<String> {
+    let named = |index: u8| {
+        if index < 8 {
+            base + index
+        } else {
+            base + 60 + index - 8
+        }
+    };
+    Some(match color {
+        Color::Reset => return None,
+        Color::Indexed(index) => format!("{};5;{index}", base + 8),
+        Color::Rgb(r, g, b) => format!("{};2;{r};{g};{b}", base + 8),
+        color => named(palette_index(color)?).to_string(),
+    })
+}
+
+/// Index of a named colour in the 16 colour palette
+fn palette_index(color: Color) -> Option<u8> {
+    Some(match color {
+        Color::Black => 0,
+        Color::Red => 1,
+        Color::Green => 2,
+        Color::Yellow => 3,
+        Color::Blue => 4,
+        Color::Magenta => 5,
+        Color::Cyan => 6,
+        Color::Gray => 7,
+        Color::DarkGray => 8,
+        Color::LightRed => 9,
+        Color::LightGreen => 10,
+        Color::LightYellow => 11,
+        Color::LightBlue => 12,
+        Color::LightMagenta => 13,
+        Color::LightCyan => 14,
+        Color::White => 15,
+        Color::Indexed(index) => index,
+        Color::Reset | Color::Rgb(..) => return None,
+    })
+}
+
+/// The colour as the xterm palette shows it; None for the default colour
+fn hex(color: Color) -> Option<String> {
+    const BASIC: [(u8, u8, u8); 16] = [
+        (0x00, 0x00, 0x00),
+        (0xcd, 0x00, 0x00),
+        (0x00, 0xcd, 0x00),
+        (0xcd, 0xcd, 0x00),
+        (0x00, 0x00, 0xee),
+        (0xcd, 0x00, 0xcd),
+        (0x00, 0xcd, 0xcd),
+        (0xe5, 0xe5, 0xe5),
+        (0x7f, 0x7f, 0x7f),
+        (0xff, 0x00, 0x00),
+        (0x00, 0xff, 0x00),
+        (0xff, 0xff, 0x00),
+        (0x5c, 0x5c, 0xff),
+        (0xff, 0x00, 0xff),
+        (0x00, 0xff, 0xff),
+        (0xff, 0xff, 0xff),
+    ];
+    const CUBE: [u8; 6] = [0, 95, 135, 175, 215, 255];
+    let (r, g, b) = match color {
+        Color::Rgb(r, g, b) => (r, g, b),
+        color => match palette_index(color)? {
+            index @ 0..16 => BASIC[index as usize],
+            index @ 16..232 => {
+                let index = index - 16;
+                (
+                    CUBE[(index / 36) as usize],
+                    CUBE[(index / 6 % 6) as usize],
+                    CUBE[(index % 6) as usize],
+                )
+            }
+            index => {
+                let level = 8 + 10 * (index - 232);
+                (level, level, level)
+            }
+        },
+    };
+    Some(format!("#{r:02x}{g:02x}{b:02x}"))
+}
+
+/// The foreground and background colour of a cell, with reversed cells swapped
+fn svg_colors(cell: &Cell) -> (String, Option<String>) {
+    let fg = hex(cell.fg);
+    let bg = hex(cell.bg);
+    if cell.modifier.contains(Modifier::REVERSED) {
+        (
+            bg.unwrap_or_else(|| SVG_BACKGROUND.to_string()),
+            Some(fg.unwrap_or_else(|| SVG_FOREGROUND.to_string())),
+        )
+    } else {
+        (fg.unwrap_or_else(|| SVG_FOREGROUND.to_string()), bg)
+    }
+}
+
+/// A picture of the screen: one text element per run of equally styled cells,
+/// stretched to the width of its cells so it lines up in any monospace font
+pub fn to_svg(buffer: &Buffer) -> String {
+    let width = buffer.area.width as f64 * SVG_CELL_WIDTH;
+    let height = buffer.area.height as f64 * SVG_CELL_HEIGHT;
+    let mut backgrounds = String::new();
+    let mut texts = String::new();
+
+    for (y, line) in lines(buffer).enumerate() {
+        let top = y as f64 * SVG_CELL_HEIGHT;
+        let mut x = 0;
+        while x < line.len() {
+            let (fg, bg) = svg_colors(&line[x]);
+            let modifier = line[x].modifier;
+            let run = line[x..]
+                .iter()
+                .take_while(|cell| svg_colors(cell) == (fg.clone(), bg.clone()))
+                .take_while(|cell| cell.modifier == modifier)
+                .count();
+            let cells = &line[x..x + run];
+            let left = x as f64 * SVG_CELL_WIDTH;
+            let run_width = run as f64 * SVG_CELL_WIDTH;
+
+            if let Some(bg) = &bg {
+                backgrounds.push_str(&format!(
+                    "<rect x=\"{left}\" y=\"{top}\" width=\"{run_width}\" height=\"{SVG_CELL_HEIGHT}\" fill=\"{bg}\"/>\n"
+                ));
+            }
+            let symbols: String = cells
+                .iter()
+                .filter(|cell| !cell.skip)
+                .map(Cell::symbol)
+                .collect();
+            if !symbols.trim().is_empty() {
+                let mut attributes = format!("fill=\"{fg}\"");
+                if modifier.contains(Modifier::BOLD) {
+                    attributes.push_str(" font-weight=\"bold\"");
+                }
+                if modifier.contains(Modifier::ITALIC) {
+                    attributes.push_str(" font-style=\"italic\"");
+                }
+                if modifier.contains(Modifier::UNDERLINED) {
+                    attributes.push_str(" text-decoration=\"underline\"");
+                }
+                texts.push_str(&format!(
+                    "<text x=\"{left}\" y=\"{}\" textLength=\"{run_width}\" lengthAdjust=\"spacingAndGlyphs\" {attributes}>{}</text>\n",
+                    top + SVG_CELL_HEIGHT * 0.75,
+                    escape_xml(&symbols)
+                ));
+            }
+            x += run;
+        }
+    }
+
+    format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{width}\" height=\"{height}\" viewBox=\"0 0 {width} {height}\">\n\
+         <rect width=\"100%\" height=\"100%\" fill=\"{SVG_BACKGROUND}\"/>\n\
+         {backgrounds}\
+         <g font-family=\"DejaVu Sans Mono, Menlo, Consolas, monospace\" font-size=\"15px\" xml:space=\"preserve\">\n\
+         {texts}\
+         </g>\n\
+         </svg>\n"
+    )
+}
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ratatui::layout::Rect;
+    use ratatui::style::Style;
+
+    fn buffer() -> Buffer {
+        let mut buffer = Buffer::empty(Rect::new(0, 0, 12, 2));
+        buffer.set_string(0, 0, "OK", Style::default().fg(Color::Green));
+        buffer.set_string(
+            3,
+            0,
+            "<ERR>",
+            Style::default()
+                .fg(Color::Indexed(166))
+                .add_modifier(Modifier::BOLD),
+        );
+        buffer.set_string(
+            0,
+            1,
+            "Tab",
+            Style::default().add_modifier(Modifier::REVERSED),
+        );
+        buffer
+    }
+
+    #[test]
+    fn test_save_screen() {
+        let dir = std::env::temp_dir().join(format!("ceph-doctor-export-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        // A second export under the same name does not overwrite the first
+        let saved = save_screen(&buffer(), &dir, "screen").unwrap();
+        assert_eq!(saved, "Saved the screen to screen.{txt,ansi,svg}");
+        std::fs::write(dir.join("screen.txt"), "first").unwrap();
+        let saved = save_screen(&buffer(), &dir, "screen").unwrap();
+        assert_eq!(saved, "Saved the screen to screen-1.{txt,ansi,svg}");
+        assert_eq!(
+            std::fs::read_to_string(dir.join("screen.txt")).unwrap(),
+            "first"
+        );
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 6);
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        // Why the export failed goes into the notice
+        let error = save_screen(&buffer(), &dir, "screen").unwrap_err();
+        assert!(error
+            .to_string()
+            .starts_with("cannot write screen.txt: No such file or directory"));
+    }
+
+    #[test]
+    fn test_to_text() {
+        assert_eq!(to_text(&buffer()), "OK <ERR>\nTab\n");
+    }
+
+    #[test]
+    fn test_to_ansi() {
+        assert_eq!(
+            to_ansi(&buffer()),
+            "\x1b[0;32mOK\x1b[0m \x1b[0;1;38;5;166m<ERR>\x1b[0m\n\x1b[0;7mTab\x1b[0m\n"
+        );
+    }
+
+    #[test]
+    fn test_to_svg() {
+        let svg = to_svg(&buffer());
+        assert!(svg
+            .starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"108\" height=\"36\""));
+        assert!(svg.contains(
+            "<text x=\"0\" y=\"13.5\" textLength=\"18\" lengthAdjust=\"spacingAndGlyphs\" fill=\"#00cd00\">OK</text>"
+        ));
+        // Indexed colour 166 is orange; markup in the text is escaped
+        assert!(svg.contains("fill=\"#d75f00\" font-weight=\"bold\">&lt;ERR&gt;</text>"));
+        // Reversed cells swap the colours
+        assert!(
+            svg.contains("<rect x=\"0\" y=\"18\" width=\"27\" height=\"18\" fill=\"#d0d0d0\"/>")
+        );
+        assert!(svg.contains("fill=\"#1c1c1c\">Tab</text>"));
+    }
+}
@@ -0,0 +1,116 @@
+use crate::common::{CephOsdTree, CephPgDump, CephStatus};
+use crate::error::CephError;
+use crate::monitor::source::{CephQuery, DataSource};
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+
+/// Result of one background fetch cycle.
+pub struct FetchResult {
+    pub pg_dump: Result<CephPgDump, CephError>,
+    /// Only requested when host names are needed (network view)
+    pub osd_tree: Option<Result<CephOsdTree, CephError>>,
+    /// Only requested when the cluster health is needed (multi-cluster summary)
+    pub status: Option<Result<CephStatus, CephError>>,
+}
+
+/// The optional queries to run alongside `pg dump` in one fetch cycle.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FetchRequest {
+    pub osd_tree: bool,
+    pub status: bool,
+}
+
+/// Runs cluster queries in a background task so the UI stays responsive.
+/// At most one fetch is in flight; results are delivered over a channel.
+pub struct Fetcher {
+    source: DataSource,
+    timeout: Duration,
+    sender: mpsc::UnboundedSender<FetchResult>,
+    receiver: mpsc::UnboundedReceiver<FetchResult>,
+    in_flight: Option<(Instant, JoinHandle<()>)>,
+}
+
+impl Fetcher {
+    pub fn new(source: DataSource, timeout: Duration) -> Self {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        Self {
+            source,
+            timeout,
+            sender,
+            receiver,
+            in_flight: None,
+        }
+    }
+
+    pub fn start(&mut self, request: FetchRequest) {
+        if self.is_fetching() {
+            return;
+        }
+
+        let source = self.source.clone();
+        let timeout = self.timeout;
+        let sender = self.sender.clone();
+        let handle = tokio::spawn(async move {
+            let pg_dump = source.fetch(CephQuery::PgDump, timeout).await;
+            let osd_tree = if request.osd_tree && pg_dump.is_ok() {
+                Some(source.fetch(CephQuery::OsdTree, timeout).await)
+            } else {
+                None
+            };
+            let status = if request.status && pg_dump.is_ok() {
+                Some(source.fetch(CephQuery::Status, timeout).await)
+            } else {
+                None
+            };
+            // The receiver is gone when the monitor is shutting down
+            let _ = sender.send(FetchResult {
+                pg_dump,
+                osd_tree,
+                status,
+            });
+        });
+
+        self.in_flight = Some((Instant::now(), handle));
+    }
+
+    pub fn is_fetching(&self) -> bool {
+        self.in_flight.is_some()
+    }
+
+    /// Time since the current fetch was started
+    pub fn elapsed(&self) -> Option<Duration> {
+        self.in_flight
+            .as_ref()
+            .map(|(started, _)| started.elapsed())
+    }
+
+    pub fn try_recv(&mut self) -> Option<FetchResult> {
+        match self.receiver.try_recv() {
+            Ok(result) => {
+                self.in_flight = None;
+                Some(result)
+            }
+            Err(_) => {
+                // A task that finished without sending a result has panicked; allow a retry
+                if matches!(&self.in_flight, Some((_, handle)) if handle.is_finished()) {
+                    self.in_flight = None;
+                }
+                None
+            }
+        }
+    }
+
+    /// Abort the running fetch; the child process is killed when its future is dropped
+    pub fn cancel(&mut self) {
+        if let Some((_, handle)) = self.in_flight.take() {
+            handle.abort();
+        }
+    }
+}
+
+impl Drop for Fetcher {
+    fn drop(&mut self) {
+        self.cancel();
+    }
+}
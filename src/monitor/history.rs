@@ -0,0 +1,723 @@
+//! Sampled recovery metrics in a local SQLite database, keyed by cluster fsid,
+//! so that rates and ETAs survive a restart of ceph-doctor.
+
+use crate::common::{CephPgDump, InconsistentPgProgress, OsdDataMovement};
+use crate::monitor::data::{count_osd_misplaced_objects, format_number, format_time};
+use crate::monitor::state::{MonitorState, RecoveryData};
+use crate::Result;
+use rusqlite::{params, Connection};
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+/// Samples older than this are deleted while recording
+pub const RETENTION_DAYS: i64 = 30;
+/// How often, in sample time, recording deletes the old samples
+const PRUNE_INTERVAL_SECS: i64 = 60 * 60;
+/// Same as the number of samples the rate calculations look at
+const HISTORY_SIZE: usize = 20;
+
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS samples (
+        fsid TEXT NOT NULL,
+        ts INTEGER NOT NULL,
+        degraded INTEGER NOT NULL,
+        misplaced INTEGER NOT NULL,
+        missing INTEGER NOT NULL,
+        unfound INTEGER NOT NULL,
+        num_objects INTEGER NOT NULL,
+        num_bytes INTEGER NOT NULL,
+        PRIMARY KEY (fsid, ts)
+    );
+    CREATE TABLE IF NOT EXISTS osd_samples (
+        fsid TEXT NOT NULL,
+        ts INTEGER NOT NULL,
+        osd INTEGER NOT NULL,
+        missing INTEGER NOT NULL,
+        excess INTEGER NOT NULL,
+        PRIMARY KEY (fsid, ts, osd)
+    );
+    CREATE TABLE IF NOT EXISTS scrub_samples (
+        fsid TEXT NOT NULL,
+        ts INTEGER NOT NULL,
+        pgid TEXT NOT NULL,
+        objects_scrubbed INTEGER NOT NULL,
+        num_objects INTEGER NOT NULL,
+        PRIMARY KEY (fsid, ts, pgid)
+    );
+";
+
+/// Cluster totals at one point in time
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Sample {
+    pub ts: i64, // Unix seconds
+    pub degraded: i64,
+    pub misplaced: i64,
+    pub missing: i64,
+    pub unfound: i64,
+    pub num_objects: i64,
+    pub num_bytes: i64,
+}
+
+impl Sample {
+    pub fn from_pg_dump(ts: i64, data: &CephPgDump) -> Self {
+        let stats_sum = &data.pg_map.pg_stats_sum.stat_sum;
+        Self {
+            ts,
+            degraded: stats_sum.num_objects_degraded,
+            misplaced: stats_sum.num_objects_misplaced,
+            missing: stats_sum.num_objects_missing,
+            unfound: stats_sum.num_objects_unfound,
+            num_objects: stats_sum.num_objects,
+            num_bytes: stats_sum.num_bytes,
+        }
+    }
+
+    /// Objects that still need recovery or backfill
+    pub fn remaining(&self) -> i64 {
+        self.degraded + self.misplaced + self.missing + self.unfound
+    }
+}
+
+/// Misplaced objects of one OSD; OSDs without any are not stored
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct OsdSample {
+    pub ts: i64,
+    pub osd: u32,
+    pub missing: i64,
+    pub excess: i64,
+}
+
+/// Scrub progress of one inconsistent PG
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ScrubSample {
+    pub ts: i64,
+    pub pgid: String,
+    pub objects_scrubbed: u64,
+    pub num_objects: i64,
+}
+
+/// The rows one pg dump adds to the database
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Record {
+    pub fsid: String,
+    pub sample: Sample,
+    pub osd_samples: Vec<OsdSample>,
+    pub scrub_samples: Vec<ScrubSample>,
+}
+
+impl Record {
+    /// The totals, per-OSD movement and scrub progress of one pg dump
+    pub fn from_pg_dump(fsid: &str, ts: i64, data: &CephPgDump) -> Self {
+        let osd_samples = count_osd_misplaced_objects(data)
+            .into_iter()
+            .filter(|(_, movement)| movement.missing_objects > 0 || movement.excess_objects > 0)
+            .map(|(osd, movement)| OsdSample {
+                ts,
+                osd,
+                missing: movement.missing_objects,
+                excess: movement.excess_objects,
+            })
+            .collect();
+        let scrub_samples = data
+            .pg_map
+            .pg_stats
+            .iter()
+            .filter(|pg| pg.state.contains("inconsistent"))
+            .map(|pg| ScrubSample {
+                ts,
+                pgid: pg.pgid.clone(),
+                objects_scrubbed: pg.objects_scrubbed.unwrap_or(0),
+                num_objects: pg.stat_sum.num_object_copies,
+            })
+            .collect();
+        Self {
+            fsid: fsid.to_string(),
+            sample: Sample::from_pg_dump(ts, data),
+            osd_samples,
+            scrub_samples,
+        }
+    }
+}
+
+/// Which clusters the database knows and over what time span
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClusterHistory {
+    pub fsid: String,
+    pub first: i64,
+    pub last: i64,
+    pub samples: u64,
+}
+
+/// A stretch of samples with objects left to recover
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Recovery {
+    pub start: i64,
+    /// None while the last sample still had objects left
+    pub end: Option<i64>,
+    pub peak_degraded: i64,
+    pub peak_misplaced: i64,
+    pub samples: usize,
+}
+
+pub struct HistoryStore {
+    conn: Connection,
+    /// Sample time of the last pruning while recording
+    last_prune: Option<i64>,
+}
+
+impl HistoryStore {
+    pub fn open(path: &Path) -> Result<Self> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)
+                .map_err(|e| format!("Cannot create {}: {e}", dir.display()))?;
+        }
+        let conn = Connection::open(path)
+            .map_err(|e| format!("Cannot open history {}: {e}", path.display()))?;
+        // Several clusters of the dashboard write to the same file
+        conn.busy_timeout(Duration::from_secs(5))?;
+        Self::init(conn)
+    }
+
+    pub fn open_in_memory() -> Result<Self> {
+        Self::init(Connection::open_in_memory()?)
+    }
+
+    fn init(conn: Connection) -> Result<Self> {
+        conn.execute_batch(SCHEMA)?;
+        Ok(Self {
+            conn,
+            last_prune: None,
+        })
+    }
+
+    /// Store the totals, per-OSD movement and scrub progress of one pg dump
+    pub fn record(&mut self, fsid: &str, ts: i64, data: &CephPgDump) -> Result<()> {
+        self.write(&Record::from_pg_dump(fsid, ts, data))
+    }
+
+    /// Store a record, then delete the samples past the retention, at most
+    /// once per hour of samples so a long running monitor stays bounded
+    pub fn write(&mut self, record: &Record) -> Result<()> {
+        let fsid = &record.fsid;
+        let sample = &record.sample;
+        let tx = self.conn.transaction()?;
+        tx.execute(
+            "INSERT OR REPLACE INTO samples VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            params![
+                fsid,
+                sample.ts,
+                sample.degraded,
+                sample.misplaced,
+                sample.missing,
+                sample.unfound,
+                sample.num_objects,
+                sample.num_bytes
+            ],
+        )?;
+        for osd in &record.osd_samples {
+            tx.execute(
+                "INSERT OR REPLACE INTO osd_samples VALUES (?1, ?2, ?3, ?4, ?5)",
+                params![fsid, osd.ts, osd.osd, osd.missing, osd.excess],
+            )?;
+        }
+        for scrub in &record.scrub_samples {
+            tx.execute(
+                "INSERT OR REPLACE INTO scrub_samples VALUES (?1, ?2, ?3, ?4, ?5)",
+                params![
+                    fsid,
+                    scrub.ts,
+                    scrub.pgid,
+                    scrub.objects_scrubbed as i64,
+                    scrub.num_objects
+                ],
+            )?;
+        }
+        tx.commit()?;
+
+        if self
+            .last_prune
+            .is_none_or(|last| sample.ts - last >= PRUNE_INTERVAL_SECS)
+        {
+            self.prune(sample.ts - RETENTION_DAYS * 24 * 60 * 60)?;
+            self.last_prune = Some(sample.ts);
+        }
+        Ok(())
+    }
+
+    /// Delete everything recorded before `ts`
+    pub fn prune(&self, before: i64) -> Result<()> {
+        for table in ["samples", "osd_samples", "scrub_samples"] {
+            self.conn
+                .execute(&format!("DELETE FROM {table} WHERE ts < ?1"), [before])?;
+        }
+        Ok(())
+    }
+
+    pub fn samples(&self, fsid: &str, since: i64) -> Result<Vec<Sample>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT ts, degraded, misplaced, missing, unfound, num_objects, num_bytes
+             FROM samples WHERE fsid = ?1 AND ts >= ?2 ORDER BY ts",
+        )?;
+        let rows = stmt.query_map(params![fsid, since], |row| {
+            Ok(Sample {
+                ts: row.get(0)?,
+                degraded: row.get(1)?,
+                misplaced: row.get(2)?,
+                missing: row.get(3)?,
+                unfound: row.get(4)?,
+                num_objects: row.get(5)?,
+                num_bytes: row.get(6)?,
+            })
+        })?;
+        Ok(rows.collect::<rusqlite::Result<_>>()?)
+    }
+
+    pub fn osd_samples(&self, fsid: &str, since: i64) -> Result<Vec<OsdSample>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT ts, osd, missing, excess FROM osd_samples
+             WHERE fsid = ?1 AND ts >= ?2 ORDER BY ts, osd",
+        )?;
+        let rows = stmt.query_map(params![fsid, since], |row| {
+            Ok(OsdSample {
+                ts: row.get(0)?,
+                osd: row.get(1)?,
+                missing: row.get(2)?,
+                excess: row.get(3)?,
+            })
+        })?;
+        Ok(rows.collect::<rusqlite::Result<_>>()?)
+    }
+
+    pub fn scrub_samples(&self, fsid: &str, since: i64) -> Result<Vec<ScrubSample>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT ts, pgid, objects_scrubbed, num_objects FROM scrub_samples
+             WHERE fsid = ?1 AND ts >= ?2 ORDER BY ts, pgid",
+        )?;
+        let rows = stmt.query_map(params![fsid, since], |row| {
+            Ok(ScrubSample {
+                ts: row.get(0)?,
+                pgid: row.get(1)?,
+                objects_scrubbed: row.get::<_, i64>(2)? as u64,
+                num_objects: row.get(3)?,
+            })
+        })?;
+        Ok(rows.collect::<rusqlite::Result<_>>()?)
+    }
+
+    pub fn clusters(&self) -> Result<Vec<ClusterHistory>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT fsid, MIN(ts), MAX(ts), COUNT(*) FROM samples GROUP BY fsid ORDER BY fsid",
+        )?;
+        let rows = stmt.query_map([], |row| {
+            Ok(ClusterHistory {
+                fsid: row.get(0)?,
+                first: row.get(1)?,
+                last: row.get(2)?,
+                samples: row.get::<_, i64>(3)? as u64,
+            })
+        })?;
+        Ok(rows.collect::<rusqlite::Result<_>>()?)
+    }
+
+    /// Load the samples of the last `HISTORY_SIZE` intervals into the state,
+    /// as if the monitor had been running all along
+    pub fn restore(
+        &self,
+        state: &mut MonitorState,
+        fsid: &str,
+        now: i64,
+        interval: u64,
+    ) -> Result<()> {
+        let interval = interval.max(1) as i64;
+        let since = now - HISTORY_SIZE as i64 * interval;
+
+        let samples = self.samples(fsid, since)?;
+        let points: Vec<(i64, Sample)> = samples.iter().map(|s| (s.ts, *s)).collect();
+        let series = resample(&points, now, interval);
+        let avg_object_size = series
+            .last()
+            .filter(|s| s.num_objects > 0)
+            .map_or(0.0, |s| s.num_bytes as f64 / s.num_objects as f64);
+        let categories = [
+            (
+                "Missing",
+                series.iter().map(|s| s.missing).collect::<Vec<_>>(),
+            ),
+            ("Unfound", series.iter().map(|s| s.unfound).collect()),
+            ("Misplaced", series.iter().map(|s| s.misplaced).collect()),
+            ("Degraded", series.iter().map(|s| s.degraded).collect()),
+        ];
+        for (category, values) in categories {
+            // The recovery table only records a category while it is non-zero
+            let history: Vec<RecoveryData> = trailing_active(values.into_iter())
+                .into_iter()
+                .map(|objects| RecoveryData {
+                    objects,
+                    bytes: (objects as f64 * avg_object_size) as i64,
+                })
+                .collect();
+            if !history.is_empty() {
+                *state.get_recovery_history_mut(category) = history;
+            }
+        }
+
+        // Counts per OSD by sample time, so each OSD looks up its own rows only
+        let mut osd_counts: BTreeMap<u32, HashMap<i64, (i64, i64)>> = BTreeMap::new();
+        for s in self.osd_samples(fsid, since)? {
+            osd_counts
+                .entry(s.osd)
+                .or_default()
+                .insert(s.ts, (s.missing, s.excess));
+        }
+        let mut movements = state.get_osd_movements().clone();
+        for (osd, counts) in osd_counts {
+            // An OSD without a row in a sample had nothing to move at that time
+            let points: Vec<(i64, (i64, i64))> = samples
+                .iter()
+                .map(|sample| {
+                    let counts = counts.get(&sample.ts).copied().unwrap_or((0, 0));
+                    (sample.ts, counts)
+                })
+                .collect();
+            let series = resample(&points, now, interval);
+            movements.insert(
+                osd,
+                OsdDataMovement {
+                    osd_id: osd,
+                    missing_objects_history: series.iter().map(|c| c.0).collect(),
+                    excess_objects_history: series.iter().map(|c| c.1).collect(),
+                    ..Default::default()
+                },
+            );
+        }
+        state.set_osd_movements(movements);
+
+        let scrub_samples = self.scrub_samples(fsid, since)?;
+        let mut progress = state.get_inconsistent_pg_progress().clone();
+        let mut pg_samples_by_pgid: BTreeMap<&str, Vec<&ScrubSample>> = BTreeMap::new();
+        for s in &scrub_samples {
+            pg_samples_by_pgid.entry(&s.pgid).or_default().push(s);
+        }
+        for (pgid, pg_samples) in pg_samples_by_pgid {
+            let points: Vec<(i64, u64)> = pg_samples
+                .iter()
+                .map(|s| (s.ts, s.objects_scrubbed))
+                .collect();
+            let last = pg_samples.last().expect("pgid comes from the samples");
+            progress.insert(
+                pgid.to_string(),
+                InconsistentPgProgress {
+                    pgid: pgid.to_string(),
+                    num_objects: last.num_objects,
+                    objects_scrubbed: last.objects_scrubbed,
+                    scrubbed_history: resample(&points, now, interval),
+                    ..Default::default()
+                },
+            );
+        }
+        state.set_inconsistent_pg_progress(progress);
+
+        Ok(())
+    }
+}
+
+/// Writes records in a blocking task, so the database never holds up the UI
+pub struct HistoryWriter {
+    sender: mpsc::UnboundedSender<Record>,
+    error_receiver: mpsc::UnboundedReceiver<String>,
+}
+
+impl HistoryWriter {
+    /// Hand the store to a blocking task; it stops at the first error, or
+    /// when the writer is dropped
+    pub fn new(mut store: HistoryStore) -> Self {
+        let (sender, mut receiver) = mpsc::unbounded_channel::<Record>();
+        let (error_sender, error_receiver) = mpsc::unbounded_channel();
+        tokio::task::spawn_blocking(move || {
+            while let Some(record) = receiver.blocking_recv() {
+                if let Err(e) = store.write(&record) {
+                    // The receiver is gone when the monitor is shutting down
+                    let _ = error_sender.send(e.to_string());
+                    break;
+                }
+            }
+        });
+        Self {
+            sender,
+            error_receiver,
+        }
+    }
+
+    pub fn write(&self, record: Record) {
+        // Only fails after an error, which take_error reports
+        let _ = self.sender.send(record);
+    }
+
+    /// Why writing stopped, once
+    pub fn take_error(&mut self) -> Option<String> {
+        self.error_receiver.try_recv().ok()
+    }
+}
+
+/// Values at `now - k * interval` for k = HISTORY_SIZE-1 down to 1, each taken
+/// from the latest point at or before that time. Starts at the first point.
+fn resample<T: Clone>(points: &[(i64, T)], now: i64, interval: i64) -> Vec<T> {
+    (1..HISTORY_SIZE as i64)
+        .rev()
+        .filter_map(|k| {
+            let t = now - k * interval;
+            points
+                .iter()
+                .take_while(|(ts, _)| *ts <= t)
+                .last()
+                .map(|(_, value)| value.clone())
+        })
+        .collect()
+}
+
+/// The values after the last zero
+fn trailing_active(values: impl Iterator<Item = i64>) -> Vec<i64> {
+    let values: Vec<i64> = values.collect();
+    let start = values
+        .iter()
+        .rposition(|&value| value <= 0)
+        .map_or(0, |index| index + 1);
+    values[start..].to_vec()
+}
+
+/// Split the samples into recoveries: from the first sample with objects left
+/// to the next one without
+pub fn recoveries(samples: &[Sample]) -> Vec<Recovery> {
+    let mut recoveries = Vec::new();
+    let mut current: Option<Recovery> = None;
+    for sample in samples {
+        if sample.remaining() > 0 {
+            let recovery = current.get_or_insert(Recovery {
+                start: sample.ts,
+                end: None,
+                peak_degraded: 0,
+                peak_misplaced: 0,
+                samples: 0,
+            });
+            recovery.peak_degraded = recovery.peak_degraded.max(sample.degraded);
+            recovery.peak_misplaced = recovery.peak_misplaced.max(sample.misplaced);
+            recovery.samples += 1;
+        } else if let Some(mut recovery) = current.take() {
+            recovery.end = Some(sample.ts);
+            recoveries.push(recovery);
+        }
+    }
+    recoveries.extend(current);
+    recoveries
+}
+
+pub(crate) fn format_timestamp(ts: i64) -> String {
+    chrono::DateTime::from_timestamp(ts, 0).map_or_else(
+        || ts.to_string(),
+        |t| t.format("%Y-%m-%d %H:%M:%S").to_string(),
+    )
+}
+
+/// The recoveries of each cluster recorded since `since`, as text for the
+/// `history` command
+pub fn history_report(store: &HistoryStore, fsid: Option<&str>, since: i64) -> Result<String> {
+    let clusters: Vec<ClusterHistory> = store
+        .clusters()?
+        .into_iter()
+        .filter(|cluster| fsid.is_none_or(|fsid| cluster.fsid == fsid))
+        .collect();
+    if clusters.is_empty() {
+        return Ok(match fsid {
+            Some(fsid) => format!("No history recorded for cluster {fsid}\n"),
+            None => "No history recorded yet\n".to_string(),
+        });
+    }
+
+    let mut report = String::new();
+    for cluster in clusters {
+        report.push_str(&format!(
+            "Cluster {} ({} samples, {} - {} UTC)\n",
+            cluster.fsid,
+            cluster.samples,
+            format_timestamp(cluster.first),
+            format_timestamp(cluster.last)
+        ));
+
+        let found = recoveries(&store.samples(&cluster.fsid, since)?);
+        if found.is_empty() {
+            report.push_str("  No recoveries\n\n");
+            continue;
+        }
+        report.push_str(&format!(
+            "  {:<20} {:<20} {:>12} {:>10} {:>10}\n",
+            "Start (UTC)", "End (UTC)", "Duration", "Degraded", "Misplaced"
+        ));
+        for recovery in found {
+            let (end, duration) = match recovery.end {
+                Some(end) => (format_timestamp(end), end - recovery.start),
+                None => ("ongoing".to_string(), cluster.last - recovery.start),
+            };
+            report.push_str(&format!(
+                "  {:<20} {:<20} {:>12} {:>10} {:>10}\n",
+                format_timestamp(recovery.start),
+                end,
+                format_time(duration.max(0) as u64),
+                format_number(recovery.peak_degraded),
+                format_number(recovery.peak_misplaced)
+            ));
+        }
+        report.push('\n');
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pg_dump(degraded: i64, misplaced: i64) -> CephPgDump {
+        let mut data: CephPgDump = serde_json::from_str(include_str!(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/tests/fixtures/pg_dump.json"
+        )))
+        .unwrap();
+        let stats_sum = &mut data.pg_map.pg_stats_sum.stat_sum;
+        stats_sum.num_objects_degraded = degraded;
+        stats_sum.num_objects_misplaced = misplaced;
+        data
+    }
+
+    #[test]
+    fn test_resample() {
+        let points = [(100, 1), (104, 2), (111, 3)];
+        // Taken at 100, 105, 110 and 115; nothing before the first point
+        assert_eq!(resample(&points, 120, 5), vec![1, 2, 2, 3]);
+        assert!(resample::<i32>(&[], 120, 5).is_empty());
+    }
+
+    #[test]
+    fn test_trailing_active() {
+        assert_eq!(trailing_active([5, 0, 3, 2].into_iter()), vec![3, 2]);
+        assert_eq!(trailing_active([5, 4].into_iter()), vec![5, 4]);
+        assert!(trailing_active([5, 0].into_iter()).is_empty());
+    }
+
+    #[test]
+    fn test_recoveries() {
+        let sample = |ts, misplaced| Sample {
+            ts,
+            misplaced,
+            ..Default::default()
+        };
+        let samples = [
+            sample(0, 0),
+            sample(10, 500),
+            sample(20, 800),
+            sample(30, 0),
+            sample(40, 100),
+        ];
+        let found = recoveries(&samples);
+        assert_eq!(found.len(), 2);
+        assert_eq!(found[0].start, 10);
+        assert_eq!(found[0].end, Some(30));
+        assert_eq!(found[0].peak_misplaced, 800);
+        assert_eq!(found[1].end, None);
+    }
+
+    #[test]
+    fn test_record_and_restore() {
+        let mut store = HistoryStore::open_in_memory().unwrap();
+        for (i, misplaced) in [1000, 900, 800].into_iter().enumerate() {
+            let ts = 1000 + i as i64 * 5;
+            store.record("fsid-a", ts, &pg_dump(0, misplaced)).unwrap();
+        }
+        store.record("fsid-b", 1000, &pg_dump(7, 0)).unwrap();
+
+        let clusters = store.clusters().unwrap();
+        assert_eq!(clusters.len(), 2);
+        assert_eq!(clusters[0].samples, 3);
+        assert_eq!((clusters[0].first, clusters[0].last), (1000, 1010));
+
+        let mut state = MonitorState::new();
+        store.restore(&mut state, "fsid-a", 1015, 5).unwrap();
+        let misplaced: Vec<i64> = state
+            .get_recovery_history("Misplaced")
+            .unwrap()
+            .iter()
+            .map(|data| data.objects)
+            .collect();
+        assert_eq!(misplaced, vec![1000, 900, 800]);
+        assert!(state.get_recovery_history("Degraded").is_none());
+        assert!(!state.get_osd_movements().is_empty());
+
+        store.prune(1005).unwrap();
+        assert_eq!(store.samples("fsid-a", 0).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_record_prunes_old_samples() {
+        const DAY: i64 = 24 * 60 * 60;
+        let mut store = HistoryStore::open_in_memory().unwrap();
+        let start = 100 * DAY;
+        store.record("fsid-a", start, &pg_dump(0, 10)).unwrap();
+        store.record("fsid-a", start + 60, &pg_dump(0, 10)).unwrap();
+
+        // The first sample an hour after the last pruning deletes what is past
+        // the retention; the next ones wait for another hour
+        let later = start + RETENTION_DAYS * DAY + 30;
+        store.record("fsid-a", later, &pg_dump(0, 10)).unwrap();
+        assert_eq!(store.samples("fsid-a", 0).unwrap().len(), 2);
+        store.record("fsid-a", later + 60, &pg_dump(0, 10)).unwrap();
+        assert_eq!(store.samples("fsid-a", 0).unwrap().len(), 3);
+        store
+            .record("fsid-a", later + PRUNE_INTERVAL_SECS, &pg_dump(0, 10))
+            .unwrap();
+        let samples = store.samples("fsid-a", 0).unwrap();
+        assert_eq!(samples.first().unwrap().ts, later);
+    }
+
+    #[tokio::test]
+    async fn test_history_writer() {
+        let path = std::env::temp_dir().join(format!(
+            "ceph-doctor-history-writer-{}.db",
+            std::process::id()
+        ));
+        let _ = std::fs::remove_file(&path);
+        let mut writer = HistoryWriter::new(HistoryStore::open(&path).unwrap());
+        writer.write(Record::from_pg_dump("fsid-a", 1000, &pg_dump(0, 10)));
+        writer.write(Record::from_pg_dump("fsid-a", 1005, &pg_dump(0, 5)));
+        drop(writer.sender);
+
+        // The task ends once it has written everything it was sent
+        assert_eq!(writer.error_receiver.recv().await, None);
+        let store = HistoryStore::open(&path).unwrap();
+        assert_eq!(store.samples("fsid-a", 0).unwrap().len(), 2);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_history_report() {
+        let mut store = HistoryStore::open_in_memory().unwrap();
+        assert_eq!(
+            history_report(&store, None, 0).unwrap(),
+            "No history recorded yet\n"
+        );
+
+        store.record("fsid-a", 0, &pg_dump(0, 0)).unwrap();
+        store.record("fsid-a", 60, &pg_dump(20, 18230)).unwrap();
+        store.record("fsid-a", 3660, &pg_dump(0, 0)).unwrap();
+
+        let report = history_report(&store, Some("fsid-a"), 0).unwrap();
+        assert!(report.starts_with(
+            "Cluster fsid-a (3 samples, 1970-01-01 00:00:00 - 1970-01-01 01:01:00 UTC)"
+        ));
+        assert!(report.contains("1970-01-01 00:01:00  1970-01-01 01:01:00"));
+        assert!(report.contains("1h00m00s"));
+        assert!(report.contains("18.2K"));
+        assert!(history_report(&store, Some("fsid-b"), 0)
+            .unwrap()
+            .contains("No history recorded for cluster fsid-b"));
+    }
+}
@@ -0,0 +1,494 @@
+//! The keys of the monitor. Every action has default keys; the `[keys]` table
+//! of the config file replaces them per action:
+//!
+//! ```toml
+//! [keys]
+//! pause = ["space"]
+//! quit = ["q", "ctrl+d"]
+//! ```
+//!
+//! Ctrl+C quits regardless, so a broken keymap cannot lock anyone in.
+
+use crate::monitor::state::View;
+use crate::monitor::terminal::{RuntimeControl, TextInput, ViewAction};
+use crossterm::event::{Event, KeyCode, KeyEvent, KeyModifiers};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::str::FromStr;
+
+/// Rows Page Up and Page Down move the selection by
+const PAGE_ROWS: isize = 10;
+
+/// Everything a key does in the monitor, named as in the `[keys]` table
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Action {
+    Quit,
+    Help,
+    Back,
+    ClosePopup,
+    NextTab,
+    PreviousTab,
+    Overview,
+    OsdMovement,
+    InconsistentPgs,
+    PgStates,
+    OsdOverview,
+    Network,
+    Events,
+    Pgs,
+    Up,
+    Down,
+    PageUp,
+    PageDown,
+    First,
+    Last,
+    Select,
+    SortNext,
+    SortReverse,
+    Search,
+    Save,
+    Export,
+    ColumnChooser,
+    Toggle,
+    Pause,
+    Refresh,
+    SlowerInterval,
+    FasterInterval,
+}
+
+impl Action {
+    /// All actions in the order of the help overlay
+    pub const ALL: [Action; 32] = [
+        Action::Quit,
+        Action::Help,
+        Action::Back,
+        Action::ClosePopup,
+        Action::NextTab,
+        Action::PreviousTab,
+        Action::Overview,
+        Action::OsdMovement,
+        Action::InconsistentPgs,
+        Action::PgStates,
+        Action::OsdOverview,
+        Action::Network,
+        Action::Events,
+        Action::Pgs,
+        Action::Up,
+        Action::Down,
+        Action::PageUp,
+        Action::PageDown,
+        Action::First,
+        Action::Last,
+        Action::Select,
+        Action::SortNext,
+        Action::SortReverse,
+        Action::Search,
+        Action::Save,
+        Action::Export,
+        Action::ColumnChooser,
+        Action::Toggle,
+        Action::Pause,
+        Action::Refresh,
+        Action::SlowerInterval,
+        Action::FasterInterval,
+    ];
+
+    pub fn description(self) -> &'static str {
+        match self {
+            Action::Quit => "Quit (Ctrl+C always quits)",
+            Action::Help => "Show or hide this help",
+            Action::Back => "Back to the cluster summary",
+            Action::ClosePopup => "Close the error popup",
+            Action::NextTab => "Next tab",
+            Action::PreviousTab => "Previous tab",
+            Action::Overview => "Overview tab",
+            Action::OsdMovement => "OSD Movement tab",
+            Action::InconsistentPgs => "Inconsistent PGs tab",
+            Action::PgStates => "PG States tab",
+            Action::OsdOverview => "OSD Overview tab",
+            Action::Network => "Network tab",
+            Action::Events => "Events tab",
+            Action::Pgs => "PGs tab",
+            Action::Up => "Select or scroll up",
+            Action::Down => "Select or scroll down",
+            Action::PageUp => "Page up",
+            Action::PageDown => "Page down",
+            Action::First => "First row",
+            Action::Last => "Last row",
+            Action::Select => "Open the selected cluster",
+            Action::SortNext => "Next sort column",
+            Action::SortReverse => "Reverse the sort direction",
+            Action::Search => "Filter the events or PGs",
+            Action::Save => "Save the event log",
+            Action::Export => "Export the screen (text/ANSI/SVG)",
+            Action::ColumnChooser => "Choose OSD movement columns",
+            Action::Toggle => "Toggle the chosen column",
+            Action::Pause => "Pause or resume polling",
+            Action::Refresh => "Fetch now",
+            Action::SlowerInterval => "Raise the refresh interval",
+            Action::FasterInterval => "Lower the refresh interval",
+        }
+    }
+
+    fn default_keys(self) -> &'static [&'static str] {
+        match self {
+            Action::Quit => &["q", "esc"],
+            Action::Help => &["?"],
+            Action::Back => &["esc", "backspace"],
+            Action::ClosePopup => &["esc", "enter", "space"],
+            Action::NextTab => &["tab"],
+            Action::PreviousTab => &["shift+tab"],
+            Action::Overview => &["1"],
+            Action::OsdMovement => &["2"],
+            Action::InconsistentPgs => &["3"],
+            Action::PgStates => &["4"],
+            Action::OsdOverview => &["5"],
+            Action::Network => &["6"],
+            Action::Events => &["7"],
+            Action::Pgs => &["8"],
+            Action::Up => &["up", "k"],
+            Action::Down => &["down", "j"],
+            Action::PageUp => &["pageup"],
+            Action::PageDown => &["pagedown"],
+            Action::First => &["home"],
+            Action::Last => &["end"],
+            Action::Select => &["enter"],
+            Action::SortNext => &["s"],
+            Action::SortReverse => &["r"],
+            Action::Search => &["/"],
+            Action::Save => &["w"],
+            Action::Export => &["x"],
+            Action::ColumnChooser => &["c"],
+            Action::Toggle => &["space", "enter"],
+            Action::Pause => &["p"],
+            Action::Refresh => &["f"],
+            // '=' is '+' without Shift on most layouts
+            Action::SlowerInterval => &["+", "="],
+            Action::FasterInterval => &["-"],
+        }
+    }
+
+    /// The tab the action switches to
+    pub fn view(self) -> Option<View> {
+        match self {
+            Action::Overview => Some(View::Overview),
+            Action::OsdMovement => Some(View::OsdMovement),
+            Action::InconsistentPgs => Some(View::InconsistentPgs),
+            Action::PgStates => Some(View::PgStates),
+            Action::OsdOverview => Some(View::OsdOverview),
+            Action::Network => Some(View::Network),
+            Action::Events => Some(View::Events),
+            Action::Pgs => Some(View::Pgs),
+            _ => None,
+        }
+    }
+
+    pub fn for_view(view: View) -> Action {
+        Action::ALL
+            .into_iter()
+            .find(|action| action.view() == Some(view))
+            .unwrap_or(Action::Overview)
+    }
+}
+
+/// Names of the keys that are not a single character, as shown and as
+/// accepted in the config file besides the lowercase aliases
+const KEY_NAMES: [(KeyCode, &str, &[&str]); 16] = [
+    (KeyCode::Up, "↑", &["up"]),
+    (KeyCode::Down, "↓", &["down"]),
+    (KeyCode::Left, "←", &["left"]),
+    (KeyCode::Right, "→", &["right"]),
+    (KeyCode::PageUp, "PgUp", &["pageup"]),
+    (KeyCode::PageDown, "PgDn", &["pagedown"]),
+    (KeyCode::Home, "Home", &[]),
+    (KeyCode::End, "End", &[]),
+    (KeyCode::Enter, "Enter", &["return"]),
+    (KeyCode::Esc, "Esc", &["escape"]),
+    (KeyCode::Tab, "Tab", &[]),
+    (KeyCode::BackTab, "Shift+Tab", &["backtab"]),
+    (KeyCode::Backspace, "Backspace", &[]),
+    (KeyCode::Delete, "Del", &["delete"]),
+    (KeyCode::Insert, "Ins", &["insert"]),
+    (KeyCode::Char(' '), "Space", &[]),
+];
+
+/// One key, optionally with Ctrl. Shift is part of the character: `?`, `+`, `G`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct KeyBinding {
+    pub code: KeyCode,
+    pub ctrl: bool,
+}
+
+impl KeyBinding {
+    pub fn matches(&self, key: &KeyEvent) -> bool {
+        key.code == self.code && key.modifiers.contains(KeyModifiers::CONTROL) == self.ctrl
+    }
+}
+
+impl FromStr for KeyBinding {
+    type Err = String;
+
+    /// `q`, `?`, `esc`, `pagedown`, `shift+tab`, `f5` or `ctrl+d`
+    fn from_str(text: &str) -> Result<Self, String> {
+        let (ctrl, key) = match text.get(..5) {
+            Some(prefix) if prefix.eq_ignore_ascii_case("ctrl+") => (true, &text[5..]),
+            _ => (false, text),
+        };
+        let name = key.to_lowercase();
+        let named = KEY_NAMES.iter().find(|(_, shown, aliases)| {
+            shown.to_lowercase() == name || aliases.contains(&name.as_str())
+        });
+        let mut chars = key.chars();
+        let code = match (named, chars.next(), chars.next()) {
+            (Some((code, _, _)), _, _) => *code,
+            // Terminals report Ctrl+D as a lowercase d
+            (None, Some(c), None) if ctrl => KeyCode::Char(c.to_ascii_lowercase()),
+            (None, Some(c), None) => KeyCode::Char(c),
+            _ => match name.strip_prefix('f').and_then(|n| n.parse().ok()) {
+                Some(n @ 1..=12) => KeyCode::F(n),
+                _ => return Err(format!("unknown key '{text}'")),
+            },
+        };
+        Ok(KeyBinding { code, ctrl })
+    }
+}
+
+impl TryFrom<String> for KeyBinding {
+    type Error = String;
+
+    fn try_from(text: String) -> Result<Self, String> {
+        text.parse()
+    }
+}
+
+impl From<KeyBinding> for String {
+    fn from(key: KeyBinding) -> String {
+        key.to_string()
+    }
+}
+
+impl fmt::Display for KeyBinding {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.ctrl {
+            write!(f, "Ctrl+")?;
+        }
+        match KEY_NAMES.iter().find(|(code, _, _)| *code == self.code) {
+            Some((_, shown, _)) => write!(f, "{shown}"),
+            None => match self.code {
+                KeyCode::Char(c) if self.ctrl => write!(f, "{}", c.to_ascii_uppercase()),
+                KeyCode::Char(c) => write!(f, "{c}"),
+                KeyCode::F(n) => write!(f, "F{n}"),
+                code => write!(f, "{code:?}"),
+            },
+        }
+    }
+}
+
+/// The keys of every action: the defaults with the configured ones replacing them
+#[derive(Debug, Clone, PartialEq)]
+pub struct Keymap {
+    keys: BTreeMap<Action, Vec<KeyBinding>>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        let keys = Action::ALL
+            .into_iter()
+            .map(|action| {
+                let keys = action
+                    .default_keys()
+                    .iter()
+                    .map(|key| key.parse().expect("default keys parse"))
+                    .collect();
+                (action, keys)
+            })
+            .collect();
+        Keymap { keys }
+    }
+}
+
+impl Keymap {
+    /// The default keys, with those of the actions in `overrides` replaced;
+    /// an empty list leaves the action without a key
+    pub fn new(overrides: &BTreeMap<Action, Vec<KeyBinding>>) -> Self {
+        let mut keymap = Keymap::default();
+        for (action, keys) in overrides {
+            keymap.keys.insert(*action, keys.clone());
+        }
+        keymap
+    }
+
+    pub fn keys(&self, action: Action) -> &[KeyBinding] {
+        self.keys.get(&action).map_or(&[], Vec::as_slice)
+    }
+
+    pub fn matches(&self, action: Action, event: &Event) -> bool {
+        let Event::Key(key) = event else {
+            return false;
+        };
+        if action == Action::Quit
+            && key.code == KeyCode::Char('c')
+            && key.modifiers.contains(KeyModifiers::CONTROL)
+        {
+            return true;
+        }
+        self.keys(action).iter().any(|binding| binding.matches(key))
+    }
+
+    /// The first key of the action for the hints on screen, `-` when it has none
+    pub fn key(&self, action: Action) -> String {
+        self.keys(action)
+            .first()
+            .map_or("-".to_string(), KeyBinding::to_string)
+    }
+
+    /// All keys of the action, as listed in the help
+    pub fn describe(&self, action: Action) -> String {
+        let keys: Vec<String> = self
+            .keys(action)
+            .iter()
+            .map(KeyBinding::to_string)
+            .collect();
+        keys.join("/")
+    }
+
+    /// A hint like `[s/r] Sort` with the first key of each action
+    pub fn hint(&self, actions: &[Action], label: &str) -> String {
+        let keys: Vec<String> = actions.iter().map(|action| self.key(*action)).collect();
+        format!("[{}] {label}", keys.join("/"))
+    }
+
+    /// A key typed into a text field; Ctrl+C is left to quit
+    pub fn text_input(&self, event: &Event) -> Option<TextInput> {
+        let Event::Key(key) = event else {
+            return None;
+        };
+        if key.modifiers.contains(KeyModifiers::CONTROL) {
+            return None;
+        }
+        match key.code {
+            KeyCode::Char(c) => Some(TextInput::Char(c)),
+            KeyCode::Backspace => Some(TextInput::Backspace),
+            KeyCode::Enter => Some(TextInput::Done),
+            KeyCode::Esc => Some(TextInput::Cancel),
+            _ => None,
+        }
+    }
+
+    /// Rows to move the selection of a tab by: up and down move one row,
+    /// page up and down a page, first and last to the first or last row
+    pub fn selection_move(&self, event: &Event) -> Option<isize> {
+        [
+            (Action::Up, -1),
+            (Action::Down, 1),
+            (Action::PageUp, -PAGE_ROWS),
+            (Action::PageDown, PAGE_ROWS),
+            (Action::First, isize::MIN),
+            (Action::Last, isize::MAX),
+        ]
+        .into_iter()
+        .find(|(action, _)| self.matches(*action, event))
+        .map(|(_, delta)| delta)
+    }
+
+    /// Pause or resume polling, fetch now, or change the refresh interval
+    pub fn runtime_control(&self, event: &Event) -> Option<RuntimeControl> {
+        [
+            (Action::Pause, RuntimeControl::TogglePause),
+            (Action::Refresh, RuntimeControl::RefreshNow),
+            (Action::SlowerInterval, RuntimeControl::SlowerInterval),
+            (Action::FasterInterval, RuntimeControl::FasterInterval),
+        ]
+        .into_iter()
+        .find(|(action, _)| self.matches(*action, event))
+        .map(|(_, control)| control)
+    }
+
+    pub fn view_action(&self, event: &Event) -> Option<ViewAction> {
+        if self.matches(Action::NextTab, event) {
+            Some(ViewAction::NextView)
+        } else if self.matches(Action::PreviousTab, event) {
+            Some(ViewAction::PreviousView)
+        } else if self.matches(Action::SortNext, event) {
+            Some(ViewAction::SortNext)
+        } else if self.matches(Action::SortReverse, event) {
+            Some(ViewAction::SortReverse)
+        } else {
+            View::ALL
+                .into_iter()
+                .find(|view| self.matches(Action::for_view(*view), event))
+                .map(ViewAction::SelectView)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(code: KeyCode, modifiers: KeyModifiers) -> Event {
+        Event::Key(KeyEvent::new(code, modifiers))
+    }
+
+    #[test]
+    fn test_parse_key_binding() {
+        let parse = |text: &str| text.parse::<KeyBinding>().unwrap();
+        assert_eq!(parse("q").code, KeyCode::Char('q'));
+        assert_eq!(parse("Q").code, KeyCode::Char('Q'));
+        assert_eq!(parse("Esc").code, KeyCode::Esc);
+        assert_eq!(parse("pagedown").code, KeyCode::PageDown);
+        assert_eq!(parse("shift+tab").code, KeyCode::BackTab);
+        assert_eq!(parse("F5").code, KeyCode::F(5));
+        assert_eq!(
+            parse("Ctrl+D"),
+            KeyBinding {
+                code: KeyCode::Char('d'),
+                ctrl: true
+            }
+        );
+        assert!("ctrl+".parse::<KeyBinding>().is_err());
+        assert!("hyper".parse::<KeyBinding>().is_err());
+
+        // What is shown parses back to the same key
+        for action in Action::ALL {
+            for binding in Keymap::default().keys(action) {
+                assert_eq!(parse(&binding.to_string()), *binding);
+            }
+        }
+    }
+
+    #[test]
+    fn test_keymap() {
+        let overrides =
+            toml::from_str::<BTreeMap<Action, Vec<KeyBinding>>>("pause = [\"space\"]\nsave = []")
+                .unwrap();
+        let keymap = Keymap::new(&overrides);
+
+        let space = key(KeyCode::Char(' '), KeyModifiers::NONE);
+        assert!(keymap.matches(Action::Pause, &space));
+        assert!(!keymap.matches(Action::Pause, &key(KeyCode::Char('p'), KeyModifiers::NONE)));
+        assert_eq!(keymap.key(Action::Save), "-");
+        // Untouched actions keep their defaults
+        assert_eq!(keymap.describe(Action::Up), "↑/k");
+        assert_eq!(
+            keymap.hint(&[Action::SortNext, Action::SortReverse], "Sort"),
+            "[s/r] Sort"
+        );
+
+        // Ctrl+C quits but is not the column chooser
+        let ctrl_c = key(KeyCode::Char('c'), KeyModifiers::CONTROL);
+        assert!(keymap.matches(Action::Quit, &ctrl_c));
+        assert!(!keymap.matches(Action::ColumnChooser, &ctrl_c));
+        // Shift is part of the character
+        let plus = key(KeyCode::Char('+'), KeyModifiers::SHIFT);
+        assert!(keymap.matches(Action::SlowerInterval, &plus));
+
+        assert!(
+            toml::from_str::<BTreeMap<Action, Vec<KeyBinding>>>("pause = [\"hyper\"]").is_err()
+        );
+        assert!(toml::from_str::<BTreeMap<Action, Vec<KeyBinding>>>("fly = [\"x\"]").is_err());
+    }
+}
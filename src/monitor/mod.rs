@@ -1,188 +1,118 @@
+pub mod alerts;
+pub mod cluster;
 pub mod data;
+pub mod events;
+pub mod export;
+pub mod fetcher;
+pub mod history;
+pub mod keymap;
+pub mod mon;
+pub mod mouse;
+pub mod multi;
+pub mod plain;
+pub mod prefix;
+pub mod rest;
+pub mod source;
 pub mod state;
 pub mod terminal;
 pub mod ui;
 
 use crate::common::CephPgDump;
+use crate::config::ColorMode;
 use crate::Result;
+use alerts::AlertSettings;
+use cluster::ClusterMonitor;
 use data::*;
-use ratatui::backend::CrosstermBackend;
+use keymap::{Action, Keymap};
+use mouse::{MouseAreas, TableArea};
+use ratatui::backend::Backend;
 use ratatui::Terminal;
-use state::MonitorState;
-use std::env;
-use std::process::Command;
-use terminal::{SleepResult, TerminalManager};
+use source::DataSource;
+use state::{MonitorState, OsdMovementSettings, OsdMovementTable};
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+use terminal::{RuntimeControl, TerminalManager, TextInput, ViewAction};
 use ui::*;
 
 pub async fn run_test(interval: u64) -> Result<()> {
     let files = ["state-a.json", "state-b.json"];
     let mut file_index = 0;
 
-    let mut terminal_manager = TerminalManager::new()?;
+    let mut terminal_manager = TerminalManager::new(true)?;
     let mut last_data: Option<CephPgDump> = None;
     let mut error_message: Option<String> = None;
     let mut state = MonitorState::new();
+    state.set_ping_threshold_ms(DEFAULT_PING_THRESHOLD_MS);
+    state.set_theme(Theme::new(
+        ThemeName::default(),
+        ColorMode::Auto.use_colors(),
+    ));
 
     // Draw initial loading screen
-    render_current_state(
-        terminal_manager.terminal(),
-        None,
-        None,
-        interval,
-        &mut state,
-    )?;
+    let areas = render_current_state(terminal_manager.terminal(), None, None, interval, &state)?;
+    state.set_mouse_areas(areas);
 
+    let mut next_load = Instant::now();
     loop {
-        // Handle events
-        if terminal_manager.poll_event(std::time::Duration::from_millis(100))? {
-            let event = terminal_manager.read_event()?;
-
-            // Always handle quit events
-            if terminal_manager.should_quit(&event) {
-                break;
-            }
-
-            // Route events based on popup state
-            if state.has_command_error_popup() {
-                // Modal popup event handling - only handle popup-specific events
-                if terminal_manager.should_close_popup(&event) {
-                    state.clear_command_error_popup();
-                    render_current_state(
-                        terminal_manager.terminal(),
-                        last_data.as_ref(),
-                        error_message.as_ref(),
-                        interval,
-                        &mut state,
-                    )?;
-                } else if terminal_manager.is_scroll_up(&event) {
-                    state.scroll_popup_up();
-                    render_current_state(
-                        terminal_manager.terminal(),
-                        last_data.as_ref(),
-                        error_message.as_ref(),
-                        interval,
-                        &mut state,
-                    )?;
-                } else if terminal_manager.is_scroll_down(&event) {
-                    state.scroll_popup_down();
-                    render_current_state(
-                        terminal_manager.terminal(),
-                        last_data.as_ref(),
-                        error_message.as_ref(),
-                        interval,
-                        &mut state,
-                    )?;
-                } else if matches!(event, crossterm::event::Event::Resize(_, _)) {
-                    render_current_state(
-                        terminal_manager.terminal(),
-                        last_data.as_ref(),
-                        error_message.as_ref(),
-                        interval,
-                        &mut state,
-                    )?;
-                }
-                // All other events are ignored when popup is active
-            } else {
-                // Normal application event handling
-                if matches!(event, crossterm::event::Event::Resize(_, _)) {
-                    render_current_state(
-                        terminal_manager.terminal(),
-                        last_data.as_ref(),
-                        error_message.as_ref(),
-                        interval,
-                        &mut state,
-                    )?;
-                }
+        let mut redraw = false;
+
+        // Load the next file once per interval, like a fetch
+        if !state.paused() && Instant::now() >= next_load {
+            next_load = Instant::now() + Duration::from_secs(interval);
+            match std::fs::read_to_string(files[file_index]) {
+                Ok(json_str) => match serde_json::from_str::<CephPgDump>(&json_str) {
+                    Ok(data) => {
+                        state.update(&data, interval);
+                        last_data = Some(data);
+                        error_message = None;
+                        file_index = (file_index + 1) % files.len();
+                    }
+                    Err(e) => error_message = Some(format!("Parse error: {e}")),
+                },
+                Err(e) => error_message = Some(format!("File read error: {e}")),
             }
+            redraw = true;
         }
 
-        // Load data
-        match std::fs::read_to_string(files[file_index]) {
-            Ok(json_str) => match serde_json::from_str::<CephPgDump>(&json_str) {
-                Ok(data) => {
-                    last_data = Some(data);
-                    error_message = None;
-                    render_current_state(
-                        terminal_manager.terminal(),
-                        last_data.as_ref(),
-                        error_message.as_ref(),
-                        interval,
-                        &mut state,
-                    )?;
-                    file_index = (file_index + 1) % files.len();
+        // Handle events
+        if terminal_manager.poll_event(Duration::from_millis(100))? {
+            let event = terminal_manager.read_event()?;
+            match handle_event(terminal_manager.keymap(), &mut state, &event) {
+                EventOutcome::Quit => break,
+                EventOutcome::Redraw => redraw = true,
+                // The files are not polled, so only pausing and loading the next one apply
+                EventOutcome::Control(RuntimeControl::TogglePause) => {
+                    state.set_paused(!state.paused());
+                    redraw = true;
                 }
-                Err(e) => {
-                    error_message = Some(format!("Parse error: {e}"));
-                    render_current_state(
-                        terminal_manager.terminal(),
-                        last_data.as_ref(),
-                        error_message.as_ref(),
-                        interval,
-                        &mut state,
-                    )?;
+                EventOutcome::Control(RuntimeControl::RefreshNow) => next_load = Instant::now(),
+                EventOutcome::Control(_) | EventOutcome::Ignore => {}
+                EventOutcome::Export => {
+                    let notice = export::export_screen(terminal_manager.terminal(), |t| {
+                        render_current_state(
+                            t,
+                            last_data.as_ref(),
+                            error_message.as_ref(),
+                            interval,
+                            &state,
+                        )
+                        .map(|_| ())
+                    });
+                    state.set_notice(notice);
+                    redraw = true;
                 }
-            },
-            Err(e) => {
-                error_message = Some(format!("File read error: {e}"));
-                render_current_state(
-                    terminal_manager.terminal(),
-                    last_data.as_ref(),
-                    error_message.as_ref(),
-                    interval,
-                    &mut state,
-                )?;
             }
         }
 
-        // Sleep with event checking
-        match terminal::sleep_with_event_check(interval, &terminal_manager).await? {
-            SleepResult::Quit => {
-                terminal_manager.cleanup()?;
-                return Ok(());
-            }
-            SleepResult::Resize => {
-                // Trigger immediate redraw on resize
-                render_current_state(
-                    terminal_manager.terminal(),
-                    last_data.as_ref(),
-                    error_message.as_ref(),
-                    interval,
-                    &mut state,
-                )?;
-            }
-            SleepResult::PopupClose => {
-                state.clear_command_error_popup();
-                render_current_state(
-                    terminal_manager.terminal(),
-                    last_data.as_ref(),
-                    error_message.as_ref(),
-                    interval,
-                    &mut state,
-                )?;
-            }
-            SleepResult::PopupScrollUp => {
-                state.scroll_popup_up();
-                render_current_state(
-                    terminal_manager.terminal(),
-                    last_data.as_ref(),
-                    error_message.as_ref(),
-                    interval,
-                    &mut state,
-                )?;
-            }
-            SleepResult::PopupScrollDown => {
-                state.scroll_popup_down();
-                render_current_state(
-                    terminal_manager.terminal(),
-                    last_data.as_ref(),
-                    error_message.as_ref(),
-                    interval,
-                    &mut state,
-                )?;
-            }
-            SleepResult::Continue => {
-                // Normal flow, continue to next iteration
-            }
+        if redraw {
+            let areas = render_current_state(
+                terminal_manager.terminal(),
+                last_data.as_ref(),
+                error_message.as_ref(),
+                interval,
+                &state,
+            )?;
+            state.set_mouse_areas(areas);
         }
     }
 
@@ -190,313 +120,336 @@ pub async fn run_test(interval: u64) -> Result<()> {
     Ok(())
 }
 
-pub async fn run(interval: u64, prefix_args: Option<&[String]>) -> Result<()> {
-    let mut terminal_manager = TerminalManager::new()?;
-    let mut last_data: Option<CephPgDump> = None;
-    let mut error_message: Option<String> = None;
-    let mut state = MonitorState::new();
+pub const DEFAULT_INTERVAL_SECS: u64 = 5;
+pub const DEFAULT_PING_THRESHOLD_MS: f64 = 1000.0;
+pub const DEFAULT_TIMEOUT_SECS: u64 = 60;
+
+/// PG states shown on the overview tab; the PG states tab shows all of them
+const OVERVIEW_PG_STATES: usize = 10;
+
+pub struct MonitorOptions {
+    pub interval: u64,
+    pub source: DataSource,
+    pub ping_threshold_ms: f64,
+    pub timeout: u64,
+    pub theme: Theme,
+    /// SQLite database for the sample history, None to keep no history
+    pub history_db: Option<PathBuf>,
+    /// File the events are appended to
+    pub event_log: Option<PathBuf>,
+    /// Where to send events, None without alert sinks
+    pub alerts: Option<AlertSettings>,
+    pub osd_movement: OsdMovementSettings,
+    pub keymap: Keymap,
+    /// Capture the mouse for clicks and the wheel
+    pub mouse: bool,
+}
+
+pub async fn run(options: MonitorOptions) -> Result<()> {
+    let mut terminal_manager =
+        TerminalManager::new(options.mouse)?.with_keymap(options.keymap.clone());
+    let mut cluster = ClusterMonitor::new(
+        options.source,
+        options.interval,
+        options.timeout,
+        options.ping_threshold_ms,
+        options.theme,
+    );
+    if let Some(path) = &options.history_db {
+        cluster = cluster.with_history(path);
+    }
+    if let Some(path) = &options.event_log {
+        cluster = cluster.with_event_log(path);
+    }
+    if let Some(alerts) = &options.alerts {
+        cluster = cluster.with_alerts(alerts)?;
+    }
+    cluster
+        .state_mut()
+        .set_osd_movement_table(OsdMovementTable::new(&options.osd_movement));
+    cluster.state_mut().set_keymap(options.keymap);
+    cluster.state_mut().set_mouse(options.mouse);
 
     // Draw initial loading screen
-    render_current_state(
-        terminal_manager.terminal(),
-        None,
-        None,
-        interval,
-        &mut state,
-    )?;
+    cluster.draw(terminal_manager.terminal())?;
 
     loop {
+        let mut redraw = cluster.poll();
+
         // Handle events
-        if terminal_manager.poll_event(std::time::Duration::from_millis(100))? {
+        if terminal_manager.poll_event(Duration::from_millis(100))? {
             let event = terminal_manager.read_event()?;
-
-            // Always handle quit events
-            if terminal_manager.should_quit(&event) {
-                break;
-            }
-
-            // Route events based on popup state
-            if state.has_command_error_popup() {
-                // Modal popup event handling - only handle popup-specific events
-                if terminal_manager.should_close_popup(&event) {
-                    state.clear_command_error_popup();
-                    render_current_state(
-                        terminal_manager.terminal(),
-                        last_data.as_ref(),
-                        error_message.as_ref(),
-                        interval,
-                        &mut state,
-                    )?;
-                } else if terminal_manager.is_scroll_up(&event) {
-                    state.scroll_popup_up();
-                    render_current_state(
-                        terminal_manager.terminal(),
-                        last_data.as_ref(),
-                        error_message.as_ref(),
-                        interval,
-                        &mut state,
-                    )?;
-                } else if terminal_manager.is_scroll_down(&event) {
-                    state.scroll_popup_down();
-                    render_current_state(
-                        terminal_manager.terminal(),
-                        last_data.as_ref(),
-                        error_message.as_ref(),
-                        interval,
-                        &mut state,
-                    )?;
-                } else if matches!(event, crossterm::event::Event::Resize(_, _)) {
-                    render_current_state(
-                        terminal_manager.terminal(),
-                        last_data.as_ref(),
-                        error_message.as_ref(),
-                        interval,
-                        &mut state,
-                    )?;
-                }
-                // All other events are ignored when popup is active
-            } else {
-                // Normal application event handling
-                if matches!(event, crossterm::event::Event::Resize(_, _)) {
-                    render_current_state(
-                        terminal_manager.terminal(),
-                        last_data.as_ref(),
-                        error_message.as_ref(),
-                        interval,
-                        &mut state,
-                    )?;
-                }
-            }
-        }
-
-        // Don't fetch data if popup is open
-        if state.has_command_error_popup() {
-            // Just sleep and check for events
-            match terminal::sleep_with_event_check(interval, &terminal_manager).await? {
-                SleepResult::Quit => {
-                    terminal_manager.cleanup()?;
-                    return Ok(());
-                }
-                SleepResult::Resize => {
-                    // Trigger immediate redraw on resize
-                    render_current_state(
-                        terminal_manager.terminal(),
-                        last_data.as_ref(),
-                        error_message.as_ref(),
-                        interval,
-                        &mut state,
-                    )?;
-                }
-                SleepResult::PopupClose => {
-                    state.clear_command_error_popup();
-                    render_current_state(
-                        terminal_manager.terminal(),
-                        last_data.as_ref(),
-                        error_message.as_ref(),
-                        interval,
-                        &mut state,
-                    )?;
+            match handle_event(terminal_manager.keymap(), cluster.state_mut(), &event) {
+                EventOutcome::Quit => break,
+                EventOutcome::Redraw => redraw = true,
+                EventOutcome::Control(control) => {
+                    cluster.control(control);
+                    redraw = true;
                 }
-                SleepResult::PopupScrollUp => {
-                    state.scroll_popup_up();
-                    render_current_state(
-                        terminal_manager.terminal(),
-                        last_data.as_ref(),
-                        error_message.as_ref(),
-                        interval,
-                        &mut state,
-                    )?;
-                }
-                SleepResult::PopupScrollDown => {
-                    state.scroll_popup_down();
-                    render_current_state(
-                        terminal_manager.terminal(),
-                        last_data.as_ref(),
-                        error_message.as_ref(),
-                        interval,
-                        &mut state,
-                    )?;
-                }
-                SleepResult::Continue => {
-                    // Normal flow, continue to next iteration
+                EventOutcome::Export => {
+                    let notice =
+                        export::export_screen(terminal_manager.terminal(), |t| cluster.draw(t));
+                    cluster.state_mut().set_notice(notice);
+                    redraw = true;
                 }
+                EventOutcome::Ignore => {}
             }
-            continue;
         }
 
-        // Fetch data
-        match fetch_ceph_pg_dump(prefix_args).await {
-            Ok(data) => {
-                last_data = Some(data);
-                error_message = None;
-                render_current_state(
-                    terminal_manager.terminal(),
-                    last_data.as_ref(),
-                    error_message.as_ref(),
-                    interval,
-                    &mut state,
-                )?;
-            }
-            Err(e) => {
-                // Check if this is a CommandError (special format)
-                if let Some(cmd_error_str) = e.to_string().strip_prefix("CommandError:") {
-                    // This is a command error, show it in popup
-                    if let Ok(cmd_error) = parse_command_error(cmd_error_str) {
-                        state.set_command_error_popup(cmd_error);
-                        error_message = None; // Clear regular error message
-                    } else {
-                        error_message = Some(e.to_string());
-                    }
-                } else {
-                    error_message = Some(e.to_string());
-                }
-                render_current_state(
-                    terminal_manager.terminal(),
-                    last_data.as_ref(),
-                    error_message.as_ref(),
-                    interval,
-                    &mut state,
-                )?;
-            }
-        }
-
-        // Sleep with event checking
-        match terminal::sleep_with_event_check(interval, &terminal_manager).await? {
-            SleepResult::Quit => {
-                terminal_manager.cleanup()?;
-                return Ok(());
-            }
-            SleepResult::Resize => {
-                // Trigger immediate redraw on resize
-                render_current_state(
-                    terminal_manager.terminal(),
-                    last_data.as_ref(),
-                    error_message.as_ref(),
-                    interval,
-                    &mut state,
-                )?;
-            }
-            SleepResult::PopupClose => {
-                state.clear_command_error_popup();
-                render_current_state(
-                    terminal_manager.terminal(),
-                    last_data.as_ref(),
-                    error_message.as_ref(),
-                    interval,
-                    &mut state,
-                )?;
-            }
-            SleepResult::PopupScrollUp => {
-                state.scroll_popup_up();
-                render_current_state(
-                    terminal_manager.terminal(),
-                    last_data.as_ref(),
-                    error_message.as_ref(),
-                    interval,
-                    &mut state,
-                )?;
-            }
-            SleepResult::PopupScrollDown => {
-                state.scroll_popup_down();
-                render_current_state(
-                    terminal_manager.terminal(),
-                    last_data.as_ref(),
-                    error_message.as_ref(),
-                    interval,
-                    &mut state,
-                )?;
-            }
-            SleepResult::Continue => {
-                // Normal flow, continue to next iteration
-            }
+        if redraw {
+            cluster.draw(terminal_manager.terminal())?;
         }
     }
 
+    cluster.cancel();
     terminal_manager.cleanup()?;
     Ok(())
 }
 
-async fn fetch_ceph_pg_dump(prefix_args: Option<&[String]>) -> Result<CephPgDump> {
-    let mut command = if let Some(args) = prefix_args.filter(|args| !args.is_empty()) {
-        let mut cmd = Command::new(&args[0]);
-        for arg in &args[1..] {
-            cmd.arg(arg);
-        }
-        cmd.arg("ceph");
-        cmd
-    } else {
-        Command::new("ceph")
-    };
+enum EventOutcome {
+    Quit,
+    Redraw,
+    /// Handled by the `ClusterMonitor` that owns the polling
+    Control(RuntimeControl),
+    /// Save the screen; handled by the caller, which can draw it again
+    Export,
+    Ignore,
+}
 
-    // Add the ceph command arguments
-    command.args(["pg", "dump", "--format", "json-pretty"]);
+fn handle_event(
+    keymap: &Keymap,
+    state: &mut MonitorState,
+    event: &crossterm::event::Event,
+) -> EventOutcome {
+    // While a filter is typed, keys are text; only Ctrl+C still quits
+    if state.event_log().editing {
+        return match keymap.text_input(event) {
+            Some(input) => {
+                edit_event_filter(state, input);
+                EventOutcome::Redraw
+            }
+            None => filter_field_event(event),
+        };
+    }
+    if state.pg_browser().editing {
+        return match keymap.text_input(event) {
+            Some(input) => {
+                edit_pg_filter(state, input);
+                EventOutcome::Redraw
+            }
+            None => filter_field_event(event),
+        };
+    }
 
-    let output = command.output()?;
+    if let crossterm::event::Event::Mouse(mouse) = event {
+        return mouse::handle_mouse_event(state, mouse);
+    }
 
-    if !output.status.success() {
-        let stdout_str = String::from_utf8_lossy(&output.stdout);
-        let stderr_str = String::from_utf8_lossy(&output.stderr);
+    // The help is modal too; its own key, Esc and Enter close it
+    if state.help() {
+        if keymap.matches(Action::Help, event) || keymap.matches(Action::ClosePopup, event) {
+            state.toggle_help();
+            return EventOutcome::Redraw;
+        }
+        if !keymap.matches(Action::Quit, event)
+            && !matches!(event, crossterm::event::Event::Resize(_, _))
+        {
+            return EventOutcome::Ignore;
+        }
+    }
 
-        let command_str = if let Some(args) = prefix_args.filter(|args| !args.is_empty()) {
-            format!("{} ceph pg dump --format json-pretty", args.join(" "))
-        } else {
-            "ceph pg dump --format json-pretty".to_string()
-        };
+    // The column chooser is modal; Esc closes it instead of quitting
+    if state.osd_movement_table().chooser.is_some() {
+        if let Some(outcome) = handle_column_chooser_event(keymap, state, event) {
+            return outcome;
+        }
+    }
 
-        // Create the command error in our simple format
-        let cmd_error_str = format!(
-            "CommandError:{}|{}|{}|{}",
-            command_str,
-            output.status.code().unwrap_or(-1),
-            stdout_str,
-            stderr_str
-        );
+    // Always handle quit and resize events
+    if keymap.matches(Action::Quit, event) {
+        return EventOutcome::Quit;
+    }
+    if matches!(event, crossterm::event::Event::Resize(_, _)) {
+        return EventOutcome::Redraw;
+    }
 
-        return Err(cmd_error_str.into());
+    if state.has_command_error_popup() {
+        // Modal popup event handling - only handle popup-specific events
+        if keymap.matches(Action::ClosePopup, event) {
+            state.clear_command_error_popup();
+        } else if keymap.matches(Action::Up, event) {
+            state.scroll_popup_up();
+        } else if keymap.matches(Action::Down, event) {
+            state.scroll_popup_down();
+        } else if keymap.matches(Action::Export, event) {
+            return EventOutcome::Export;
+        } else {
+            // All other events are ignored when popup is active
+            return EventOutcome::Ignore;
+        }
+        EventOutcome::Redraw
+    } else if keymap.matches(Action::Help, event) {
+        state.toggle_help();
+        EventOutcome::Redraw
+    } else if keymap.matches(Action::Export, event) {
+        EventOutcome::Export
+    } else if let Some(action) = keymap.view_action(event) {
+        handle_view_action(state, action);
+        EventOutcome::Redraw
+    } else if let Some(control) = keymap.runtime_control(event) {
+        EventOutcome::Control(control)
+    } else if state.active_view() == state::View::Events {
+        handle_event_log_event(keymap, state, event)
+    } else if state.active_view() == state::View::OsdMovement
+        && keymap.matches(Action::ColumnChooser, event)
+    {
+        state.osd_movement_table_mut().chooser = Some(0);
+        EventOutcome::Redraw
+    } else if state.active_view() == state::View::Pgs && keymap.matches(Action::Search, event) {
+        state.pg_browser_mut().editing = true;
+        EventOutcome::Redraw
+    } else if let Some(delta) = keymap.selection_move(event) {
+        state.move_selection(delta);
+        EventOutcome::Redraw
+    } else {
+        EventOutcome::Ignore
     }
+}
 
-    let json_str = String::from_utf8(output.stdout)?;
+/// Keys that are no text while a filter is typed: all but Ctrl+C and resizing
+/// are ignored, so the filter keeps the keyboard until it is closed
+fn filter_field_event(event: &crossterm::event::Event) -> EventOutcome {
+    use crossterm::event::{Event, KeyCode, KeyModifiers};
+    match event {
+        Event::Resize(_, _) => EventOutcome::Redraw,
+        Event::Key(key)
+            if key.code == KeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL) =>
+        {
+            EventOutcome::Quit
+        }
+        _ => EventOutcome::Ignore,
+    }
+}
 
-    let data: CephPgDump = serde_json::from_str(&json_str).map_err(|e| {
-        let stderr_str = String::from_utf8_lossy(&output.stderr);
-        let mut error_msg = format!(
-            "Failed to parse ceph command output as JSON: {}\n\nCommand output was:\n{}",
-            e,
-            json_str.trim()
-        );
+fn handle_event_log_event(
+    keymap: &Keymap,
+    state: &mut MonitorState,
+    event: &crossterm::event::Event,
+) -> EventOutcome {
+    let cluster_name = state.cluster_name().map(str::to_string);
+    let log = state.event_log_mut();
+    if keymap.matches(Action::Search, event) {
+        log.editing = true;
+    } else if keymap.matches(Action::Up, event) {
+        log.scroll = log.scroll.saturating_sub(1);
+    } else if keymap.matches(Action::Down, event) {
+        log.scroll = (log.scroll + 1).min(log.filtered().len().saturating_sub(1));
+    } else if keymap.matches(Action::Save, event) {
+        log.notice = Some(match save_event_log(log, cluster_name.as_deref()) {
+            Ok(message) => message,
+            Err(e) => format!("Save failed: {e}"),
+        });
+    } else {
+        return EventOutcome::Ignore;
+    }
+    EventOutcome::Redraw
+}
 
-        if !stderr_str.trim().is_empty() {
-            error_msg.push_str(&format!("\n\nStderr output:\n{}", stderr_str.trim()));
+fn edit_event_filter(state: &mut MonitorState, input: TextInput) {
+    let log = state.event_log_mut();
+    match input {
+        TextInput::Char(c) => log.filter.push(c),
+        TextInput::Backspace => {
+            log.filter.pop();
         }
+        TextInput::Done => log.editing = false,
+        TextInput::Cancel => {
+            log.filter.clear();
+            log.editing = false;
+        }
+    }
+    log.scroll = 0;
+}
 
-        error_msg
-    })?;
-
-    Ok(data)
+/// Keys of the open column chooser; None for quitting, which it leaves to the monitor
+fn handle_column_chooser_event(
+    keymap: &Keymap,
+    state: &mut MonitorState,
+    event: &crossterm::event::Event,
+) -> Option<EventOutcome> {
+    let table = state.osd_movement_table_mut();
+    let choosable = state::OsdMovementTable::choosable();
+    let cursor = table.chooser.unwrap_or(0);
+    if keymap.matches(Action::ColumnChooser, event) || keymap.matches(Action::Back, event) {
+        table.chooser = None;
+    } else if keymap.matches(Action::Toggle, event) {
+        table.toggle_column(choosable[cursor]);
+    } else if let Some(delta) = keymap.selection_move(event) {
+        table.chooser = Some(cursor.saturating_add_signed(delta).min(choosable.len() - 1));
+    } else if matches!(event, crossterm::event::Event::Resize(_, _)) {
+        // Redraw below
+    } else if keymap.matches(Action::Quit, event) {
+        return None;
+    } else {
+        return Some(EventOutcome::Ignore);
+    }
+    Some(EventOutcome::Redraw)
 }
 
-fn count_unique_pg_states(pg_stats: &[crate::common::PgStats]) -> usize {
-    use std::collections::HashSet;
+fn edit_pg_filter(state: &mut MonitorState, input: TextInput) {
+    let browser = state.pg_browser_mut();
+    let mut filter = browser.filter().to_string();
+    match input {
+        TextInput::Char(c) => filter.push(c),
+        TextInput::Backspace => {
+            filter.pop();
+        }
+        TextInput::Done => browser.editing = false,
+        TextInput::Cancel => {
+            filter.clear();
+            browser.editing = false;
+        }
+    }
+    browser.set_filter(filter);
+    state.reset_selection();
+}
 
-    let mut unique_states = HashSet::new();
-    for pg in pg_stats {
-        unique_states.insert(&pg.state);
+/// Write the shown events, oldest first, to a file in the current directory
+fn save_event_log(log: &state::EventLog, cluster: Option<&str>) -> Result<String> {
+    let path = format!(
+        "ceph-doctor-events-{}.log",
+        chrono::Utc::now().format("%Y%m%d-%H%M%S")
+    );
+    let events = log.filtered();
+    let mut text = String::new();
+    for event in events.iter().rev() {
+        text.push_str(&event.format_line(cluster));
+        text.push('\n');
     }
+    std::fs::write(&path, text)?;
+    Ok(format!("Saved {} events to {path}", events.len()))
+}
 
-    // Return the number of unique states, but show at least top 10
-    std::cmp::min(unique_states.len(), 10)
+fn handle_view_action(state: &mut MonitorState, action: ViewAction) {
+    match action {
+        ViewAction::NextView => state.next_view(),
+        ViewAction::PreviousView => state.previous_view(),
+        ViewAction::SelectView(view) => state.select_view(view),
+        ViewAction::SortNext => state.cycle_sort(),
+        ViewAction::SortReverse => state.reverse_sort(),
+    }
 }
 
-fn render_main_ui(
+/// Header, tab bar, error box, the active tab and the footer; returns
+/// where the tabs and the table of the active tab were drawn
+fn render_tab_ui(
     f: &mut ratatui::Frame,
     data: &CephPgDump,
-    _last_data: Option<&CephPgDump>,
     interval: u64,
     error_msg: &Option<String>,
-    state: &mut MonitorState,
-) {
-    // Check for NO_COLOR environment variable
-    let use_colors = env::var("NO_COLOR").is_err();
+    state: &MonitorState,
+) -> MouseAreas {
+    let theme = state.theme();
     let size = f.area();
 
     // Create main layout
@@ -504,13 +457,21 @@ fn render_main_ui(
         .direction(ratatui::layout::Direction::Vertical)
         .constraints([
             ratatui::layout::Constraint::Length(3), // Header
+            ratatui::layout::Constraint::Length(1), // Tabs
             ratatui::layout::Constraint::Min(0),    // Content
             ratatui::layout::Constraint::Length(3), // Footer
         ])
         .split(size);
 
     // Render header
-    render_header(f, main_layout[0], data, interval);
+    render_header(f, main_layout[0], data, interval, state);
+    let tabs = render_tab_bar(
+        f,
+        main_layout[1],
+        state.active_view(),
+        state.keymap(),
+        theme,
+    );
 
     // Render error message if present
     let content_area = if let Some(ref error) = error_msg {
@@ -520,21 +481,117 @@ fn render_main_ui(
                 ratatui::layout::Constraint::Length(3), // Error
                 ratatui::layout::Constraint::Min(0),    // Content
             ])
-            .split(main_layout[1]);
+            .split(main_layout[2]);
 
-        render_error(f, error_layout[0], error, use_colors);
+        render_error(f, error_layout[0], error, theme);
         error_layout[1]
     } else {
-        main_layout[1]
+        main_layout[2]
     };
 
+    let table = render_view(f, content_area, data, state);
+
+    // Render footer
+    render_footer(
+        f,
+        main_layout[3],
+        state.cluster_name().is_some(),
+        state.paused(),
+        state.notice(),
+        state.keymap(),
+    );
+
+    MouseAreas { tabs, table }
+}
+
+/// The content of the active tab; returns where its table was drawn
+fn render_view(
+    f: &mut ratatui::Frame,
+    area: ratatui::layout::Rect,
+    data: &CephPgDump,
+    state: &MonitorState,
+) -> Option<TableArea> {
+    let theme = state.theme();
+    let view = state.active_view();
+    let rows = state.tab_rows(view);
+    let selected = (rows > 0).then(|| state.tab(view).selected.min(rows - 1));
+
+    match view {
+        state::View::Overview => Some(render_overview(f, area, data, state)),
+        state::View::OsdMovement => Some(render_osd_data_movement_table(
+            f,
+            area,
+            state.osd_movement_rows(),
+            state.osd_movement_table(),
+            selected,
+            theme,
+        )),
+        state::View::InconsistentPgs => Some(render_inconsistent_pgs_table(
+            f,
+            area,
+            state.get_inconsistent_pg_progress(),
+            selected,
+            theme,
+        )),
+        state::View::PgStates => Some(render_pg_states(
+            f,
+            area,
+            state.pg_states(),
+            None,
+            selected,
+            theme,
+        )),
+        state::View::OsdOverview => {
+            let (osd_overviews, medians) = state.osd_overview();
+            Some(render_osd_overview_table(
+                f,
+                area,
+                osd_overviews.to_vec(),
+                medians,
+                state.osd_overview_sort(),
+                selected,
+                theme,
+            ))
+        }
+        state::View::Network => {
+            let (slow_links, host_summaries) = state.slow_links();
+            let threshold_ms = state.ping_threshold_ms();
+            render_network_view(f, area, slow_links, host_summaries, threshold_ms, theme);
+            None
+        }
+        state::View::Events => {
+            render_event_log(f, area, state.event_log(), state.keymap(), theme);
+            None
+        }
+        state::View::Pgs => Some(render_pg_list(
+            f,
+            area,
+            state.pg_browser(),
+            state.keymap(),
+            selected,
+            theme,
+        )),
+    }
+}
+
+/// Recovery progress, PG states, inconsistent PGs and OSD data movement in
+/// one stack; returns where the OSD data movement table was drawn, whose
+/// headers sort it here too
+fn render_overview(
+    f: &mut ratatui::Frame,
+    area: ratatui::layout::Rect,
+    data: &CephPgDump,
+    state: &MonitorState,
+) -> TableArea {
+    let theme = state.theme();
+
     // Calculate dynamic heights
-    let inconsistent_pgs = calculate_inconsistent_pg_progress(data, state, interval);
+    let inconsistent_pgs = state.get_inconsistent_pg_progress();
     let recovery_progress_height = calculate_recovery_progress_height(data);
 
-    // Calculate PG states height dynamically based on content
-    let pg_states_count = count_unique_pg_states(&data.pg_map.pg_stats);
-    let pg_states_height = (pg_states_count + 3).clamp(4, 15) as u16;
+    // Calculate PG states height dynamically based on content, top 10 at most
+    let pg_states_height =
+        (state.pg_states().len().min(OVERVIEW_PG_STATES) + 3).clamp(4, 15) as u16;
 
     // Split content area into sections based on whether there are inconsistent PGs
     let (content_layout, osd_layout_index) = if inconsistent_pgs.is_empty() {
@@ -546,7 +603,7 @@ fn render_main_ui(
                 ratatui::layout::Constraint::Length(pg_states_height), // PG states (dynamic)
                 ratatui::layout::Constraint::Min(0),                   // OSD Data Movement
             ])
-            .split(content_area);
+            .split(area);
         (layout, 2)
     } else {
         // Show inconsistent PGs block
@@ -559,32 +616,45 @@ fn render_main_ui(
                 ratatui::layout::Constraint::Length(inconsistent_pgs_height), // Inconsistent PGs (dynamic)
                 ratatui::layout::Constraint::Min(0),                          // OSD Data Movement
             ])
-            .split(content_area);
+            .split(area);
         (layout, 3)
     };
 
     // Render sections
-    render_recovery_progress(f, content_layout[0], data, state, interval);
-    render_pg_states(f, content_layout[1], data, use_colors);
+    render_recovery_progress(f, content_layout[0], state.get_recovery_progress());
+    render_pg_states(
+        f,
+        content_layout[1],
+        state.pg_states(),
+        Some(OVERVIEW_PG_STATES),
+        None,
+        theme,
+    );
 
     // Only render inconsistent PGs table if there are inconsistent PGs
     if !inconsistent_pgs.is_empty() {
-        render_inconsistent_pgs_table(f, content_layout[2], inconsistent_pgs, use_colors);
+        render_inconsistent_pgs_table(f, content_layout[2], inconsistent_pgs, None, theme);
     }
 
-    let osd_data_movements = calculate_osd_data_movement(data, state, interval);
     render_osd_data_movement_table(
         f,
         content_layout[osd_layout_index],
-        osd_data_movements,
-        use_colors,
-    );
-
-    // Render footer
-    render_footer(f, main_layout[2]);
+        state.osd_movement_rows(),
+        state.osd_movement_table(),
+        None,
+        theme,
+    )
 }
 
-fn render_loading_screen(f: &mut ratatui::Frame, interval: u64) {
+fn render_loading_screen(
+    f: &mut ratatui::Frame,
+    interval: u64,
+    fetch_description: &str,
+    fetch_elapsed: Option<Duration>,
+    paused: bool,
+    keymap: &Keymap,
+    in_cluster_list: bool,
+) {
     use ratatui::prelude::*;
     use ratatui::widgets::*;
 
@@ -606,7 +676,7 @@ fn render_loading_screen(f: &mut ratatui::Frame, interval: u64) {
         .title("Ceph Doctor - Monitor")
         .title_style(Style::default().add_modifier(Modifier::BOLD));
 
-    let header_content = format!("Refresh interval: {interval} seconds");
+    let header_content = refresh_line(interval, paused, keymap);
     let header_paragraph = Paragraph::new(header_content)
         .block(header_block)
         .style(Style::default());
@@ -619,7 +689,10 @@ fn render_loading_screen(f: &mut ratatui::Frame, interval: u64) {
         .title("Status")
         .title_style(Style::default().add_modifier(Modifier::BOLD));
 
-    let loading_text = "Loading cluster data...\n\nFetching: ceph pg dump --format json-pretty\n\nPress 'q', Ctrl+C, or Esc to quit";
+    let elapsed = fetch_elapsed.map_or(String::new(), |e| format!(" ({}s)", e.as_secs()));
+    let quit_hint = quit_hint(in_cluster_list, keymap);
+    let loading_text =
+        format!("Loading cluster data...\n\nFetching: {fetch_description}{elapsed}\n\n{quit_hint}");
     let loading_paragraph = Paragraph::new(loading_text)
         .block(loading_block)
         .style(Style::default())
@@ -629,43 +702,110 @@ fn render_loading_screen(f: &mut ratatui::Frame, interval: u64) {
     f.render_widget(loading_paragraph, main_layout[1]);
 
     // Footer
-    render_footer(f, main_layout[2]);
+    render_footer(f, main_layout[2], in_cluster_list, paused, None, keymap);
 }
 
-fn render_current_state(
-    terminal: &mut Terminal<CrosstermBackend<std::io::Stdout>>,
+/// Draw the loading screen, the error screen or the active view, plus the
+/// command error popup, and return where the mouse can click. Generic over
+/// the backend so tests can render into a `TestBackend`.
+fn render_current_state<B: Backend>(
+    terminal: &mut Terminal<B>,
     data: Option<&CephPgDump>,
     error: Option<&String>,
     interval: u64,
-    state: &mut MonitorState,
-) -> Result<()> {
+    state: &MonitorState,
+) -> Result<MouseAreas> {
+    let mut mouse_areas = MouseAreas::default();
     terminal.draw(|f| {
         // First render the main UI
         match (data, error) {
             (Some(data), _) => {
                 // Has data - render main UI (may also show error overlay)
                 let error_opt = error.cloned();
-                render_main_ui(f, data, None, interval, &error_opt, state);
+                mouse_areas = render_tab_ui(f, data, interval, &error_opt, state);
             }
             (None, Some(error)) => {
                 // No data but has error - render error screen
-                render_error_screen(f, error, interval);
+                render_error_screen(
+                    f,
+                    error,
+                    interval,
+                    state.paused(),
+                    state.notice(),
+                    state.keymap(),
+                    state.cluster_name().is_some(),
+                );
             }
             (None, None) => {
                 // No data and no error - render loading screen
-                render_loading_screen(f, interval);
+                render_loading_screen(
+                    f,
+                    interval,
+                    state.fetch_description(),
+                    state.fetch_elapsed(),
+                    state.paused(),
+                    state.keymap(),
+                    state.cluster_name().is_some(),
+                );
             }
         }
 
+        if data.is_some()
+            && state.active_view() == state::View::OsdMovement
+            && state.osd_movement_table().chooser.is_some()
+        {
+            render_column_chooser(f, state.osd_movement_table(), state.keymap());
+        }
+
         // Then render popup overlay if there's a command error popup
         if let Some(cmd_error) = state.get_command_error_popup() {
-            render_command_error_popup(f, cmd_error);
+            render_command_error_popup(f, cmd_error, state.keymap(), state.theme());
+        }
+
+        if state.help() {
+            render_help(f, state.keymap(), state.mouse());
         }
     })?;
-    Ok(())
+    Ok(mouse_areas)
+}
+
+/// The header line of the loading and error screens
+fn refresh_line(interval: u64, paused: bool, keymap: &Keymap) -> String {
+    if paused {
+        format!(
+            "Refresh interval: {interval} seconds • PAUSED, press {} to resume",
+            keymap.key(Action::Pause)
+        )
+    } else {
+        format!("Refresh interval: {interval} seconds")
+    }
 }
 
-fn render_error_screen(f: &mut ratatui::Frame, error: &str, interval: u64) {
+fn quit_hint(in_cluster_list: bool, keymap: &Keymap) -> String {
+    if in_cluster_list {
+        format!(
+            "Press {} to go back to the clusters, {} or Ctrl+C to quit",
+            keymap.key(Action::Back),
+            keymap.key(Action::Quit)
+        )
+    } else {
+        format!(
+            "Press {} or Ctrl+C to quit • {} for all keys",
+            keymap.describe(Action::Quit),
+            keymap.key(Action::Help)
+        )
+    }
+}
+
+fn render_error_screen(
+    f: &mut ratatui::Frame,
+    error: &str,
+    interval: u64,
+    paused: bool,
+    notice: Option<&str>,
+    keymap: &Keymap,
+    in_cluster_list: bool,
+) {
     use ratatui::prelude::*;
     use ratatui::widgets::*;
 
@@ -687,7 +827,7 @@ fn render_error_screen(f: &mut ratatui::Frame, error: &str, interval: u64) {
         .title("Ceph Doctor - Monitor")
         .title_style(Style::default().add_modifier(Modifier::BOLD));
 
-    let header_content = format!("Refresh interval: {interval} seconds");
+    let header_content = refresh_line(interval, paused, keymap);
     let header_paragraph = Paragraph::new(header_content)
         .block(header_block)
         .style(Style::default());
@@ -700,8 +840,8 @@ fn render_error_screen(f: &mut ratatui::Frame, error: &str, interval: u64) {
         .title("Error")
         .title_style(Style::default().add_modifier(Modifier::BOLD));
 
-    let error_text =
-        format!("Failed to fetch cluster data:\n\n{error}\n\nPress 'q', Ctrl+C, or Esc to quit");
+    let quit_hint = quit_hint(in_cluster_list, keymap);
+    let error_text = format!("Failed to fetch cluster data:\n\n{error}\n\n{quit_hint}");
     let error_paragraph = Paragraph::new(error_text)
         .block(error_block)
         .style(Style::default())
@@ -710,10 +850,15 @@ fn render_error_screen(f: &mut ratatui::Frame, error: &str, interval: u64) {
     f.render_widget(error_paragraph, main_layout[1]);
 
     // Footer
-    render_footer(f, main_layout[2]);
+    render_footer(f, main_layout[2], in_cluster_list, paused, notice, keymap);
 }
 
-fn render_command_error_popup(f: &mut ratatui::Frame, cmd_error: &state::CommandError) {
+fn render_command_error_popup(
+    f: &mut ratatui::Frame,
+    cmd_error: &state::CommandError,
+    keymap: &Keymap,
+    theme: &Theme,
+) {
     use ratatui::prelude::*;
     use ratatui::widgets::*;
 
@@ -735,7 +880,7 @@ fn render_command_error_popup(f: &mut ratatui::Frame, cmd_error: &state::Command
         .borders(Borders::ALL)
         .title(format!(" Command Error: {} ", cmd_error.command))
         .title_style(Style::default().add_modifier(Modifier::BOLD))
-        .border_style(Style::default().fg(Color::Red));
+        .border_style(theme.critical);
 
     let inner_area = popup_block.inner(popup_area);
     f.render_widget(popup_block, popup_area);
@@ -753,6 +898,12 @@ fn render_command_error_popup(f: &mut ratatui::Frame, cmd_error: &state::Command
     // Build content text
     let mut content_lines = Vec::new();
 
+    content_lines.push(Line::from(vec![
+        Span::styled("Diagnosis: ", Style::default().add_modifier(Modifier::BOLD)),
+        Span::from(cmd_error.class.label()),
+    ]));
+    content_lines.push(Line::from(""));
+
     if cmd_error.exit_code != 0 {
         content_lines.push(Line::from(vec![
             Span::styled("Exit code: ", Style::default().add_modifier(Modifier::BOLD)),
@@ -784,7 +935,7 @@ fn render_command_error_popup(f: &mut ratatui::Frame, cmd_error: &state::Command
     }
 
     content_lines.push(Line::from(Span::styled(
-        "This error suggests a problem with your Ceph configuration or connectivity.",
+        cmd_error.class.hint(),
         Style::default().add_modifier(Modifier::ITALIC),
     )));
 
@@ -797,8 +948,13 @@ fn render_command_error_popup(f: &mut ratatui::Frame, cmd_error: &state::Command
     // Empty separator line (content_layout[1] is left empty)
 
     // Footer instructions on the last line
-    let footer_text =
-        "[Esc/Enter/Space] Close popup • [↑/k ↓/j] Scroll • [q/Ctrl+C] Quit application";
+    let footer_text = format!(
+        "[{}] Close popup • [{} {}] Scroll • [{}/Ctrl+C] Quit application",
+        keymap.describe(Action::ClosePopup),
+        keymap.describe(Action::Up),
+        keymap.describe(Action::Down),
+        keymap.key(Action::Quit),
+    );
     let footer_paragraph = Paragraph::new(footer_text)
         .style(Style::default().add_modifier(Modifier::ITALIC))
         .alignment(Alignment::Center);
@@ -806,19 +962,476 @@ fn render_command_error_popup(f: &mut ratatui::Frame, cmd_error: &state::Command
     f.render_widget(footer_paragraph, content_layout[2]);
 }
 
-fn parse_command_error(error_str: &str) -> Result<state::CommandError> {
-    // Simple parsing - in a real implementation you might use JSON or a proper format
-    // For now, let's create a simple format: command|exit_code|stdout|stderr
-    let parts: Vec<&str> = error_str.split('|').collect();
-    if parts.len() >= 4 {
-        Ok(state::CommandError {
-            command: parts[0].to_string(),
-            exit_code: parts[1].parse().unwrap_or(-1),
-            stdout: parts[2].to_string(),
-            stderr: parts[3].to_string(),
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorClass;
+    use ratatui::backend::TestBackend;
+
+    const WIDTH: u16 = 120;
+    const HEIGHT: u16 = 45;
+
+    fn pg_dump(name: &str) -> CephPgDump {
+        let path = format!("{}/tests/fixtures/{name}", env!("CARGO_MANIFEST_DIR"));
+        serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap()
+    }
+
+    fn render(data: Option<&CephPgDump>, error: Option<&str>, state: &MonitorState) -> String {
+        render_sized(data, error, state, HEIGHT)
+    }
+
+    fn render_sized(
+        data: Option<&CephPgDump>,
+        error: Option<&str>,
+        state: &MonitorState,
+        height: u16,
+    ) -> String {
+        let mut terminal = Terminal::new(TestBackend::new(WIDTH, height)).unwrap();
+        let error = error.map(str::to_string);
+        render_current_state(&mut terminal, data, error.as_ref(), 5, state).unwrap();
+
+        let buffer = terminal.backend().buffer();
+        let mut text = String::new();
+        for y in 0..buffer.area.height {
+            let line: String = (0..buffer.area.width)
+                .map(|x| buffer[(x, y)].symbol())
+                .collect();
+            text.push_str(line.trim_end());
+            text.push('\n');
+        }
+        text
+    }
+
+    /// Handle a key press with the default keys
+    fn press(state: &mut MonitorState, code: crossterm::event::KeyCode) -> EventOutcome {
+        let key = crossterm::event::KeyEvent::new(code, crossterm::event::KeyModifiers::NONE);
+        handle_event(
+            &Keymap::default(),
+            state,
+            &crossterm::event::Event::Key(key),
+        )
+    }
+
+    /// A state that has seen one sample of the data
+    fn updated(data: &CephPgDump) -> MonitorState {
+        let mut state = MonitorState::new();
+        state.update(data, 5);
+        state
+    }
+
+    /// Compare with `tests/fixtures/render/<name>.txt`; run with
+    /// `UPDATE_SNAPSHOTS=1` to write the files after a deliberate UI change
+    fn assert_snapshot(name: &str, actual: &str) {
+        let path = format!(
+            "{}/tests/fixtures/render/{name}.txt",
+            env!("CARGO_MANIFEST_DIR")
+        );
+        if std::env::var_os("UPDATE_SNAPSHOTS").is_some() {
+            std::fs::write(&path, actual).unwrap();
+            return;
+        }
+        let expected = std::fs::read_to_string(&path)
+            .unwrap_or_else(|e| panic!("{path}: {e}; run with UPDATE_SNAPSHOTS=1 to create it"));
+        assert_eq!(actual, expected, "rendering differs from {path}");
+    }
+
+    #[test]
+    fn test_render_clean() {
+        let data = pg_dump("render/pg_dump_clean.json");
+        let text = render(Some(&data), None, &updated(&data));
+        assert!(!text.contains("╭Inconsistent PGs"));
+        assert_snapshot("clean", &text);
+    }
+
+    #[test]
+    fn test_render_stamp_in_utc() {
+        let mut data = pg_dump("render/pg_dump_clean.json");
+        data.pg_map.stamp = "2025-07-20T11:00:00.000000+0200".to_string();
+        let text = render(Some(&data), None, &updated(&data));
+        assert!(text.contains("Last Update: 2025-07-20 09:00:00 UTC"));
+    }
+
+    #[test]
+    fn test_render_recovering() {
+        let data = pg_dump("pg_dump.json");
+        let text = render(Some(&data), None, &updated(&data));
+        assert!(text.contains("active+remapped+backfilling"));
+        assert_snapshot("recovering", &text);
+    }
+
+    #[test]
+    fn test_render_inconsistent() {
+        let data = pg_dump("render/pg_dump_inconsistent.json");
+        let text = render(Some(&data), None, &updated(&data));
+        assert!(text.contains("1.14") && text.contains("1.1f"));
+        assert_snapshot("inconsistent", &text);
+    }
+
+    #[test]
+    fn test_render_osd_overview() {
+        let data = pg_dump("pg_dump.json");
+        let mut state = updated(&data);
+        state.select_view(state::View::OsdOverview);
+        assert_snapshot("osd_overview", &render(Some(&data), None, &state));
+    }
+
+    #[test]
+    fn test_render_tabs() {
+        let data = pg_dump("pg_dump.json");
+        let mut state = updated(&data);
+
+        state.select_view(state::View::OsdMovement);
+        state.move_selection(2);
+        assert_snapshot("osd_movement", &render(Some(&data), None, &state));
+
+        state.select_view(state::View::InconsistentPgs);
+        assert_snapshot("inconsistent_pgs", &render(Some(&data), None, &state));
+
+        state.select_view(state::View::PgStates);
+        state.move_selection(isize::MAX);
+        assert_snapshot("pg_states", &render(Some(&data), None, &state));
+    }
+
+    #[test]
+    fn test_osd_movement_columns() {
+        let data = pg_dump("pg_dump.json");
+        let mut state = updated(&data);
+        state.set_osd_hosts(
+            (0..8)
+                .map(|osd| (osd, format!("node{}", osd / 2)))
+                .collect(),
+        );
+        state.select_view(state::View::OsdMovement);
+        state.cycle_sort();
+        assert_eq!(
+            state.osd_movement_table().sort.column,
+            state::OsdMovementSortColumn::Excess
+        );
+
+        // Tabs without a sortable table leave every sort alone
+        let overview_sort = state.osd_overview_sort();
+        state.select_view(state::View::Events);
+        state.cycle_sort();
+        state.reverse_sort();
+        assert_eq!(state.osd_overview_sort(), overview_sort);
+        assert_eq!(
+            state.osd_movement_table().sort.column,
+            state::OsdMovementSortColumn::Excess
+        );
+        state.select_view(state::View::OsdMovement);
+
+        // Show host and bytes, hide the waiting counts in the chooser
+        let table = state.osd_movement_table_mut();
+        table.toggle_column(state::OsdMovementColumn::Host);
+        table.toggle_column(state::OsdMovementColumn::MissingBytes);
+        table.toggle_column(state::OsdMovementColumn::ExcessBytes);
+        table.toggle_column(state::OsdMovementColumn::MissingWaiting);
+        table.toggle_column(state::OsdMovementColumn::ExcessWaiting);
+        table.toggle_column(state::OsdMovementColumn::Osd);
+        assert_eq!(table.columns.len(), 10);
+        assert_eq!(table.columns[1], state::OsdMovementColumn::Host);
+        table.chooser = Some(0);
+        assert_snapshot("osd_movement_columns", &render(Some(&data), None, &state));
+    }
+
+    #[test]
+    fn test_osd_movement_scrolling() {
+        let data = pg_dump("pg_dump.json");
+        let mut state = updated(&data);
+        state.select_view(state::View::OsdMovement);
+
+        // Five of the eight OSDs fit
+        let top = render_sized(Some(&data), None, &state, 16);
+        assert!(top.contains("▼ 3 more rows"));
+        assert_snapshot("osd_movement_scrolled", &{
+            state.move_selection(6);
+            render_sized(Some(&data), None, &state, 16)
+        });
+        state.move_selection(isize::MAX);
+        assert!(!render_sized(Some(&data), None, &state, 16).contains("more rows"));
+
+        // The selection stays on the same OSD when the order changes
+        let selected_osd = |state: &MonitorState| {
+            let rows = state.osd_movement_rows();
+            rows[state.tab(state::View::OsdMovement).selected]
+                .movement
+                .osd_id
+        };
+        state.move_selection(-5);
+        let osd = selected_osd(&state);
+        state.cycle_sort();
+        assert_eq!(selected_osd(&state), osd);
+        state.reverse_sort();
+        assert_eq!(selected_osd(&state), osd);
+        state.update(&data, 5);
+        assert_eq!(selected_osd(&state), osd);
+    }
+
+    #[test]
+    fn test_render_pgs() {
+        let data = pg_dump("pg_dump.json");
+        let mut state = updated(&data);
+        state.select_view(state::View::Pgs);
+        state.reverse_sort();
+        state.move_selection(1);
+        assert_snapshot("pgs", &render(Some(&data), None, &state));
+
+        // Typing the filter narrows the rows and starts at the first one
+        for c in "state:backfill_wait osd:0".chars() {
+            edit_pg_filter(&mut state, TextInput::Char(c));
+        }
+        edit_pg_filter(&mut state, TextInput::Done);
+        assert_eq!(state.tab_rows(state::View::Pgs), 4);
+        assert_eq!(state.tab(state::View::Pgs).selected, 0);
+        assert_snapshot("pgs_filtered", &render(Some(&data), None, &state));
+    }
+
+    #[test]
+    fn test_tab_selection() {
+        let data = pg_dump("pg_dump.json");
+        let mut state = updated(&data);
+
+        // Every tab keeps its own selection, clamped to its rows
+        state.select_view(state::View::PgStates);
+        state.move_selection(isize::MAX);
+        assert_eq!(state.tab(state::View::PgStates).selected, 3);
+        state.move_selection(-1);
+        assert_eq!(state.tab(state::View::PgStates).selected, 2);
+
+        state.next_view();
+        assert_eq!(state.active_view(), state::View::OsdOverview);
+        state.move_selection(-5);
+        assert_eq!(state.tab(state::View::OsdOverview).selected, 0);
+        assert_eq!(state.tab(state::View::PgStates).selected, 2);
+
+        // Tabs without rows have nothing to select
+        state.select_view(state::View::Overview);
+        state.move_selection(1);
+        assert_eq!(state.tab(state::View::Overview).selected, 0);
+        state.previous_view();
+        assert_eq!(state.active_view(), state::View::Pgs);
+    }
+
+    #[test]
+    fn test_render_loading() {
+        let mut state = MonitorState::new();
+        state.set_fetch_description("ceph pg dump --format json".to_string());
+        assert_snapshot("loading", &render(None, None, &state));
+    }
+
+    #[test]
+    fn test_render_fetch_error() {
+        let data = pg_dump("pg_dump.json");
+        let error = "Timeout: ceph pg dump did not finish within 60s";
+        let with_data = render(Some(&data), Some(error), &updated(&data));
+        assert!(with_data.contains(error));
+        assert_snapshot("fetch_error", &with_data);
+        assert_snapshot(
+            "fetch_error_no_data",
+            &render(None, Some(error), &MonitorState::new()),
+        );
+    }
+
+    #[test]
+    fn test_render_command_error_popup() {
+        let data = pg_dump("pg_dump.json");
+        let mut state = updated(&data);
+        state.set_command_error_popup(state::CommandError {
+            command: "ceph pg dump --format json".to_string(),
+            stdout: String::new(),
+            stderr: "[errno 13] RADOS permission denied (error connecting to the cluster)"
+                .to_string(),
+            exit_code: 13,
+            class: ErrorClass::PermissionDenied,
             scroll_offset: 0,
-        })
-    } else {
-        Err("Invalid command error format".into())
+        });
+        let text = render(Some(&data), None, &state);
+        assert!(text.contains("RADOS permission denied"));
+        assert_snapshot("command_error_popup", &text);
+    }
+
+    #[test]
+    fn test_redraw_does_not_add_samples() {
+        let mut data = pg_dump("pg_dump.json");
+        let mut state = updated(&data);
+        data.pg_map.pg_stats_sum.stat_sum.num_objects_misplaced -= 500;
+        state.update(&data, 5);
+
+        let first = render(Some(&data), None, &state);
+        let second = render(Some(&data), None, &state);
+        assert_eq!(first, second);
+        assert_eq!(state.get_recovery_history("Misplaced").unwrap().len(), 2);
+
+        // 500 objects in one 5s interval
+        let progress = &state.get_recovery_progress()[0];
+        assert_eq!(progress.category, "Misplaced");
+        assert_eq!(progress.object_rate, -100.0);
+        assert_eq!(progress.eta_seconds, Some(14));
+        assert!(first.contains("100.0/s"));
+    }
+
+    #[test]
+    fn test_render_paused() {
+        let mut data = pg_dump("pg_dump.json");
+        let mut state = updated(&data);
+        data.pg_map.pg_stats_sum.stat_sum.num_objects_misplaced -= 500;
+        state.update(&data, 5);
+        assert!(render(Some(&data), None, &state).contains("(5s interval)"));
+
+        state.set_paused(true);
+        assert_snapshot("paused", &render(Some(&data), None, &state));
+
+        // The sample after a pause starts the rates over
+        state.restart_rates();
+        state.update(&data, 5);
+        assert_eq!(state.get_recovery_history("Misplaced").unwrap().len(), 1);
+        assert_eq!(state.get_recovery_progress()[0].object_rate, 0.0);
+    }
+
+    #[test]
+    fn test_render_help() {
+        let data = pg_dump("pg_dump.json");
+        let mut state = updated(&data);
+        state.set_mouse(true);
+        state.toggle_help();
+        assert_snapshot("help", &render(Some(&data), None, &state));
+
+        // Hints follow the configured keys
+        state.toggle_help();
+        let overrides = toml::from_str("pause = [\"space\"]\nhelp = [\"h\"]").unwrap();
+        state.set_keymap(Keymap::new(&overrides));
+        let text = render(Some(&data), None, &state);
+        assert!(text.contains("[h] Help"));
+        assert!(text.contains("[Space] Pause"));
+    }
+
+    #[test]
+    fn test_render_notice() {
+        let data = pg_dump("pg_dump.json");
+        let mut state = updated(&data);
+        state.set_notice("Saved the screen to screen.{txt,ansi,svg}".to_string());
+        let text = render(Some(&data), None, &state);
+        let footer = text.lines().rev().nth(2).unwrap();
+        assert!(footer.contains("Controls"));
+        assert!(footer.ends_with(" Saved the screen to screen.{txt,ansi,svg} ╮"));
+
+        // The error screen shows it too
+        let text = render(None, Some("timed out"), &state);
+        assert!(text.contains("Saved the screen to screen.{txt,ansi,svg}"));
+    }
+
+    #[test]
+    fn test_mouse() {
+        use crossterm::event::{KeyModifiers, MouseButton, MouseEvent, MouseEventKind};
+        use ratatui::layout::Rect;
+
+        let data = pg_dump("pg_dump.json");
+        let mut state = updated(&data);
+        let mut terminal = Terminal::new(TestBackend::new(WIDTH, HEIGHT)).unwrap();
+        let mut mouse = |state: &mut MonitorState, kind, area: Rect| {
+            let areas = render_current_state(&mut terminal, Some(&data), None, 5, state).unwrap();
+            state.set_mouse_areas(areas);
+            let event = MouseEvent {
+                kind,
+                column: area.x + 1,
+                row: area.y,
+                modifiers: KeyModifiers::NONE,
+            };
+            mouse::handle_mouse_event(state, &event)
+        };
+        let click = MouseEventKind::Down(MouseButton::Left);
+
+        // The tab bar is the line below the header
+        let tab = |view: state::View| {
+            let x = state::View::ALL[..view.index()]
+                .iter()
+                .map(|view| view.title().len() as u16 + 5)
+                .sum();
+            Rect::new(x, 3, 1, 1)
+        };
+        mouse(&mut state, click, tab(state::View::PgStates));
+        assert_eq!(state.active_view(), state::View::PgStates);
+
+        // Below the tab bar: the border and the header line of the table
+        mouse(&mut state, click, Rect::new(10, 7, 1, 1));
+        assert_eq!(state.tab(state::View::PgStates).selected, 1);
+        mouse(&mut state, MouseEventKind::ScrollUp, Rect::new(10, 7, 1, 1));
+        assert_eq!(state.tab(state::View::PgStates).selected, 0);
+        assert!(matches!(
+            mouse(&mut state, click, Rect::new(10, 40, 1, 1)),
+            EventOutcome::Ignore
+        ));
+
+        // Clicking a header sorts by the column, clicking it again reverses
+        mouse(&mut state, click, tab(state::View::OsdOverview));
+        let pgs = Rect::new(2 + 5 + 7 + 8 + 8 + 4, 5, 1, 1);
+        mouse(&mut state, click, pgs);
+        assert_eq!(
+            state.osd_overview_sort(),
+            state::OsdOverviewSort {
+                column: state::OsdOverviewSortColumn::Pgs,
+                descending: true
+            }
+        );
+        mouse(&mut state, click, pgs);
+        assert!(!state.osd_overview_sort().descending);
+    }
+
+    #[test]
+    fn test_event_filter_keeps_keys() {
+        use crossterm::event::KeyCode;
+        let mut state = MonitorState::new();
+        state.select_view(state::View::Events);
+        press(&mut state, KeyCode::Char('/'));
+        assert!(state.event_log().editing);
+
+        // Tab does not leave the filter behind on another tab, and q is text
+        assert!(matches!(
+            press(&mut state, KeyCode::Tab),
+            EventOutcome::Ignore
+        ));
+        assert_eq!(state.active_view(), state::View::Events);
+        assert!(matches!(
+            press(&mut state, KeyCode::Char('q')),
+            EventOutcome::Redraw
+        ));
+        assert_eq!(state.event_log().filter, "q");
+
+        press(&mut state, KeyCode::Enter);
+        assert!(matches!(
+            press(&mut state, KeyCode::Char('q')),
+            EventOutcome::Quit
+        ));
+    }
+
+    #[test]
+    fn test_pg_filter_keeps_keys() {
+        use crossterm::event::KeyCode;
+        let data = pg_dump("pg_dump.json");
+        let mut state = updated(&data);
+        state.select_view(state::View::Pgs);
+        press(&mut state, KeyCode::Char('/'));
+        assert!(state.pg_browser().editing);
+
+        assert!(matches!(
+            press(&mut state, KeyCode::Tab),
+            EventOutcome::Ignore
+        ));
+        assert!(matches!(
+            press(&mut state, KeyCode::Down),
+            EventOutcome::Ignore
+        ));
+        assert_eq!(state.active_view(), state::View::Pgs);
+        assert!(matches!(
+            press(&mut state, KeyCode::Char('q')),
+            EventOutcome::Redraw
+        ));
+        assert_eq!(state.pg_browser().filter(), "q");
+
+        press(&mut state, KeyCode::Esc);
+        assert!(matches!(
+            press(&mut state, KeyCode::Char('q')),
+            EventOutcome::Quit
+        ));
     }
 }
@@ -0,0 +1,257 @@
+//! Reading monitor addresses from ceph.conf and secrets from keyrings.
+
+use std::collections::HashMap;
+
+pub const DEFAULT_CONF: &str = "/etc/ceph/ceph.conf";
+pub const MSGR2_PORT: u16 = 3300;
+const LEGACY_PORT: u16 = 6789;
+
+/// An ini-style ceph.conf or keyring
+#[derive(Debug, Default)]
+pub struct IniFile {
+    sections: HashMap<String, HashMap<String, String>>,
+}
+
+impl IniFile {
+    pub fn parse(text: &str) -> Self {
+        let mut sections: HashMap<String, HashMap<String, String>> = HashMap::new();
+        let mut current = String::from("global");
+
+        for line in text.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') || line.starts_with(';') {
+                continue;
+            }
+            if let Some(section) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+                current = section.trim().to_string();
+                continue;
+            }
+            if let Some((key, value)) = line.split_once('=') {
+                let value = value.split([';', '#']).next().unwrap_or_default().trim();
+                sections
+                    .entry(current.clone())
+                    .or_default()
+                    .insert(normalize_key(key), value.to_string());
+            }
+        }
+
+        Self { sections }
+    }
+
+    /// Look up a key in the first section that has it; `mon host`, `mon_host` and
+    /// `mon-host` are the same option
+    pub fn get(&self, sections: &[&str], key: &str) -> Option<&str> {
+        let key = normalize_key(key);
+        sections
+            .iter()
+            .filter_map(|section| self.sections.get(*section))
+            .find_map(|values| values.get(&key))
+            .map(String::as_str)
+    }
+}
+
+fn normalize_key(key: &str) -> String {
+    key.trim()
+        .split(|c: char| c.is_whitespace() || c == '_' || c == '-')
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("_")
+        .to_lowercase()
+}
+
+/// Extract msgr2 endpoints (`host:port`) from a `mon_host` value.
+///
+/// Accepts address vectors like `[v2:10.0.0.1:3300/0,v1:10.0.0.1:6789/0]` as well as
+/// plain `10.0.0.1`, `10.0.0.1:6789` or host names, which get the msgr2 default port.
+pub fn parse_mon_host(value: &str) -> Vec<String> {
+    let mut endpoints = Vec::new();
+    let mut rest = value.trim();
+
+    while !rest.is_empty() {
+        rest = rest.trim_start_matches(|c: char| c.is_whitespace() || c == ',' || c == ';');
+        if rest.is_empty() {
+            break;
+        }
+
+        let (token, remainder) = if let Some(vector) = rest.strip_prefix('[') {
+            if vector.starts_with("v1:") || vector.starts_with("v2:") {
+                match closing_bracket(vector) {
+                    Some(end) => (&vector[..end], &vector[end + 1..]),
+                    None => (vector, ""),
+                }
+            } else {
+                // A bare IPv6 address like [::1]:6789
+                let end = rest
+                    .find(|c: char| c.is_whitespace() || c == ',' || c == ';')
+                    .unwrap_or(rest.len());
+                (&rest[..end], &rest[end..])
+            }
+        } else {
+            let end = rest
+                .find(|c: char| c.is_whitespace() || c == ',' || c == ';')
+                .unwrap_or(rest.len());
+            (&rest[..end], &rest[end..])
+        };
+        rest = remainder;
+
+        if token.starts_with("v1:") || token.starts_with("v2:") {
+            // Prefer the v2 address of a vector, skip monitors that only speak v1
+            if let Some(v2) = token.split(',').find_map(|addr| addr.strip_prefix("v2:")) {
+                endpoints.push(strip_nonce(v2).to_string());
+            }
+        } else {
+            endpoints.push(with_msgr2_port(strip_nonce(token)));
+        }
+    }
+
+    endpoints
+}
+
+/// Position of the `]` closing a vector, skipping the brackets of IPv6 addresses inside it
+fn closing_bracket(vector: &str) -> Option<usize> {
+    let mut depth = 0;
+    for (i, c) in vector.char_indices() {
+        match c {
+            '[' => depth += 1,
+            ']' if depth == 0 => return Some(i),
+            ']' => depth -= 1,
+            _ => {}
+        }
+    }
+    None
+}
+
+fn strip_nonce(addr: &str) -> &str {
+    addr.split('/').next().unwrap_or(addr)
+}
+
+fn with_msgr2_port(addr: &str) -> String {
+    let (host, port) = if let Some(v6) = addr.strip_prefix('[') {
+        match v6.split_once("]:") {
+            Some((host, port)) => (format!("[{host}]"), port.parse::<u16>().ok()),
+            None => (format!("[{}]", v6.trim_end_matches(']')), None),
+        }
+    } else if addr.matches(':').count() > 1 {
+        // Unbracketed IPv6 address without a port
+        (format!("[{addr}]"), None)
+    } else {
+        match addr.split_once(':') {
+            Some((host, port)) => (host.to_string(), port.parse().ok()),
+            None => (addr.to_string(), None),
+        }
+    };
+
+    match port {
+        Some(port) if port != LEGACY_PORT => format!("{host}:{port}"),
+        _ => format!("{host}:{MSGR2_PORT}"),
+    }
+}
+
+/// Keyring locations ceph searches by default, with `$cluster` and `$name` expanded
+pub fn default_keyrings(name: &str) -> Vec<String> {
+    [
+        "/etc/ceph/ceph.$name.keyring",
+        "/etc/ceph/ceph.keyring",
+        "/etc/ceph/keyring",
+    ]
+    .iter()
+    .map(|path| expand_metavariables(path, name))
+    .collect()
+}
+
+/// Parse a `fsid = ...` value into the 16 raw bytes of the uuid
+pub fn parse_fsid(value: &str) -> Result<[u8; 16], String> {
+    let hex: String = value.trim().chars().filter(|c| *c != '-').collect();
+    if hex.len() != 32 {
+        return Err(format!("invalid fsid '{value}'"));
+    }
+    let mut fsid = [0u8; 16];
+    for (i, byte) in fsid.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&hex[i * 2..i * 2 + 2], 16)
+            .map_err(|_| format!("invalid fsid '{value}'"))?;
+    }
+    Ok(fsid)
+}
+
+pub fn expand_metavariables(path: &str, name: &str) -> String {
+    let id = name.split_once('.').map_or(name, |(_, id)| id);
+    path.replace("$cluster", "ceph")
+        .replace("$name", name)
+        .replace("$id", id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CEPH_CONF: &str = "
+# minimal ceph.conf for a cephadm cluster
+[global]
+\tfsid = 8b3c1f2e-5a47-4d8e-9c61-2f0e7b9a4d13
+\tmon_host = [v2:10.0.0.1:3300/0,v1:10.0.0.1:6789/0] [v2:10.0.0.2:3300/0,v1:10.0.0.2:6789/0]
+
+[client.admin]
+\tkeyring = /etc/ceph/$cluster.$name.keyring ; comment
+";
+
+    #[test]
+    fn test_ini_lookup() {
+        let conf = IniFile::parse(CEPH_CONF);
+        assert_eq!(
+            conf.get(&["client.admin", "global"], "mon host").unwrap(),
+            "[v2:10.0.0.1:3300/0,v1:10.0.0.1:6789/0] [v2:10.0.0.2:3300/0,v1:10.0.0.2:6789/0]"
+        );
+        assert_eq!(
+            conf.get(&["client.admin", "global"], "keyring").unwrap(),
+            "/etc/ceph/$cluster.$name.keyring"
+        );
+        assert!(conf.get(&["client"], "keyring").is_none());
+
+        let keyring = IniFile::parse(
+            "[client.admin]\n\tkey = AQDALJtoAAAAABAAfN0GZ8kUhU8Tb6zB5yF2xg==\n\tcaps mon = \"allow *\"\n",
+        );
+        assert_eq!(
+            keyring.get(&["client.admin"], "key").unwrap(),
+            "AQDALJtoAAAAABAAfN0GZ8kUhU8Tb6zB5yF2xg=="
+        );
+    }
+
+    #[test]
+    fn test_parse_mon_host() {
+        assert_eq!(
+            parse_mon_host(
+                "[v2:10.0.0.1:3300/0,v1:10.0.0.1:6789/0] [v2:10.0.0.2:3300/0,v1:10.0.0.2:6789/0]"
+            ),
+            vec!["10.0.0.1:3300", "10.0.0.2:3300"]
+        );
+        assert_eq!(
+            parse_mon_host("10.0.0.1, 10.0.0.2:6789,mon3:3301"),
+            vec!["10.0.0.1:3300", "10.0.0.2:3300", "mon3:3301"]
+        );
+        assert_eq!(
+            parse_mon_host("[v1:10.0.0.1:6789/0] [v2:[fd00::1]:3300/0]"),
+            vec!["[fd00::1]:3300"]
+        );
+        assert_eq!(parse_mon_host("[fd00::2]:6789"), vec!["[fd00::2]:3300"]);
+    }
+
+    #[test]
+    fn test_parse_fsid() {
+        let fsid = parse_fsid("8b3c1f2e-5a47-4d8e-9c61-2f0e7b9a4d13").unwrap();
+        assert_eq!(fsid[0], 0x8b);
+        assert_eq!(fsid[15], 0x13);
+        assert!(parse_fsid("8b3c1f2e").is_err());
+    }
+
+    #[test]
+    fn test_expand_metavariables() {
+        assert_eq!(
+            expand_metavariables("/etc/ceph/$cluster.$name.keyring", "client.admin"),
+            "/etc/ceph/ceph.client.admin.keyring"
+        );
+        assert_eq!(
+            default_keyrings("client.doctor")[0],
+            "/etc/ceph/ceph.client.doctor.keyring"
+        );
+    }
+}
@@ -0,0 +1,223 @@
+//! Little-endian encoding as done by Ceph's `encode()` and `decode()`.
+
+use std::net::SocketAddr;
+
+/// Address types of `entity_addr_t`
+pub const ADDR_TYPE_MSGR2: u32 = 2;
+pub const ADDR_TYPE_ANY: u32 = 3;
+
+#[derive(Debug, Default)]
+pub struct Encoder {
+    buf: Vec<u8>,
+}
+
+impl Encoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn u8(&mut self, value: u8) -> &mut Self {
+        self.buf.push(value);
+        self
+    }
+
+    pub fn u16(&mut self, value: u16) -> &mut Self {
+        self.raw(&value.to_le_bytes())
+    }
+
+    pub fn u32(&mut self, value: u32) -> &mut Self {
+        self.raw(&value.to_le_bytes())
+    }
+
+    pub fn u64(&mut self, value: u64) -> &mut Self {
+        self.raw(&value.to_le_bytes())
+    }
+
+    pub fn i16(&mut self, value: i16) -> &mut Self {
+        self.raw(&value.to_le_bytes())
+    }
+
+    pub fn i32(&mut self, value: i32) -> &mut Self {
+        self.raw(&value.to_le_bytes())
+    }
+
+    pub fn i64(&mut self, value: i64) -> &mut Self {
+        self.raw(&value.to_le_bytes())
+    }
+
+    pub fn raw(&mut self, bytes: &[u8]) -> &mut Self {
+        self.buf.extend_from_slice(bytes);
+        self
+    }
+
+    /// A `bufferlist` or `std::string`: 32-bit length followed by the bytes
+    pub fn bytes(&mut self, bytes: &[u8]) -> &mut Self {
+        self.u32(bytes.len() as u32).raw(bytes)
+    }
+
+    pub fn string(&mut self, value: &str) -> &mut Self {
+        self.bytes(value.as_bytes())
+    }
+
+    /// `std::vector<std::string>`
+    pub fn strings(&mut self, values: &[String]) -> &mut Self {
+        self.u32(values.len() as u32);
+        for value in values {
+            self.string(value);
+        }
+        self
+    }
+
+    /// `entity_addr_t` in the msgr2 capable encoding
+    pub fn entity_addr(&mut self, addr_type: u32, nonce: u32, addr: &SocketAddr) -> &mut Self {
+        let mut sockaddr = Encoder::new();
+        match addr {
+            SocketAddr::V4(v4) => {
+                sockaddr
+                    .u16(2) // AF_INET
+                    .raw(&v4.port().to_be_bytes())
+                    .raw(&v4.ip().octets())
+                    .raw(&[0; 8]);
+            }
+            SocketAddr::V6(v6) => {
+                sockaddr
+                    .u16(10) // AF_INET6
+                    .raw(&v6.port().to_be_bytes())
+                    .raw(&v6.flowinfo().to_be_bytes())
+                    .raw(&v6.ip().octets())
+                    .raw(&v6.scope_id().to_le_bytes());
+            }
+        }
+
+        let mut body = Encoder::new();
+        body.u32(addr_type).u32(nonce).bytes(sockaddr.as_slice());
+
+        // Marker for the new encoding, then ENCODE_START(1, 1)
+        self.u8(1).u8(1).u8(1).bytes(body.as_slice())
+    }
+
+    /// `entity_addrvec_t` holding a single address
+    pub fn entity_addrvec(&mut self, addr_type: u32, nonce: u32, addr: &SocketAddr) -> &mut Self {
+        self.u8(2).u32(1).entity_addr(addr_type, nonce, addr)
+    }
+
+    pub fn as_slice(&self) -> &[u8] {
+        &self.buf
+    }
+
+    pub fn into_inner(self) -> Vec<u8> {
+        self.buf
+    }
+}
+
+pub struct Decoder<'a> {
+    buf: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Decoder<'a> {
+    pub fn new(buf: &'a [u8]) -> Self {
+        Self { buf, pos: 0 }
+    }
+
+    pub fn raw(&mut self, len: usize) -> Result<&'a [u8], String> {
+        if self.remaining() < len {
+            return Err(format!(
+                "unexpected end of data: need {len} bytes at offset {}, have {}",
+                self.pos,
+                self.remaining()
+            ));
+        }
+        let bytes = &self.buf[self.pos..self.pos + len];
+        self.pos += len;
+        Ok(bytes)
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N], String> {
+        Ok(self
+            .raw(N)?
+            .try_into()
+            .expect("slice has the requested length"))
+    }
+
+    pub fn u8(&mut self) -> Result<u8, String> {
+        Ok(self.raw(1)?[0])
+    }
+
+    pub fn u16(&mut self) -> Result<u16, String> {
+        self.array().map(u16::from_le_bytes)
+    }
+
+    pub fn u32(&mut self) -> Result<u32, String> {
+        self.array().map(u32::from_le_bytes)
+    }
+
+    pub fn u64(&mut self) -> Result<u64, String> {
+        self.array().map(u64::from_le_bytes)
+    }
+
+    pub fn i32(&mut self) -> Result<i32, String> {
+        self.array().map(i32::from_le_bytes)
+    }
+
+    pub fn bytes(&mut self) -> Result<&'a [u8], String> {
+        let len = self.u32()? as usize;
+        self.raw(len)
+    }
+
+    pub fn string(&mut self) -> Result<String, String> {
+        Ok(String::from_utf8_lossy(self.bytes()?).into_owned())
+    }
+
+    pub fn strings(&mut self) -> Result<Vec<String>, String> {
+        let count = self.u32()?;
+        (0..count).map(|_| self.string()).collect()
+    }
+
+    pub fn remaining(&self) -> usize {
+        self.buf.len() - self.pos
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let mut encoder = Encoder::new();
+        encoder
+            .u8(1)
+            .u16(0x0100)
+            .u64(u64::MAX)
+            .i32(-13)
+            .string("pg dump")
+            .strings(&["a".to_string(), "bc".to_string()]);
+        let bytes = encoder.into_inner();
+
+        let mut decoder = Decoder::new(&bytes);
+        assert_eq!(decoder.u8().unwrap(), 1);
+        assert_eq!(decoder.u16().unwrap(), 0x0100);
+        assert_eq!(decoder.u64().unwrap(), u64::MAX);
+        assert_eq!(decoder.i32().unwrap(), -13);
+        assert_eq!(decoder.string().unwrap(), "pg dump");
+        assert_eq!(decoder.strings().unwrap(), vec!["a", "bc"]);
+        assert_eq!(decoder.remaining(), 0);
+        assert!(decoder.u8().is_err());
+    }
+
+    #[test]
+    fn test_entity_addr() {
+        let addr: SocketAddr = "10.0.0.1:3300".parse().unwrap();
+        let mut encoder = Encoder::new();
+        encoder.entity_addr(ADDR_TYPE_MSGR2, 0, &addr);
+        assert_eq!(
+            encoder.as_slice(),
+            [
+                1, 1, 1, 28, 0, 0, 0, // marker, version, compat, length
+                2, 0, 0, 0, 0, 0, 0, 0, // type, nonce
+                16, 0, 0, 0, 2, 0, 0x0c, 0xe4, 10, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 0
+            ]
+        );
+    }
+}
@@ -0,0 +1,588 @@
+//! Native monitor client: sends `mon_command` requests over msgr2 with cephx
+//! authentication, so no ceph CLI or librados is needed on the local host.
+//!
+//! Only what a read-only dashboard needs is implemented: msgr2.1 in crc mode
+//! (not secure mode), cephx, and one command per connection.
+
+pub mod cephx;
+pub mod config;
+pub mod encoding;
+pub mod msgr2;
+
+use crate::error::{CephError, ErrorClass};
+use crate::monitor::source::CephQuery;
+use cephx::{CryptoKey, EntityName};
+use encoding::{Decoder, Encoder, ADDR_TYPE_ANY, ADDR_TYPE_MSGR2};
+use msgr2::{Connection, Frame};
+use serde::de::DeserializeOwned;
+use std::io;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::time::Duration;
+use tokio::net::TcpStream;
+
+pub const MSG_MON_MAP: u16 = 4;
+pub const MSG_MON_GET_MAP: u16 = 5;
+pub const MSG_MON_COMMAND: u16 = 50;
+pub const MSG_MON_COMMAND_ACK: u16 = 51;
+
+/// Feature bits announced in CLIENT_IDENT, as sent by current librados clients
+const CLIENT_FEATURES: u64 = 0x3f01_cfbf_7ffd_ffff;
+const CONNECT_LOSSY: u64 = 1;
+const MSG_PRIO_DEFAULT: u16 = 127;
+/// Size of the PaxosServiceMessage header at the start of mon message payloads
+const PAXOS_HEADER_LEN: usize = 8 + 2 + 8;
+const COMMAND_TID: u64 = 1;
+
+#[derive(Debug, Clone, Default)]
+pub struct MonClientConfig {
+    /// ceph.conf to read `mon_host`, `fsid` and `keyring` from
+    pub conf: Option<PathBuf>,
+    pub keyring: Option<PathBuf>,
+    /// Client name, e.g. `client.admin`
+    pub name: String,
+    /// Overrides `mon_host` from ceph.conf
+    pub mon_host: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct MonClient {
+    mons: Vec<String>,
+    name: EntityName,
+    key: CryptoKey,
+    fsid: Option<[u8; 16]>,
+}
+
+/// Reply to a mon command
+#[derive(Debug)]
+pub struct CommandReply {
+    pub result: i32,
+    pub status: String,
+    pub data: Vec<u8>,
+}
+
+/// Why talking to one monitor failed; turned into a [`CephError`] by the caller
+#[derive(Debug)]
+struct Failure {
+    class: ErrorClass,
+    message: String,
+}
+
+impl Failure {
+    fn auth(message: String) -> Self {
+        Self {
+            class: ErrorClass::AuthFailure,
+            message,
+        }
+    }
+
+    fn protocol(message: String) -> Self {
+        Self {
+            class: ErrorClass::InvalidOutput,
+            message,
+        }
+    }
+}
+
+impl From<io::Error> for Failure {
+    fn from(error: io::Error) -> Self {
+        let class = match error.kind() {
+            io::ErrorKind::InvalidData => ErrorClass::InvalidOutput,
+            _ => ErrorClass::MonUnreachable,
+        };
+        Self {
+            class,
+            message: error.to_string(),
+        }
+    }
+}
+
+impl From<String> for Failure {
+    fn from(message: String) -> Self {
+        Self::protocol(message)
+    }
+}
+
+struct Message {
+    msg_type: u16,
+    tid: u64,
+    front: Vec<u8>,
+    data: Vec<u8>,
+}
+
+impl MonClient {
+    /// Set up a client from ceph.conf and a keyring, like the ceph CLI would
+    pub fn new(config: &MonClientConfig) -> crate::Result<Self> {
+        let name = EntityName::parse(&config.name)?;
+        let conf_path = config
+            .conf
+            .clone()
+            .unwrap_or_else(|| PathBuf::from(config::DEFAULT_CONF));
+        let conf = match std::fs::read_to_string(&conf_path) {
+            Ok(text) => config::IniFile::parse(&text),
+            // Everything can be given on the command line instead
+            Err(_) if config.conf.is_none() && config.mon_host.is_some() => Default::default(),
+            Err(e) => return Err(format!("Failed to read {}: {e}", conf_path.display()).into()),
+        };
+        let name_str = name.to_string();
+        let sections = [name_str.as_str(), "client", "global"];
+
+        let mon_host = config
+            .mon_host
+            .as_deref()
+            .or_else(|| conf.get(&sections, "mon host"))
+            .ok_or_else(|| format!("No mon_host found in {}", conf_path.display()))?;
+        let mons = config::parse_mon_host(mon_host);
+        if mons.is_empty() {
+            return Err(format!("No msgr2 monitor address in mon_host '{mon_host}'").into());
+        }
+
+        let fsid = conf
+            .get(&sections, "fsid")
+            .map(config::parse_fsid)
+            .transpose()?;
+
+        let key = match conf.get(&sections, "key") {
+            Some(key) => CryptoKey::from_base64(key)?,
+            None => {
+                let candidates: Vec<String> =
+                    match (&config.keyring, conf.get(&sections, "keyring")) {
+                        (Some(path), _) => vec![path.display().to_string()],
+                        (None, Some(paths)) => paths
+                            .split(',')
+                            .map(|path| config::expand_metavariables(path.trim(), &name_str))
+                            .collect(),
+                        (None, None) => config::default_keyrings(&name_str),
+                    };
+                let (path, text) = candidates
+                    .iter()
+                    .find_map(|path| Some((path, std::fs::read_to_string(path).ok()?)))
+                    .ok_or_else(|| {
+                        format!(
+                            "No readable keyring found (tried {})",
+                            candidates.join(", ")
+                        )
+                    })?;
+                let keyring = config::IniFile::parse(&text);
+                let key = keyring
+                    .get(&[name_str.as_str()], "key")
+                    .ok_or_else(|| format!("No key for {name_str} in {path}"))?;
+                CryptoKey::from_base64(key).map_err(|e| format!("Bad key in {path}: {e}"))?
+            }
+        };
+
+        Ok(Self::from_parts(mons, name, key, fsid))
+    }
+
+    pub fn from_parts(
+        mons: Vec<String>,
+        name: EntityName,
+        key: CryptoKey,
+        fsid: Option<[u8; 16]>,
+    ) -> Self {
+        Self {
+            mons,
+            name,
+            key,
+            fsid,
+        }
+    }
+
+    pub async fn fetch<T: DeserializeOwned>(
+        &self,
+        query: CephQuery,
+        timeout: Duration,
+    ) -> Result<T, CephError> {
+        let command = self.describe(query);
+        let reply = self
+            .command(&query.mon_command().to_string(), timeout)
+            .await?;
+
+        if reply.result < 0 {
+            return Err(CephError::CommandFailed {
+                command,
+                exit_code: reply.result,
+                stdout: String::from_utf8_lossy(&reply.data).into_owned(),
+                stderr: reply.status,
+            });
+        }
+
+        serde_json::from_slice(&reply.data).map_err(|e| {
+            CephError::json(
+                &command,
+                &e,
+                &String::from_utf8_lossy(&reply.data),
+                &reply.status,
+            )
+        })
+    }
+
+    /// Run a JSON mon command, trying the monitors in order until one answers
+    pub async fn command(
+        &self,
+        command: &str,
+        timeout: Duration,
+    ) -> Result<CommandReply, CephError> {
+        let attempt = async {
+            let mut last_error = None;
+            for mon in &self.mons {
+                match self.session(mon, command).await {
+                    Ok(reply) => return Ok(reply),
+                    Err(failure) => {
+                        let error = CephError::Mon {
+                            addr: mon.clone(),
+                            class: failure.class,
+                            message: failure.message,
+                        };
+                        // The other monitors share the auth database, no point in asking them
+                        if error.class() == ErrorClass::AuthFailure {
+                            return Err(error);
+                        }
+                        last_error = Some(error);
+                    }
+                }
+            }
+            Err(last_error.expect("at least one monitor is configured"))
+        };
+
+        tokio::time::timeout(timeout, attempt)
+            .await
+            .unwrap_or_else(|_| {
+                Err(CephError::Timeout {
+                    command: format!("mon_command {command}"),
+                    timeout,
+                })
+            })
+    }
+
+    pub fn describe(&self, query: CephQuery) -> String {
+        format!(
+            "mon_command {} via {}",
+            query.mon_command(),
+            self.mons.join(",")
+        )
+    }
+
+    async fn session(&self, mon: &str, command: &str) -> Result<CommandReply, Failure> {
+        let stream = TcpStream::connect(mon).await?;
+        let peer_addr = stream.peer_addr()?;
+        let local_addr = stream.local_addr()?;
+        let mut conn = Connection::new(stream);
+
+        let (peer_supported, peer_required) = conn
+            .exchange_banner(msgr2::FEATURE_REVISION_1, msgr2::FEATURE_REVISION_1)
+            .await?;
+        if peer_supported & msgr2::FEATURE_REVISION_1 == 0 {
+            return Err(Failure::protocol(
+                "the monitor does not support msgr2.1".into(),
+            ));
+        }
+        if peer_required & !msgr2::FEATURE_REVISION_1 != 0 {
+            return Err(Failure::protocol(format!(
+                "the monitor requires msgr2 features {peer_required:#x} (e.g. compression) that are not supported"
+            )));
+        }
+
+        conn.send(&hello_frame(&peer_addr)).await?;
+        expect(conn.recv().await?, msgr2::TAG_HELLO)?;
+
+        let (session_key, global_id) = self.authenticate(&mut conn).await?;
+
+        // Both sides sign what they received so far, proving nothing was tampered with
+        let (received, sent) = conn.take_pre_auth();
+        conn.send(&Frame::control(
+            msgr2::TAG_AUTH_SIGNATURE,
+            session_key.hmac_sha256(&received).to_vec(),
+        ))
+        .await?;
+        let signature = expect(conn.recv().await?, msgr2::TAG_AUTH_SIGNATURE)?;
+        if signature.payload() != session_key.hmac_sha256(&sent) {
+            return Err(Failure::protocol(
+                "the monitor sent an invalid auth signature".into(),
+            ));
+        }
+
+        let mut ident = Encoder::new();
+        ident
+            .entity_addrvec(ADDR_TYPE_ANY, rand::random(), &local_addr)
+            .entity_addr(ADDR_TYPE_MSGR2, 0, &peer_addr)
+            .i64(global_id as i64)
+            .u64(1)
+            .u64(CLIENT_FEATURES)
+            .u64(0)
+            .u64(CONNECT_LOSSY)
+            .u64(rand::random());
+        conn.send(&Frame::control(msgr2::TAG_CLIENT_IDENT, ident.into_inner()))
+            .await?;
+        let reply = conn.recv().await?;
+        match reply.tag {
+            msgr2::TAG_SERVER_IDENT => {}
+            msgr2::TAG_IDENT_MISSING_FEATURES => {
+                let missing = Decoder::new(reply.payload()).u64()?;
+                return Err(Failure::protocol(format!(
+                    "the monitor requires features {missing:#x} that are not supported"
+                )));
+            }
+            tag => return Err(unexpected(tag, msgr2::TAG_SERVER_IDENT)),
+        }
+
+        let mut seq = 0;
+        let fsid = match self.fsid {
+            Some(fsid) => fsid,
+            None => {
+                send_message(&mut conn, &mut seq, MSG_MON_GET_MAP, 0, Vec::new()).await?;
+                let monmap = recv_message(&mut conn, MSG_MON_MAP, 0).await?;
+                // bufferlist length, then ENCODE_START (version, compat, length) and the fsid
+                let mut decoder = Decoder::new(&monmap.front);
+                decoder.raw(4 + 6)?;
+                decoder.raw(16)?.try_into().expect("16 bytes")
+            }
+        };
+
+        let mut front = Encoder::new();
+        front
+            // PaxosServiceMessage: version, deprecated session mon and tid
+            .u64(0)
+            .i16(-1)
+            .u64(0)
+            .raw(&fsid)
+            .strings(&[command.to_string()]);
+        send_message(
+            &mut conn,
+            &mut seq,
+            MSG_MON_COMMAND,
+            COMMAND_TID,
+            front.into_inner(),
+        )
+        .await?;
+
+        let ack = recv_message(&mut conn, MSG_MON_COMMAND_ACK, COMMAND_TID).await?;
+        let mut decoder = Decoder::new(&ack.front);
+        decoder.raw(PAXOS_HEADER_LEN)?;
+        Ok(CommandReply {
+            result: decoder.i32()?,
+            status: decoder.string()?,
+            data: ack.data,
+        })
+    }
+
+    /// cephx handshake; returns the session key and our global id
+    async fn authenticate(
+        &self,
+        conn: &mut Connection<TcpStream>,
+    ) -> Result<(CryptoKey, u64), Failure> {
+        conn.send(&auth_request_frame(&self.name)).await?;
+
+        let reply = conn.recv().await?;
+        let server_challenge = match reply.tag {
+            msgr2::TAG_AUTH_REPLY_MORE => {
+                cephx::decode_server_challenge(Decoder::new(reply.payload()).bytes()?)?
+            }
+            msgr2::TAG_AUTH_BAD_METHOD => return Err(self.bad_method(&reply)),
+            tag => return Err(unexpected(tag, msgr2::TAG_AUTH_REPLY_MORE)),
+        };
+
+        let mut more = Encoder::new();
+        more.bytes(&cephx::authenticate_payload(
+            &self.key,
+            server_challenge,
+            rand::random(),
+        ));
+        conn.send(&Frame::control(
+            msgr2::TAG_AUTH_REQUEST_MORE,
+            more.into_inner(),
+        ))
+        .await?;
+
+        let done = conn.recv().await?;
+        match done.tag {
+            msgr2::TAG_AUTH_DONE => {}
+            msgr2::TAG_AUTH_BAD_METHOD => return Err(self.bad_method(&done)),
+            tag => return Err(unexpected(tag, msgr2::TAG_AUTH_DONE)),
+        }
+
+        let mut decoder = Decoder::new(done.payload());
+        let global_id = decoder.u64()?;
+        let con_mode = decoder.u32()?;
+        if con_mode != cephx::CON_MODE_CRC {
+            return Err(Failure::protocol(
+                "the monitor requires secure mode (ms_mon_client_mode), only crc mode is supported"
+                    .into(),
+            ));
+        }
+        let session_key =
+            cephx::decode_session_key(&self.key, decoder.bytes()?).map_err(Failure::auth)?;
+        Ok((session_key, global_id))
+    }
+
+    fn bad_method(&self, frame: &Frame) -> Failure {
+        let mut decoder = Decoder::new(frame.payload());
+        let result = decoder.u32().and_then(|_method| decoder.i32()).unwrap_or(0);
+        Failure::auth(format!(
+            "the monitor rejected cephx authentication for {} (error {result})",
+            self.name
+        ))
+    }
+}
+
+/// HELLO: we are a client, and the address we see the monitor at
+fn hello_frame(peer_addr: &SocketAddr) -> Frame {
+    let mut hello = Encoder::new();
+    hello
+        .u8(cephx::ENTITY_TYPE_CLIENT as u8)
+        .entity_addr(ADDR_TYPE_MSGR2, 0, peer_addr);
+    Frame::control(msgr2::TAG_HELLO, hello.into_inner())
+}
+
+/// AUTH_REQUEST: cephx, crc mode only, and who we are
+fn auth_request_frame(name: &EntityName) -> Frame {
+    let mut request = Encoder::new();
+    request
+        .u32(cephx::AUTH_CEPHX)
+        // Preferred connection modes: crc only
+        .u32(1)
+        .u32(cephx::CON_MODE_CRC)
+        .bytes(&cephx::auth_request_payload(name));
+    Frame::control(msgr2::TAG_AUTH_REQUEST, request.into_inner())
+}
+
+fn expect(frame: Frame, tag: u8) -> Result<Frame, Failure> {
+    if frame.tag == tag {
+        Ok(frame)
+    } else {
+        Err(unexpected(frame.tag, tag))
+    }
+}
+
+fn unexpected(tag: u8, expected: u8) -> Failure {
+    Failure::protocol(format!(
+        "unexpected msgr2 frame {tag} from the monitor (expected {expected})"
+    ))
+}
+
+async fn send_message(
+    conn: &mut Connection<TcpStream>,
+    seq: &mut u64,
+    msg_type: u16,
+    tid: u64,
+    front: Vec<u8>,
+) -> io::Result<()> {
+    *seq += 1;
+    conn.send(&message_frame(*seq, tid, msg_type, front)).await
+}
+
+/// Wait for a message of the given type and transaction id, skipping anything else
+/// the monitor sends in between
+async fn recv_message(
+    conn: &mut Connection<TcpStream>,
+    msg_type: u16,
+    tid: u64,
+) -> Result<Message, Failure> {
+    loop {
+        let frame = conn.recv().await?;
+        if frame.tag != msgr2::TAG_MESSAGE {
+            continue;
+        }
+        let message = decode_message(frame)?;
+        if message.msg_type == msg_type && message.tid == tid {
+            return Ok(message);
+        }
+    }
+}
+
+/// A MESSAGE frame: `ceph_msg_header2`, front, middle and data segments
+pub fn message_frame(seq: u64, tid: u64, msg_type: u16, front: Vec<u8>) -> Frame {
+    message_frame_with_data(seq, tid, msg_type, front, Vec::new())
+}
+
+pub fn message_frame_with_data(
+    seq: u64,
+    tid: u64,
+    msg_type: u16,
+    front: Vec<u8>,
+    data: Vec<u8>,
+) -> Frame {
+    let mut header = Encoder::new();
+    header
+        .u64(seq)
+        .u64(tid)
+        .u16(msg_type)
+        .u16(MSG_PRIO_DEFAULT)
+        .u16(1) // version
+        .u32(0) // data pre-padding
+        .u16(0) // data offset
+        .u64(0) // ack seq
+        .u8(0) // flags
+        .u16(0) // compat version
+        .u16(0); // reserved
+    Frame {
+        tag: msgr2::TAG_MESSAGE,
+        segments: vec![header.into_inner(), front, Vec::new(), data],
+    }
+}
+
+/// Message type, transaction id, front and data of a MESSAGE frame
+pub fn decode_message_header(frame: &Frame) -> Result<(u16, u64), String> {
+    let mut header = Decoder::new(frame.payload());
+    let _seq = header.u64()?;
+    let tid = header.u64()?;
+    let msg_type = header.u16()?;
+    Ok((msg_type, tid))
+}
+
+fn decode_message(mut frame: Frame) -> Result<Message, String> {
+    let (msg_type, tid) = decode_message_header(&frame)?;
+    frame.segments.resize(4, Vec::new());
+    let data = frame.segments.pop().unwrap_or_default();
+    Ok(Message {
+        msg_type,
+        tid,
+        front: std::mem::take(&mut frame.segments[1]),
+        data,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Computed outside this crate from doc/dev/msgr2.rst and the cephx structs,
+    // with a bitwise CRC-32C checked against ceph's own test vectors
+
+    #[test]
+    fn test_hello_frame_bytes() {
+        let peer_addr: SocketAddr = "10.0.0.1:3300".parse().unwrap();
+        assert_eq!(
+            hello_frame(&peer_addr).encode(),
+            [
+                0x01, 0x01, 0x24, 0x00, 0x00, 0x00, 0x08, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+                0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+                0x3f, 0xbd, 0x6b, 0x06, // preamble
+                0x08, // client
+                0x01, 0x01, 0x01, 0x1c, 0x00, 0x00, 0x00, 0x02, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+                0x00, 0x10, 0x00, 0x00, 0x00, 0x02, 0x00, 0x0c, 0xe4, 0x0a, 0x00, 0x00, 0x01, 0x00,
+                0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // v2:10.0.0.1:3300/0
+                0xf4, 0x04, 0x55, 0xbc, // crc
+            ]
+        );
+    }
+
+    #[test]
+    fn test_auth_request_frame_bytes() {
+        let name = EntityName::parse("client.admin").unwrap();
+        assert_eq!(
+            auth_request_frame(&name).encode(),
+            [
+                0x02, 0x01, 0x26, 0x00, 0x00, 0x00, 0x08, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+                0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+                0x8c, 0x5e, 0xe4, 0xbe, // preamble
+                0x02, 0x00, 0x00, 0x00, // cephx
+                0x01, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, // modes: crc
+                0x16, 0x00, 0x00, 0x00, 0x01, // auth payload, version
+                0x08, 0x00, 0x00, 0x00, 0x05, 0x00, 0x00, 0x00, b'a', b'd', b'm', b'i', b'n', 0x00,
+                0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // no global id yet
+                0x26, 0xa6, 0x3c, 0xc7, // crc
+            ]
+        );
+    }
+}
@@ -0,0 +1,359 @@
+//! Messenger v2.1 framing in crc mode (no on-wire encryption or compression).
+
+use std::io;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+pub const BANNER_PREFIX: &[u8] = b"ceph v2\n";
+pub const FEATURE_REVISION_1: u64 = 1;
+
+pub const TAG_HELLO: u8 = 1;
+pub const TAG_AUTH_REQUEST: u8 = 2;
+pub const TAG_AUTH_BAD_METHOD: u8 = 3;
+pub const TAG_AUTH_REPLY_MORE: u8 = 4;
+pub const TAG_AUTH_REQUEST_MORE: u8 = 5;
+pub const TAG_AUTH_DONE: u8 = 6;
+pub const TAG_AUTH_SIGNATURE: u8 = 7;
+pub const TAG_CLIENT_IDENT: u8 = 8;
+pub const TAG_SERVER_IDENT: u8 = 9;
+pub const TAG_IDENT_MISSING_FEATURES: u8 = 10;
+pub const TAG_MESSAGE: u8 = 17;
+
+const PREAMBLE_LEN: usize = 32;
+const MAX_SEGMENTS: usize = 4;
+const LATE_STATUS_COMPLETE: u8 = 0x0e;
+const LATE_STATUS_ABORTED_MASK: u8 = 0x0f;
+const EPILOGUE_LEN: usize = 1 + 4 * (MAX_SEGMENTS - 1);
+// Nothing we ask for comes close; protects against allocating garbage lengths
+const MAX_SEGMENT_LEN: usize = 1 << 30;
+
+/// Alignment hints for the segments of a message frame: header, front, middle, data
+const MESSAGE_ALIGNMENTS: [u16; MAX_SEGMENTS] = [8, 8, 8, 4096];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Frame {
+    pub tag: u8,
+    pub segments: Vec<Vec<u8>>,
+}
+
+impl Frame {
+    pub fn control(tag: u8, payload: Vec<u8>) -> Self {
+        Self {
+            tag,
+            segments: vec![payload],
+        }
+    }
+
+    /// The first segment, which holds the whole payload of control frames
+    pub fn payload(&self) -> &[u8] {
+        self.segments.first().map_or(&[], Vec::as_slice)
+    }
+
+    /// Serialize in the rev1 crc mode layout: preamble, first segment with its crc,
+    /// then the remaining segments followed by an epilogue with their crcs.
+    pub fn encode(&self) -> Vec<u8> {
+        assert!((1..=MAX_SEGMENTS).contains(&self.segments.len()));
+
+        let mut preamble = vec![self.tag, self.segments.len() as u8];
+        for (i, segment) in self.segments.iter().enumerate() {
+            preamble.extend_from_slice(&(segment.len() as u32).to_le_bytes());
+            let alignment = if self.tag == TAG_MESSAGE {
+                MESSAGE_ALIGNMENTS[i]
+            } else {
+                8
+            };
+            preamble.extend_from_slice(&alignment.to_le_bytes());
+        }
+        preamble.resize(PREAMBLE_LEN - 4, 0);
+        let crc = crc32c(0, &preamble);
+        preamble.extend_from_slice(&crc.to_le_bytes());
+
+        let mut out = preamble;
+        out.extend_from_slice(&self.segments[0]);
+        out.extend_from_slice(&crc32c(u32::MAX, &self.segments[0]).to_le_bytes());
+
+        if self.segments.len() > 1 {
+            let mut epilogue = vec![LATE_STATUS_COMPLETE];
+            for i in 1..MAX_SEGMENTS {
+                let segment = self.segments.get(i).map_or(&[][..], Vec::as_slice);
+                out.extend_from_slice(segment);
+                epilogue.extend_from_slice(&crc32c(u32::MAX, segment).to_le_bytes());
+            }
+            out.extend_from_slice(&epilogue);
+        }
+        out
+    }
+}
+
+/// Banner sent by both sides before the first frame
+pub fn banner(supported: u64, required: u64) -> Vec<u8> {
+    let mut banner = BANNER_PREFIX.to_vec();
+    banner.extend_from_slice(&16u16.to_le_bytes());
+    banner.extend_from_slice(&supported.to_le_bytes());
+    banner.extend_from_slice(&required.to_le_bytes());
+    banner
+}
+
+/// A socket that speaks msgr2 frames and records the bytes exchanged before
+/// authentication completes, which both sides sign with the session key.
+pub struct Connection<S> {
+    stream: S,
+    pre_auth: Option<(Vec<u8>, Vec<u8>)>,
+}
+
+impl<S: AsyncRead + AsyncWrite + Unpin> Connection<S> {
+    pub fn new(stream: S) -> Self {
+        Self {
+            stream,
+            pre_auth: Some((Vec::new(), Vec::new())),
+        }
+    }
+
+    pub fn get_ref(&self) -> &S {
+        &self.stream
+    }
+
+    /// Stop recording and return the received and sent pre-auth bytes
+    pub fn take_pre_auth(&mut self) -> (Vec<u8>, Vec<u8>) {
+        self.pre_auth.take().unwrap_or_default()
+    }
+
+    async fn write_all(&mut self, bytes: &[u8]) -> io::Result<()> {
+        if let Some((_, tx)) = &mut self.pre_auth {
+            tx.extend_from_slice(bytes);
+        }
+        self.stream.write_all(bytes).await
+    }
+
+    async fn read_exact(&mut self, len: usize) -> io::Result<Vec<u8>> {
+        let mut buf = vec![0; len];
+        self.stream.read_exact(&mut buf).await?;
+        if let Some((rx, _)) = &mut self.pre_auth {
+            rx.extend_from_slice(&buf);
+        }
+        Ok(buf)
+    }
+
+    /// Exchange banners and return the features (supported, required) of the peer
+    pub async fn exchange_banner(
+        &mut self,
+        supported: u64,
+        required: u64,
+    ) -> io::Result<(u64, u64)> {
+        self.write_all(&banner(supported, required)).await?;
+
+        let prefix = self.read_exact(BANNER_PREFIX.len() + 2).await?;
+        if &prefix[..BANNER_PREFIX.len()] != BANNER_PREFIX {
+            return Err(invalid_data("peer is not speaking the msgr2 protocol"));
+        }
+        let len = u16::from_le_bytes([prefix[8], prefix[9]]) as usize;
+        let payload = self.read_exact(len).await?;
+        if payload.len() < 16 {
+            return Err(invalid_data("msgr2 banner too short"));
+        }
+        let peer_supported = u64::from_le_bytes(payload[0..8].try_into().unwrap());
+        let peer_required = u64::from_le_bytes(payload[8..16].try_into().unwrap());
+        Ok((peer_supported, peer_required))
+    }
+
+    pub async fn send(&mut self, frame: &Frame) -> io::Result<()> {
+        self.write_all(&frame.encode()).await?;
+        self.stream.flush().await
+    }
+
+    pub async fn recv(&mut self) -> io::Result<Frame> {
+        let preamble = self.read_exact(PREAMBLE_LEN).await?;
+        let expected = u32::from_le_bytes(preamble[28..32].try_into().unwrap());
+        if crc32c(0, &preamble[..28]) != expected {
+            return Err(invalid_data("msgr2 preamble crc mismatch"));
+        }
+
+        let tag = preamble[0];
+        let num_segments = preamble[1] as usize;
+        if !(1..=MAX_SEGMENTS).contains(&num_segments) {
+            return Err(invalid_data("msgr2 frame with invalid segment count"));
+        }
+        let mut lengths = [0usize; MAX_SEGMENTS];
+        for (i, length) in lengths.iter_mut().enumerate().take(num_segments) {
+            let offset = 2 + i * 6;
+            *length = u32::from_le_bytes(preamble[offset..offset + 4].try_into().unwrap()) as usize;
+            if *length > MAX_SEGMENT_LEN {
+                return Err(invalid_data("msgr2 segment too large"));
+            }
+        }
+
+        let mut segments = vec![self.read_exact(lengths[0]).await?];
+        let crc = self.read_exact(4).await?;
+        if crc32c(u32::MAX, &segments[0]) != u32::from_le_bytes(crc.try_into().unwrap()) {
+            return Err(invalid_data("msgr2 segment crc mismatch"));
+        }
+
+        if num_segments > 1 {
+            for length in &lengths[1..] {
+                segments.push(self.read_exact(*length).await?);
+            }
+            let epilogue = self.read_exact(EPILOGUE_LEN).await?;
+            if epilogue[0] & LATE_STATUS_ABORTED_MASK != LATE_STATUS_COMPLETE {
+                return Err(invalid_data("msgr2 frame aborted by peer"));
+            }
+            for (i, segment) in segments.iter().enumerate().skip(1) {
+                let offset = 1 + (i - 1) * 4;
+                let expected = u32::from_le_bytes(epilogue[offset..offset + 4].try_into().unwrap());
+                if crc32c(u32::MAX, segment) != expected {
+                    return Err(invalid_data("msgr2 segment crc mismatch"));
+                }
+            }
+            segments.truncate(num_segments);
+        }
+
+        Ok(Frame { tag, segments })
+    }
+}
+
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+/// CRC-32C as computed by `ceph_crc32c`: no inversion of the seed or the result
+pub fn crc32c(seed: u32, data: &[u8]) -> u32 {
+    const POLY: u32 = 0x82f6_3b78;
+    const TABLE: [u32; 256] = {
+        let mut table = [0u32; 256];
+        let mut i = 0;
+        while i < 256 {
+            let mut crc = i as u32;
+            let mut bit = 0;
+            while bit < 8 {
+                crc = if crc & 1 != 0 {
+                    (crc >> 1) ^ POLY
+                } else {
+                    crc >> 1
+                };
+                bit += 1;
+            }
+            table[i] = crc;
+            i += 1;
+        }
+        table
+    };
+
+    data.iter().fold(seed, |crc, &byte| {
+        TABLE[((crc ^ u32::from(byte)) & 0xff) as usize] ^ (crc >> 8)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_crc32c_matches_ceph() {
+        // The standard CRC-32C check value is 0xe3069283; ceph skips the final inversion
+        assert_eq!(crc32c(u32::MAX, b"123456789"), !0xe306_9283);
+        assert_eq!(crc32c(u32::MAX, b""), u32::MAX);
+        // Crc32c.Small in ceph's src/test/common/test_crc32c.cc
+        assert_eq!(crc32c(0, b"foo bar baz"), 4119623852);
+        assert_eq!(crc32c(1234, b"foo bar baz"), 881700046);
+        assert_eq!(crc32c(0, b"whiz bang boom"), 2360230088);
+        assert_eq!(crc32c(5678, b"whiz bang boom"), 3743019208);
+    }
+
+    // The expected bytes below follow doc/dev/msgr2.rst and were computed
+    // outside this crate, with a bitwise CRC-32C checked against the vectors above
+
+    #[test]
+    fn test_banner_bytes() {
+        assert_eq!(
+            banner(FEATURE_REVISION_1, 0),
+            [
+                b'c', b'e', b'p', b'h', b' ', b'v', b'2', b'\n', // prefix
+                16, 0, // length
+                1, 0, 0, 0, 0, 0, 0, 0, // supported
+                0, 0, 0, 0, 0, 0, 0, 0, // required
+            ]
+        );
+    }
+
+    #[test]
+    fn test_control_frame_bytes() {
+        let payload = vec![0xaa; 5];
+        assert_eq!(
+            Frame::control(TAG_HELLO, payload).encode(),
+            [
+                1, 1, // tag, segments
+                5, 0, 0, 0, 8, 0, // segment length and alignment
+                0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, // unused segments
+                0, 0, // flags, reserved
+                0xe5, 0x15, 0x48, 0x8c, // preamble crc
+                0xaa, 0xaa, 0xaa, 0xaa, 0xaa, // payload
+                0x77, 0x37, 0x89, 0x5c, // payload crc
+            ]
+        );
+    }
+
+    #[test]
+    fn test_message_frame_bytes() {
+        let message = Frame {
+            tag: TAG_MESSAGE,
+            segments: vec![
+                (0..8).collect(),
+                b"front".to_vec(),
+                Vec::new(),
+                b"data".to_vec(),
+            ],
+        };
+        assert_eq!(
+            message.encode(),
+            [
+                0x11, 0x04, // tag, segments
+                0x08, 0x00, 0x00, 0x00, 0x08, 0x00, // header
+                0x05, 0x00, 0x00, 0x00, 0x08, 0x00, // front
+                0x00, 0x00, 0x00, 0x00, 0x08, 0x00, // middle
+                0x04, 0x00, 0x00, 0x00, 0x00, 0x10, // data, page aligned
+                0x00, 0x00, // flags, reserved
+                0x23, 0x82, 0xc3, 0x2d, // preamble crc
+                0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, // header
+                0xc4, 0x43, 0xd3, 0x75, // header crc
+                b'f', b'r', b'o', b'n', b't', b'd', b'a', b't', b'a', // front, middle, data
+                0x0e, // late status: complete
+                0xf4, 0xbb, 0x56, 0x84, // front crc
+                0xff, 0xff, 0xff, 0xff, // middle crc
+                0x2e, 0x82, 0x27, 0x51, // data crc
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_frames_round_trip() {
+        let (client, server) = tokio::io::duplex(4096);
+        let mut client = Connection::new(client);
+        let mut server = Connection::new(server);
+
+        let control = Frame::control(TAG_HELLO, vec![8, 1, 2, 3]);
+        let message = Frame {
+            tag: TAG_MESSAGE,
+            segments: vec![vec![0; 41], b"front".to_vec(), Vec::new(), b"data".to_vec()],
+        };
+        client.send(&control).await.unwrap();
+        client.send(&message).await.unwrap();
+
+        assert_eq!(server.recv().await.unwrap(), control);
+        assert_eq!(server.recv().await.unwrap(), message);
+
+        let (rx, _) = server.take_pre_auth();
+        let (_, tx) = client.take_pre_auth();
+        assert_eq!(rx, tx);
+    }
+
+    #[tokio::test]
+    async fn test_corrupted_frame_is_rejected() {
+        let (mut client, server) = tokio::io::duplex(4096);
+        let mut server = Connection::new(server);
+
+        let mut bytes = Frame::control(TAG_HELLO, vec![8, 1, 2, 3]).encode();
+        bytes[33] ^= 0xff;
+        client.write_all(&bytes).await.unwrap();
+
+        let error = server.recv().await.unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+    }
+}
@@ -0,0 +1,203 @@
+use crate::monitor::state::{MonitorState, View};
+use crate::monitor::EventOutcome;
+use crossterm::event::{MouseButton, MouseEvent, MouseEventKind};
+use ratatui::layout::{Constraint, Flex, Layout, Position, Rect};
+use ratatui::text::Line;
+use ratatui::widgets::TableState;
+
+/// Rows one step of the mouse wheel moves the selection by
+pub const WHEEL_ROWS: isize = 3;
+
+/// Where the last frame drew what can be clicked
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct MouseAreas {
+    /// The tabs of the tab bar
+    pub tabs: Vec<(Rect, View)>,
+    /// The table of the active tab
+    pub table: Option<TableArea>,
+}
+
+/// Where a table drew its header cells and its rows
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct TableArea {
+    /// One cell per column, in column order
+    pub headers: Vec<Rect>,
+    /// The rows below the header
+    pub rows: Rect,
+    /// Index of the first shown row
+    pub offset: usize,
+}
+
+impl TableArea {
+    /// Where a table drew into `area`, the inside of its block. Call it after
+    /// rendering with the state the table was rendered with: the table has
+    /// then scrolled it to the offset it shows. Table does not expose its
+    /// column positions, so the columns are laid out like it lays them out,
+    /// after the highlight symbol while a row is selected.
+    pub fn new(
+        area: Rect,
+        widths: &[Constraint],
+        header_height: u16,
+        highlight_symbol: &str,
+        state: &TableState,
+    ) -> TableArea {
+        let highlight_width = match state.selected() {
+            Some(_) => Line::from(highlight_symbol).width() as u16,
+            None => 0,
+        };
+        let [_, columns_area] =
+            Layout::horizontal([Constraint::Length(highlight_width), Constraint::Fill(0)])
+                .areas(area);
+        let header_height = header_height.min(area.height);
+        let headers = Layout::horizontal(widths.iter().copied())
+            .flex(Flex::Start)
+            .spacing(1)
+            .split(columns_area)
+            .iter()
+            .map(|column| Rect {
+                height: header_height,
+                ..*column
+            })
+            .collect();
+        let rows = Rect {
+            y: area.y + header_height,
+            height: area.height - header_height,
+            ..area
+        };
+        TableArea {
+            headers,
+            rows,
+            offset: state.offset(),
+        }
+    }
+
+    /// Index of the row at a position; it may be past the last row
+    pub fn row_at(&self, position: Position) -> Option<usize> {
+        self.rows
+            .contains(position)
+            .then(|| self.offset + (position.y - self.rows.y) as usize)
+    }
+
+    /// Index of the column whose header is at a position
+    pub fn header_at(&self, position: Position) -> Option<usize> {
+        self.headers
+            .iter()
+            .position(|header| header.contains(position))
+    }
+}
+
+/// Clicks select tabs and rows or sort by a column, the wheel scrolls
+pub(super) fn handle_mouse_event(state: &mut MonitorState, mouse: &MouseEvent) -> EventOutcome {
+    // Filters being typed, the help and the column chooser take only keys
+    if state.event_log().editing
+        || state.pg_browser().editing
+        || state.help()
+        || state.osd_movement_table().chooser.is_some()
+    {
+        return EventOutcome::Ignore;
+    }
+
+    let position = Position::new(mouse.column, mouse.row);
+    let wheel = match mouse.kind {
+        MouseEventKind::ScrollUp => -1,
+        MouseEventKind::ScrollDown => 1,
+        MouseEventKind::Down(MouseButton::Left) if !state.has_command_error_popup() => {
+            return click(state, position);
+        }
+        _ => return EventOutcome::Ignore,
+    };
+
+    if state.has_command_error_popup() {
+        if wheel < 0 {
+            state.scroll_popup_up();
+        } else {
+            state.scroll_popup_down();
+        }
+    } else if state.active_view() == View::Events {
+        let log = state.event_log_mut();
+        log.scroll = log
+            .scroll
+            .saturating_add_signed(wheel * WHEEL_ROWS)
+            .min(log.filtered().len().saturating_sub(1));
+    } else if state.tab_rows(state.active_view()) > 0 {
+        state.move_selection(wheel * WHEEL_ROWS);
+    } else {
+        return EventOutcome::Ignore;
+    }
+    EventOutcome::Redraw
+}
+
+fn click(state: &mut MonitorState, position: Position) -> EventOutcome {
+    let areas = state.mouse_areas().clone();
+    if let Some((_, view)) = areas.tabs.iter().find(|(area, _)| area.contains(position)) {
+        state.select_view(*view);
+        return EventOutcome::Redraw;
+    }
+    let Some(table) = &areas.table else {
+        return EventOutcome::Ignore;
+    };
+    if let Some(column) = table.header_at(position) {
+        if state.sort_by_header(column) {
+            return EventOutcome::Redraw;
+        }
+    } else if let Some(row) = table.row_at(position) {
+        if state.select_row(row) {
+            return EventOutcome::Redraw;
+        }
+    }
+    EventOutcome::Ignore
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ratatui::buffer::Buffer;
+    use ratatui::widgets::{Block, Row, StatefulWidget, Table};
+
+    /// Renders a two-column table with a block and returns the buffer and
+    /// where the table says it drew
+    fn render_table(selected: Option<usize>) -> (Buffer, TableArea) {
+        let widths = [Constraint::Length(5), Constraint::Length(8)];
+        let area = Rect::new(0, 0, 42, 12);
+        let block = Block::bordered();
+        let inner = block.inner(area);
+        let rows = (0..20).map(|row| Row::new(vec![format!("r{row}"), format!("v{row}")]));
+        let table = Table::new(rows, widths)
+            .header(Row::new(vec!["Name", "Value"]).height(2))
+            .block(block)
+            .highlight_symbol("» ");
+        let mut state = TableState::default().with_selected(selected);
+        let mut buffer = Buffer::empty(area);
+        StatefulWidget::render(table, area, &mut buffer, &mut state);
+        (buffer, TableArea::new(inner, &widths, 2, "» ", &state))
+    }
+
+    fn text_at(buffer: &Buffer, rect: Rect) -> String {
+        (rect.x..rect.right())
+            .map(|x| buffer[(x, rect.y)].symbol())
+            .collect::<String>()
+            .trim_end()
+            .to_string()
+    }
+
+    #[test]
+    fn test_table_area() {
+        let (buffer, table) = render_table(None);
+        assert_eq!(text_at(&buffer, table.headers[0]), "Name");
+        assert_eq!(text_at(&buffer, table.headers[1]), "Value");
+        assert_eq!(table.rows, Rect::new(1, 3, 40, 8));
+        assert_eq!(table.header_at(Position::new(8, 2)), Some(1));
+        assert_eq!(table.header_at(Position::new(6, 2)), None);
+        assert_eq!(table.row_at(Position::new(20, 3)), Some(0));
+        assert_eq!(table.row_at(Position::new(20, 11)), None);
+
+        // The highlight symbol moves the columns; the selected row scrolls into view
+        let (buffer, table) = render_table(Some(12));
+        assert_eq!(text_at(&buffer, table.headers[0]), "Name");
+        assert_eq!(table.offset, 5);
+        let row = table.row_at(Position::new(20, 10)).unwrap();
+        assert_eq!(row, 12);
+        let first_cell = Rect::new(table.headers[0].x, 10, 5, 1);
+        assert_eq!(text_at(&buffer, first_cell), format!("r{row}"));
+    }
+}
@@ -0,0 +1,392 @@
+use crate::common::ClusterSummary;
+use crate::monitor::alerts::AlertSettings;
+use crate::monitor::cluster::ClusterMonitor;
+use crate::monitor::data::count_inconsistent_pgs;
+use crate::monitor::export;
+use crate::monitor::keymap::{Action, Keymap};
+use crate::monitor::mouse::{TableArea, WHEEL_ROWS};
+use crate::monitor::source::DataSource;
+use crate::monitor::state::{OsdMovementSettings, OsdMovementTable};
+use crate::monitor::terminal::{RuntimeControl, TerminalManager};
+use crate::monitor::ui::*;
+use crate::monitor::EventOutcome;
+use crate::Result;
+use crossterm::event::{MouseButton, MouseEventKind};
+use ratatui::backend::Backend;
+use ratatui::Terminal;
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// A named cluster of the multi-cluster dashboard
+pub struct Cluster {
+    pub name: String,
+    pub source: DataSource,
+}
+
+pub struct MultiMonitorOptions {
+    pub interval: u64,
+    pub clusters: Vec<Cluster>,
+    pub ping_threshold_ms: f64,
+    pub timeout: u64,
+    pub theme: Theme,
+    /// SQLite database for the sample history, None to keep no history
+    pub history_db: Option<PathBuf>,
+    /// File the events are appended to
+    pub event_log: Option<PathBuf>,
+    /// Where to send the events of every cluster, None without alert sinks
+    pub alerts: Option<AlertSettings>,
+    pub osd_movement: OsdMovementSettings,
+    pub keymap: Keymap,
+    /// Capture the mouse for clicks and the wheel
+    pub mouse: bool,
+}
+
+/// What the summary shows besides the clusters
+#[derive(Default)]
+struct Summary {
+    selected: usize,
+    /// The cluster whose full monitor is shown, None for the summary
+    open: Option<usize>,
+    /// The help overlay of the summary
+    help: bool,
+    /// The mouse is captured for clicks and the wheel
+    mouse: bool,
+    /// Result of the last screen export, shown in the footer
+    notice: Option<String>,
+    /// Where the summary drew the clusters, for the mouse
+    table: TableArea,
+}
+
+/// Monitor several clusters at once: a summary with one row per cluster,
+/// and the full monitor of the selected cluster on Enter.
+pub async fn run(options: MultiMonitorOptions) -> Result<()> {
+    let interval = options.interval;
+    let mut names = Vec::new();
+    let mut clusters = Vec::new();
+    for cluster in options.clusters {
+        let mut monitor = ClusterMonitor::new(
+            cluster.source,
+            interval,
+            options.timeout,
+            options.ping_threshold_ms,
+            options.theme,
+        )
+        .with_status();
+        if let Some(path) = &options.history_db {
+            monitor = monitor.with_history(path);
+        }
+        if let Some(path) = &options.event_log {
+            monitor = monitor.with_event_log(path);
+        }
+        if let Some(alerts) = &options.alerts {
+            monitor = monitor.with_alerts(alerts)?;
+        }
+        monitor
+            .state_mut()
+            .set_osd_movement_table(OsdMovementTable::new(&options.osd_movement));
+        monitor.state_mut().set_cluster_name(cluster.name.clone());
+        monitor.state_mut().set_keymap(options.keymap.clone());
+        monitor.state_mut().set_mouse(options.mouse);
+        names.push(cluster.name);
+        clusters.push(monitor);
+    }
+
+    let keymap = options.keymap;
+    let mut terminal_manager = TerminalManager::new(options.mouse)?.with_keymap(keymap.clone());
+    let mut summary = Summary {
+        mouse: options.mouse,
+        ..Default::default()
+    };
+    summary.table = draw(
+        terminal_manager.terminal(),
+        &names,
+        &mut clusters,
+        &summary,
+        &keymap,
+    )?;
+
+    loop {
+        // All clusters keep fetching; only changes to what is on screen need a redraw
+        let mut redraw = false;
+        for (index, cluster) in clusters.iter_mut().enumerate() {
+            if cluster.poll() && summary.open.is_none_or(|open| open == index) {
+                redraw = true;
+            }
+        }
+
+        if terminal_manager.poll_event(Duration::from_millis(100))? {
+            let event = terminal_manager.read_event()?;
+            let outcome = match summary.open {
+                Some(index) => {
+                    let state = clusters[index].state_mut();
+                    // Filters being typed, the help and the column chooser handle Esc themselves
+                    let modal = state.event_log().editing
+                        || state.pg_browser().editing
+                        || state.help()
+                        || state.osd_movement_table().chooser.is_some();
+                    if terminal_manager.is(Action::Back, &event) && !modal {
+                        // Esc closes the popup first, then goes back to the summary
+                        if state.has_command_error_popup() {
+                            state.clear_command_error_popup();
+                        } else {
+                            summary.open = None;
+                        }
+                        EventOutcome::Redraw
+                    } else {
+                        super::handle_event(terminal_manager.keymap(), state, &event)
+                    }
+                }
+                None => {
+                    handle_summary_event(&terminal_manager, &event, &mut summary, clusters.len())
+                }
+            };
+            match outcome {
+                EventOutcome::Quit => break,
+                EventOutcome::Redraw => redraw = true,
+                EventOutcome::Control(control) => {
+                    match summary.open {
+                        Some(index) => clusters[index].control(control),
+                        None => control_all(&mut clusters, control),
+                    }
+                    redraw = true;
+                }
+                EventOutcome::Export => {
+                    let notice = export::export_screen(terminal_manager.terminal(), |t| {
+                        draw(t, &names, &mut clusters, &summary, &keymap).map(|_| ())
+                    });
+                    match summary.open {
+                        Some(index) => clusters[index].state_mut().set_notice(notice),
+                        None => summary.notice = Some(notice),
+                    }
+                    redraw = true;
+                }
+                EventOutcome::Ignore => {}
+            }
+        }
+
+        if redraw {
+            summary.table = draw(
+                terminal_manager.terminal(),
+                &names,
+                &mut clusters,
+                &summary,
+                &keymap,
+            )?;
+        }
+    }
+
+    for cluster in &mut clusters {
+        cluster.cancel();
+    }
+    terminal_manager.cleanup()?;
+    Ok(())
+}
+
+fn handle_summary_event(
+    terminal_manager: &TerminalManager,
+    event: &crossterm::event::Event,
+    summary: &mut Summary,
+    cluster_count: usize,
+) -> EventOutcome {
+    let Summary {
+        selected,
+        open,
+        help,
+        table,
+        ..
+    } = summary;
+    if *help {
+        // Only closing the help or quitting
+        if terminal_manager.is(Action::Help, event)
+            || terminal_manager.is(Action::ClosePopup, event)
+        {
+            *help = false;
+            return EventOutcome::Redraw;
+        }
+        if !terminal_manager.is(Action::Quit, event) {
+            return EventOutcome::Ignore;
+        }
+    }
+
+    if let crossterm::event::Event::Mouse(mouse) = event {
+        if *help {
+            return EventOutcome::Ignore;
+        }
+        let position = ratatui::layout::Position::new(mouse.column, mouse.row);
+        match mouse.kind {
+            MouseEventKind::ScrollUp => {
+                *selected = selected.saturating_sub(WHEEL_ROWS as usize);
+            }
+            MouseEventKind::ScrollDown => {
+                *selected = (*selected + WHEEL_ROWS as usize).min(cluster_count.saturating_sub(1));
+            }
+            // The first click selects a cluster, a click on the selected one shows it
+            MouseEventKind::Down(MouseButton::Left) => match table.row_at(position) {
+                Some(row) if row == *selected => *open = Some(row),
+                Some(row) if row < cluster_count => *selected = row,
+                _ => return EventOutcome::Ignore,
+            },
+            _ => return EventOutcome::Ignore,
+        }
+        return EventOutcome::Redraw;
+    }
+
+    if terminal_manager.is(Action::Quit, event) {
+        EventOutcome::Quit
+    } else if matches!(event, crossterm::event::Event::Resize(_, _)) {
+        EventOutcome::Redraw
+    } else if terminal_manager.is(Action::Up, event) {
+        *selected = selected.saturating_sub(1);
+        EventOutcome::Redraw
+    } else if terminal_manager.is(Action::Down, event) {
+        *selected = (*selected + 1).min(cluster_count.saturating_sub(1));
+        EventOutcome::Redraw
+    } else if terminal_manager.is(Action::Select, event) {
+        *open = Some(*selected);
+        EventOutcome::Redraw
+    } else if terminal_manager.is(Action::Help, event) {
+        *help = true;
+        EventOutcome::Redraw
+    } else if terminal_manager.is(Action::Export, event) {
+        EventOutcome::Export
+    } else if let Some(control) = terminal_manager.keymap().runtime_control(event) {
+        EventOutcome::Control(control)
+    } else {
+        EventOutcome::Ignore
+    }
+}
+
+/// The summary controls every cluster; pausing pauses all unless all are paused already
+fn control_all(clusters: &mut [ClusterMonitor], control: RuntimeControl) {
+    let pause = !clusters.iter().all(|cluster| cluster.state().paused());
+    for cluster in clusters {
+        if control != RuntimeControl::TogglePause || cluster.state().paused() != pause {
+            cluster.control(control);
+        }
+    }
+}
+
+/// Draw the open cluster or the summary; returns where the summary drew the
+/// clusters
+fn draw<B: Backend>(
+    terminal: &mut Terminal<B>,
+    names: &[String],
+    clusters: &mut [ClusterMonitor],
+    summary: &Summary,
+    keymap: &Keymap,
+) -> Result<TableArea> {
+    if let Some(index) = summary.open {
+        clusters[index].draw(terminal)?;
+        return Ok(TableArea::default());
+    }
+
+    let interval = clusters.first().map_or(0, ClusterMonitor::interval);
+    let paused = clusters.iter().all(|cluster| cluster.state().paused());
+    let theme = clusters
+        .first()
+        .map_or_else(Theme::default, |cluster| *cluster.state().theme());
+    let summaries: Vec<ClusterSummary> = names
+        .iter()
+        .zip(clusters.iter())
+        .map(|(name, cluster)| summarize_cluster(name, cluster))
+        .collect();
+
+    let mut table = TableArea::default();
+    terminal.draw(|f| {
+        table = render_summary_ui(f, &summaries, summary, interval, paused, keymap, &theme);
+        if summary.help {
+            render_help(f, keymap, summary.mouse);
+        }
+    })?;
+    Ok(table)
+}
+
+fn summarize_cluster(name: &str, cluster: &ClusterMonitor) -> ClusterSummary {
+    let mut summary = ClusterSummary {
+        name: name.to_string(),
+        health: cluster
+            .last_status()
+            .map(|status| status.health.status.clone()),
+        eta_seconds: cluster.recovery_eta(),
+        fetch_elapsed: cluster.fetch_elapsed(),
+        ..Default::default()
+    };
+
+    if let Some(data) = cluster.last_data() {
+        let stats_sum = &data.pg_map.pg_stats_sum.stat_sum;
+        summary.has_data = true;
+        summary.degraded_objects = stats_sum.num_objects_degraded;
+        summary.misplaced_objects = stats_sum.num_objects_misplaced;
+        summary.inconsistent_pgs = count_inconsistent_pgs(data);
+    }
+
+    if let Some(error) = cluster.error_message() {
+        summary.error = Some(error.clone());
+    } else if let Some(cmd_error) = cluster.state().get_command_error_popup() {
+        summary.error = Some(format!("{} (Enter for details)", cmd_error.class.label()));
+    }
+
+    summary
+}
+
+fn render_summary_ui(
+    f: &mut ratatui::Frame,
+    summaries: &[ClusterSummary],
+    summary: &Summary,
+    interval: u64,
+    paused: bool,
+    keymap: &Keymap,
+    theme: &Theme,
+) -> TableArea {
+    use ratatui::prelude::*;
+    use ratatui::widgets::*;
+
+    let main_layout = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(3), // Header
+            Constraint::Min(0),    // Clusters
+            Constraint::Length(3), // Footer
+        ])
+        .split(f.area());
+
+    let header_block = Block::default()
+        .borders(Borders::ALL)
+        .border_type(BorderType::Rounded)
+        .title(if paused {
+            format!("CEPH DOCTOR - Cluster Overview (PAUSED, {interval}s interval)")
+        } else {
+            format!("CEPH DOCTOR - Cluster Overview ({interval}s interval)")
+        })
+        .title_style(Style::default().add_modifier(Modifier::BOLD));
+    let header = Paragraph::new(format!("Monitoring {} clusters", summaries.len()))
+        .block(header_block)
+        .style(Style::default());
+    f.render_widget(header, main_layout[0]);
+
+    let table = render_cluster_summary(f, main_layout[1], summaries, summary.selected, theme);
+
+    let footer = Paragraph::new(
+        [
+            keymap.hint(&[Action::Quit], "Quit"),
+            keymap.hint(&[Action::Help], "Help"),
+            keymap.hint(&[Action::Up, Action::Down], "Select cluster"),
+            keymap.hint(&[Action::Select], "Show cluster"),
+            keymap.hint(
+                &[Action::Pause],
+                if paused { "Resume all" } else { "Pause all" },
+            ),
+            keymap.hint(&[Action::Refresh], "Refresh all"),
+            keymap.hint(
+                &[Action::SlowerInterval, Action::FasterInterval],
+                "Interval",
+            ),
+        ]
+        .join(" • "),
+    )
+    .block(footer_block(summary.notice.as_deref()))
+    .style(Style::default())
+    .wrap(Wrap { trim: true });
+    f.render_widget(footer, main_layout[2]);
+    table
+}
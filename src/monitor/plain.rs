@@ -0,0 +1,215 @@
+use crate::common::{CephPgDump, CephStatus, RecoveryProgress};
+use crate::monitor::cluster::ClusterMonitor;
+use crate::monitor::data::count_inconsistent_pgs;
+use crate::monitor::data::formatter::*;
+use crate::monitor::state::{CommandError, OsdMovementTable};
+use crate::monitor::MonitorOptions;
+use crate::Result;
+use std::io::Write;
+use std::time::Duration;
+
+/// Monitor without the terminal UI: one status line per sample on standard
+/// output, for pipes, log files and services. Stops after `count` lines, or
+/// when standard output is closed.
+pub async fn run(options: MonitorOptions, count: Option<u64>) -> Result<()> {
+    let mut cluster = ClusterMonitor::new(
+        options.source,
+        options.interval,
+        options.timeout,
+        options.ping_threshold_ms,
+        options.theme,
+    )
+    .with_status();
+    if let Some(path) = &options.history_db {
+        cluster = cluster.with_history(path);
+    }
+    if let Some(path) = &options.event_log {
+        cluster = cluster.with_event_log(path);
+    }
+    if let Some(alerts) = &options.alerts {
+        cluster = cluster.with_alerts(alerts)?;
+    }
+    cluster
+        .state_mut()
+        .set_osd_movement_table(OsdMovementTable::new(&options.osd_movement));
+
+    let mut stdout = std::io::stdout();
+    let mut fetches = cluster.fetches();
+    let mut lines = 0;
+    while count.is_none_or(|count| lines < count) {
+        cluster.poll();
+        if cluster.fetches() == fetches {
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            continue;
+        }
+        fetches = cluster.fetches();
+
+        let now = chrono::Utc::now();
+        let line = match (
+            cluster.error_message(),
+            cluster.state().get_command_error_popup(),
+        ) {
+            (Some(error), _) => error_line(now, error),
+            (None, Some(cmd_error)) => error_line(now, &command_error(cmd_error)),
+            (None, None) => match cluster.last_data() {
+                Some(data) => status_line(
+                    now,
+                    cluster.last_status(),
+                    data,
+                    cluster.state().get_recovery_progress(),
+                    cluster.recovery_eta(),
+                ),
+                None => continue,
+            },
+        };
+        // Nobody can close the popup here, and it would hold back the next fetch
+        cluster.state_mut().clear_command_error_popup();
+
+        let written = writeln!(stdout, "{line}").and_then(|_| stdout.flush());
+        match written {
+            Ok(()) => lines += 1,
+            // The reader went away, e.g. `| head`
+            Err(e) if e.kind() == std::io::ErrorKind::BrokenPipe => break,
+            Err(e) => return Err(e.into()),
+        }
+    }
+
+    cluster.cancel();
+    Ok(())
+}
+
+fn timestamp(now: chrono::DateTime<chrono::Utc>) -> String {
+    now.format("%Y-%m-%d %H:%M:%S UTC").to_string()
+}
+
+/// Time, health, object counts, recovery rate, ETA and inconsistent PGs as
+/// `key=value` pairs after the health, so the line is easy to grep and split
+fn status_line(
+    now: chrono::DateTime<chrono::Utc>,
+    status: Option<&CephStatus>,
+    data: &CephPgDump,
+    progress: &[RecoveryProgress],
+    eta: Option<u64>,
+) -> String {
+    let stats_sum = &data.pg_map.pg_stats_sum.stat_sum;
+    let health = status.map_or("HEALTH_UNKNOWN", |status| status.health.status.as_str());
+
+    // Degraded and misplaced objects make up the recovery, as for the ETA;
+    // their rates are negative while they go down
+    let recovering = progress
+        .iter()
+        .filter(|row| matches!(row.category, "Degraded" | "Misplaced"));
+    let (object_rate, data_rate) = recovering.fold((0.0, 0.0), |(objects, bytes), row| {
+        (
+            objects + (-row.object_rate).max(0.0),
+            bytes + (-row.data_rate).max(0.0),
+        )
+    });
+
+    let remaining = stats_sum.num_objects_degraded + stats_sum.num_objects_misplaced;
+    let eta = match eta {
+        Some(seconds) => format_time(seconds),
+        None if remaining > 0 => "calculating".to_string(),
+        None => "-".to_string(),
+    };
+
+    format!(
+        "{} {health} degraded={} misplaced={} unfound={} rate={object_rate:.1}obj/s,{} eta={eta} inconsistent={}",
+        timestamp(now),
+        stats_sum.num_objects_degraded,
+        stats_sum.num_objects_misplaced,
+        stats_sum.num_objects_unfound,
+        format_bytes_per_second(data_rate),
+        count_inconsistent_pgs(data),
+    )
+}
+
+fn error_line(now: chrono::DateTime<chrono::Utc>, error: &str) -> String {
+    // Keep it to one line whatever the command printed
+    let error = error.split_whitespace().collect::<Vec<_>>().join(" ");
+    format!("{} ERROR {error}", timestamp(now))
+}
+
+/// What the command error popup would show, briefly
+fn command_error(cmd_error: &CommandError) -> String {
+    let output = if cmd_error.stderr.trim().is_empty() {
+        &cmd_error.stdout
+    } else {
+        &cmd_error.stderr
+    };
+    let first_line = output.lines().find(|line| !line.trim().is_empty());
+    format!(
+        "{}: {} exited with code {}{}",
+        cmd_error.class.label(),
+        cmd_error.command,
+        cmd_error.exit_code,
+        first_line.map_or(String::new(), |line| format!(": {line}"))
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorClass;
+
+    fn pg_dump(name: &str) -> CephPgDump {
+        let path = format!("{}/tests/fixtures/{name}", env!("CARGO_MANIFEST_DIR"));
+        serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_status_line() {
+        let now = chrono::DateTime::from_timestamp(1_760_000_000, 0).unwrap();
+        let data = pg_dump("pg_dump.json");
+        let stats_sum = &data.pg_map.pg_stats_sum.stat_sum;
+        let progress = [
+            RecoveryProgress {
+                category: "Degraded",
+                objects: 100,
+                object_rate: -12.5,
+                data_rate: -4.0 * 1024.0 * 1024.0,
+                eta_seconds: Some(8),
+            },
+            RecoveryProgress {
+                category: "Misplaced",
+                objects: 100,
+                object_rate: -7.5,
+                data_rate: -1024.0 * 1024.0,
+                eta_seconds: Some(13),
+            },
+        ];
+
+        let line = status_line(now, None, &data, &progress, Some(3725));
+        assert_eq!(
+            line,
+            format!(
+                "2025-10-09 08:53:20 UTC HEALTH_UNKNOWN degraded={} misplaced={} unfound={} rate=20.0obj/s,5.0MB/s eta=1h02m05s inconsistent={}",
+                stats_sum.num_objects_degraded,
+                stats_sum.num_objects_misplaced,
+                stats_sum.num_objects_unfound,
+                count_inconsistent_pgs(&data),
+            )
+        );
+        assert!(!line.contains('\n'));
+    }
+
+    #[test]
+    fn test_error_line() {
+        let now = chrono::DateTime::from_timestamp(1_760_000_000, 0).unwrap();
+        let cmd_error = CommandError {
+            command: "ceph pg dump --format json".to_string(),
+            stdout: String::new(),
+            stderr: "\nError EACCES: access denied\nmore\n".to_string(),
+            exit_code: 13,
+            class: ErrorClass::PermissionDenied,
+            scroll_offset: 0,
+        };
+        assert_eq!(
+            error_line(now, &command_error(&cmd_error)),
+            format!(
+                "2025-10-09 08:53:20 UTC ERROR {}: ceph pg dump --format json exited with code 13: Error EACCES: access denied",
+                ErrorClass::PermissionDenied.label()
+            )
+        );
+    }
+}
@@ -0,0 +1,256 @@
+/// Marks where the ceph command is inserted into the prefix command.
+pub const PLACEHOLDER: &str = "{}";
+
+/// Command used to run `ceph` somewhere else, e.g. `ssh host sudo` or `cephadm shell -- {}`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PrefixCommand {
+    args: Vec<String>,
+}
+
+impl PrefixCommand {
+    /// Split a prefix command string using POSIX shell quoting rules
+    pub fn parse(command: &str) -> Result<Self, String> {
+        Ok(Self {
+            args: split_shell_words(command)?,
+        })
+    }
+
+    pub fn from_args(args: Vec<String>) -> Self {
+        Self { args }
+    }
+
+    /// Build the full argument vector that runs `ceph <ceph_args>` through the prefix.
+    ///
+    /// An argument that is exactly `{}` is replaced by the ceph command as separate
+    /// arguments, unless it follows `-c` or an option cluster ending in `c` (as in
+    /// `sh -c '{}'` or `bash -lc '{}'`), where a shell expects the command as one string. A `{}` inside a longer argument is replaced by the
+    /// shell-quoted command line. Without a placeholder the command is appended.
+    pub fn build(&self, ceph_args: &[&str]) -> Vec<String> {
+        let mut ceph_command = vec!["ceph".to_string()];
+        ceph_command.extend(ceph_args.iter().map(|arg| arg.to_string()));
+
+        if !self.args.iter().any(|arg| arg.contains(PLACEHOLDER)) {
+            let mut args = self.args.clone();
+            args.extend(ceph_command);
+            return args;
+        }
+
+        let command_line = join_shell_words(&ceph_command);
+        let mut args = Vec::new();
+        for (i, arg) in self.args.iter().enumerate() {
+            let after_dash_c = i > 0 && is_command_option(&self.args[i - 1]);
+            if arg == PLACEHOLDER && !after_dash_c {
+                args.extend(ceph_command.iter().cloned());
+            } else {
+                args.push(arg.replace(PLACEHOLDER, &command_line));
+            }
+        }
+        args
+    }
+
+    /// Human readable form of the command that runs `ceph <ceph_args>`
+    pub fn display(&self, ceph_args: &[&str]) -> String {
+        join_shell_words(&self.build(ceph_args))
+    }
+}
+
+/// Whether a shell takes its command string after this argument: `-c`, or a cluster
+/// of short options ending in it like `-lc` or `-ec`
+fn is_command_option(arg: &str) -> bool {
+    arg.strip_prefix('-').is_some_and(|options| {
+        options.ends_with('c') && options.chars().all(|c| c.is_ascii_alphabetic())
+    })
+}
+
+/// Split a string into words like a POSIX shell would, without expansions.
+pub fn split_shell_words(input: &str) -> Result<Vec<String>, String> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let mut in_word = false;
+    let mut chars = input.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            c if c.is_whitespace() => {
+                if in_word {
+                    words.push(std::mem::take(&mut word));
+                    in_word = false;
+                }
+            }
+            '\'' => {
+                in_word = true;
+                loop {
+                    match chars.next() {
+                        Some('\'') => break,
+                        Some(c) => word.push(c),
+                        None => return Err("unterminated single quote".to_string()),
+                    }
+                }
+            }
+            '"' => {
+                in_word = true;
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        // Inside double quotes a backslash only escapes these characters
+                        Some('\\') => match chars.next() {
+                            Some(c @ ('"' | '\\' | '$' | '`')) => word.push(c),
+                            Some('\n') => {}
+                            Some(c) => {
+                                word.push('\\');
+                                word.push(c);
+                            }
+                            None => return Err("unterminated double quote".to_string()),
+                        },
+                        Some(c) => word.push(c),
+                        None => return Err("unterminated double quote".to_string()),
+                    }
+                }
+            }
+            '\\' => match chars.next() {
+                Some('\n') => {}
+                Some(c) => {
+                    in_word = true;
+                    word.push(c);
+                }
+                None => return Err("trailing backslash".to_string()),
+            },
+            c => {
+                in_word = true;
+                word.push(c);
+            }
+        }
+    }
+
+    if in_word {
+        words.push(word);
+    }
+
+    Ok(words)
+}
+
+/// Join words into a command line that a POSIX shell splits back into the same words
+pub fn join_shell_words(words: &[String]) -> String {
+    words
+        .iter()
+        .map(|word| quote_shell_word(word))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn quote_shell_word(word: &str) -> String {
+    let is_safe = |c: char| c.is_ascii_alphanumeric() || "-_./:=@%+,{}".contains(c);
+    if !word.is_empty() && word.chars().all(is_safe) {
+        word.to_string()
+    } else {
+        format!("'{}'", word.replace('\'', r"'\''"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PG_DUMP: [&str; 4] = ["pg", "dump", "--format", "json"];
+
+    #[test]
+    fn test_split_shell_words() {
+        assert_eq!(
+            split_shell_words("ssh -o 'ProxyCommand=ssh -W %h:%p bastion' host sudo").unwrap(),
+            vec![
+                "ssh",
+                "-o",
+                "ProxyCommand=ssh -W %h:%p bastion",
+                "host",
+                "sudo"
+            ]
+        );
+        assert_eq!(
+            split_shell_words(r#"docker exec "my container" a\ b "x\"y" ''"#).unwrap(),
+            vec!["docker", "exec", "my container", "a b", "x\"y", ""]
+        );
+        assert!(split_shell_words("ssh 'host").is_err());
+        assert!(split_shell_words("ssh \"host").is_err());
+    }
+
+    #[test]
+    fn test_build_appends_without_placeholder() {
+        let prefix = PrefixCommand::parse("ssh host sudo").unwrap();
+        assert_eq!(
+            prefix.build(&PG_DUMP),
+            vec!["ssh", "host", "sudo", "ceph", "pg", "dump", "--format", "json"]
+        );
+        assert_eq!(
+            PrefixCommand::default().build(&PG_DUMP),
+            vec!["ceph", "pg", "dump", "--format", "json"]
+        );
+    }
+
+    #[test]
+    fn test_build_with_placeholder() {
+        let prefix = PrefixCommand::parse("cephadm shell -- {} --connect-timeout 10").unwrap();
+        assert_eq!(
+            prefix.build(&PG_DUMP),
+            vec![
+                "cephadm",
+                "shell",
+                "--",
+                "ceph",
+                "pg",
+                "dump",
+                "--format",
+                "json",
+                "--connect-timeout",
+                "10"
+            ]
+        );
+
+        let prefix = PrefixCommand::parse("kubectl exec pod -- sh -c '{}'").unwrap();
+        assert_eq!(
+            prefix.build(&PG_DUMP),
+            vec![
+                "kubectl",
+                "exec",
+                "pod",
+                "--",
+                "sh",
+                "-c",
+                "ceph pg dump --format json"
+            ]
+        );
+
+        let prefix = PrefixCommand::parse("sh -c 'exec {} 2>/dev/null'").unwrap();
+        assert_eq!(
+            prefix.build(&PG_DUMP),
+            vec!["sh", "-c", "exec ceph pg dump --format json 2>/dev/null"]
+        );
+    }
+
+    #[test]
+    fn test_build_with_option_cluster() {
+        for option in ["-lc", "-ec", "-euc"] {
+            let prefix = PrefixCommand::from_args(vec!["bash".into(), option.into(), "{}".into()]);
+            assert_eq!(
+                prefix.build(&PG_DUMP),
+                vec!["bash", option, "ceph pg dump --format json"]
+            );
+        }
+
+        // Long options and option values are not a shell command slot
+        for option in ["--spec", "-"] {
+            let prefix = PrefixCommand::from_args(vec!["tool".into(), option.into(), "{}".into()]);
+            assert_eq!(
+                prefix.build(&PG_DUMP),
+                vec!["tool", option, "ceph", "pg", "dump", "--format", "json"]
+            );
+        }
+    }
+
+    #[test]
+    fn test_display_round_trips() {
+        let prefix = PrefixCommand::from_args(vec!["ssh".into(), "-o".into(), "A=b c".into()]);
+        let display = prefix.display(&PG_DUMP);
+        assert_eq!(display, "ssh -o 'A=b c' ceph pg dump --format json");
+        assert_eq!(split_shell_words(&display).unwrap(), prefix.build(&PG_DUMP));
+    }
+}
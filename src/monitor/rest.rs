@@ -0,0 +1,158 @@
+use crate::error::CephError;
+use crate::monitor::source::CephQuery;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// Connection settings for the ceph-mgr `restful` module.
+#[derive(Debug, Clone, Default)]
+pub struct RestConfig {
+    /// Base URL, e.g. `https://mgr-host:8003`
+    pub url: String,
+    /// User name and API key as created by `ceph restful create-key <user>`
+    pub user: Option<String>,
+    pub key: Option<String>,
+    /// Bearer token, for gateways in front of the mgr that use token auth
+    pub token: Option<String>,
+    /// Accept self-signed or otherwise invalid certificates
+    pub insecure: bool,
+    /// Extra CA certificate (PEM) to trust
+    pub ca_cert: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone)]
+enum RestAuth {
+    None,
+    Basic { user: String, key: String },
+    Bearer(String),
+}
+
+/// HTTP client that runs mon/mgr commands through the `restful` module's
+/// `POST /request?wait=1` endpoint.
+#[derive(Debug, Clone)]
+pub struct RestClient {
+    base_url: String,
+    auth: RestAuth,
+    client: reqwest::Client,
+}
+
+/// Response of `POST /request?wait=1`
+#[derive(Debug, Deserialize)]
+struct RestRequest {
+    #[serde(default)]
+    finished: Vec<RestCommandResult>,
+    #[serde(default)]
+    failed: Vec<RestCommandResult>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RestCommandResult {
+    #[serde(default)]
+    command: String,
+    #[serde(default)]
+    outb: String,
+    #[serde(default)]
+    outs: String,
+}
+
+impl RestClient {
+    pub fn new(config: &RestConfig) -> crate::Result<Self> {
+        let mut builder = reqwest::Client::builder().danger_accept_invalid_certs(config.insecure);
+        if let Some(path) = &config.ca_cert {
+            let pem = std::fs::read(path)
+                .map_err(|e| format!("Failed to read CA certificate {}: {e}", path.display()))?;
+            builder = builder.add_root_certificate(reqwest::Certificate::from_pem(&pem)?);
+        }
+
+        let auth = match (&config.token, &config.user, &config.key) {
+            (Some(token), _, _) => RestAuth::Bearer(token.clone()),
+            (None, Some(user), Some(key)) => RestAuth::Basic {
+                user: user.clone(),
+                key: key.clone(),
+            },
+            (None, None, None) => RestAuth::None,
+            _ => return Err("The REST API needs both a user and an API key".into()),
+        };
+
+        Ok(Self {
+            base_url: config.url.trim_end_matches('/').to_string(),
+            auth,
+            client: builder.build()?,
+        })
+    }
+
+    pub async fn fetch<T: DeserializeOwned>(
+        &self,
+        query: CephQuery,
+        timeout: Duration,
+    ) -> Result<T, CephError> {
+        let url = format!("{}/request?wait=1", self.base_url);
+        let command = self.describe(query);
+
+        let mut request = self
+            .client
+            .post(&url)
+            .timeout(timeout)
+            .json(&query.mon_command());
+        request = match &self.auth {
+            RestAuth::None => request,
+            RestAuth::Basic { user, key } => request.basic_auth(user, Some(key)),
+            RestAuth::Bearer(token) => request.bearer_auth(token),
+        };
+
+        let http_error = |e: reqwest::Error| {
+            if e.is_timeout() {
+                CephError::Timeout {
+                    command: command.clone(),
+                    timeout,
+                }
+            } else {
+                CephError::Http {
+                    url: url.clone(),
+                    status: e.status().map(|s| s.as_u16()),
+                    message: e.to_string(),
+                }
+            }
+        };
+
+        let response = request.send().await.map_err(http_error)?;
+        let status = response.status();
+        let body = response.text().await.map_err(http_error)?;
+
+        if !status.is_success() {
+            return Err(CephError::Http {
+                url,
+                status: Some(status.as_u16()),
+                message: body.trim().to_string(),
+            });
+        }
+
+        let result: RestRequest =
+            serde_json::from_str(&body).map_err(|e| CephError::json(&command, &e, &body, ""))?;
+
+        if let Some(failed) = result.failed.into_iter().next() {
+            return Err(CephError::CommandFailed {
+                command: format!("{command} ({})", failed.command),
+                exit_code: -1,
+                stdout: failed.outb,
+                stderr: failed.outs,
+            });
+        }
+
+        let Some(finished) = result.finished.into_iter().next() else {
+            return Err(CephError::Http {
+                url,
+                status: Some(status.as_u16()),
+                message: "The request finished without a result".to_string(),
+            });
+        };
+
+        serde_json::from_str(&finished.outb)
+            .map_err(|e| CephError::json(&command, &e, &finished.outb, &finished.outs))
+    }
+
+    pub fn describe(&self, query: CephQuery) -> String {
+        format!("POST {}/request {}", self.base_url, query.mon_command())
+    }
+}
@@ -0,0 +1,125 @@
+use crate::error::CephError;
+use crate::monitor::mon::MonClient;
+use crate::monitor::prefix::PrefixCommand;
+use crate::monitor::rest::RestClient;
+use serde::de::DeserializeOwned;
+use std::time::Duration;
+use tokio::process::Command;
+
+/// The cluster queries ceph-doctor knows how to run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CephQuery {
+    PgDump,
+    OsdTree,
+    Status,
+}
+
+impl CephQuery {
+    /// Arguments for the `ceph` command line tool
+    pub fn cli_args(self) -> &'static [&'static str] {
+        match self {
+            CephQuery::PgDump => &["pg", "dump", "--format", "json-pretty"],
+            CephQuery::OsdTree => &["osd", "tree", "--format", "json"],
+            CephQuery::Status => &["status", "--format", "json"],
+        }
+    }
+
+    /// The same query as a mon/mgr command, e.g. `{"prefix":"pg dump","format":"json"}`
+    pub fn mon_command(self) -> serde_json::Value {
+        let prefix = match self {
+            CephQuery::PgDump => "pg dump",
+            CephQuery::OsdTree => "osd tree",
+            CephQuery::Status => "status",
+        };
+        serde_json::json!({ "prefix": prefix, "format": "json" })
+    }
+}
+
+/// Where cluster data comes from.
+#[derive(Debug, Clone)]
+pub enum DataSource {
+    /// Run the `ceph` CLI, optionally through a prefix command such as `ssh host sudo`
+    Cli(PrefixCommand),
+    /// Talk to the ceph-mgr `restful` module over HTTP(S)
+    Rest(RestClient),
+    /// Send mon commands directly, using ceph.conf and a keyring
+    Mon(MonClient),
+}
+
+impl Default for DataSource {
+    fn default() -> Self {
+        DataSource::Cli(PrefixCommand::default())
+    }
+}
+
+impl DataSource {
+    pub async fn fetch<T: DeserializeOwned>(
+        &self,
+        query: CephQuery,
+        timeout: Duration,
+    ) -> Result<T, CephError> {
+        match self {
+            DataSource::Cli(prefix) => fetch_cli_json(prefix, query.cli_args(), timeout).await,
+            DataSource::Rest(client) => client.fetch(query, timeout).await,
+            DataSource::Mon(client) => client.fetch(query, timeout).await,
+        }
+    }
+
+    /// Short description of the query for loading screens and messages
+    pub fn describe(&self, query: CephQuery) -> String {
+        match self {
+            DataSource::Cli(prefix) => prefix.display(query.cli_args()),
+            DataSource::Rest(client) => client.describe(query),
+            DataSource::Mon(client) => client.describe(query),
+        }
+    }
+}
+
+async fn fetch_cli_json<T: DeserializeOwned>(
+    prefix: &PrefixCommand,
+    ceph_args: &[&str],
+    timeout: Duration,
+) -> Result<T, CephError> {
+    let args = prefix.build(ceph_args);
+    let mut command = Command::new(&args[0]);
+    command.args(&args[1..]);
+    // Make sure a timed out or cancelled command does not linger
+    command.kill_on_drop(true);
+
+    let command_str = prefix.display(ceph_args);
+
+    let output = match tokio::time::timeout(timeout, command.output()).await {
+        Ok(Ok(output)) => output,
+        Ok(Err(source)) => {
+            return Err(CephError::Spawn {
+                command: command_str,
+                source,
+            })
+        }
+        Err(_) => {
+            return Err(CephError::Timeout {
+                command: command_str,
+                timeout,
+            })
+        }
+    };
+
+    if !output.status.success() {
+        return Err(CephError::CommandFailed {
+            command: command_str,
+            exit_code: output.status.code().unwrap_or(-1),
+            stdout: String::from_utf8_lossy(&output.stdout).into_owned(),
+            stderr: String::from_utf8_lossy(&output.stderr).into_owned(),
+        });
+    }
+
+    let json_str = String::from_utf8(output.stdout).map_err(|source| CephError::Utf8 {
+        command: command_str.clone(),
+        source,
+    })?;
+
+    serde_json::from_str(&json_str).map_err(|e| {
+        let stderr_str = String::from_utf8_lossy(&output.stderr);
+        CephError::json(&command_str, &e, &json_str, &stderr_str)
+    })
+}
@@ -1,5 +1,20 @@
-use crate::common::{InconsistentPgProgress, OsdDataMovement};
-use std::collections::HashMap;
+use crate::common::{
+    CephPgDump, HostLinkSummary, InconsistentPgProgress, OsdDataMovement, OsdOverview,
+    OsdOverviewMedians, PgListEntry, RecoveryProgress, SlowLink,
+};
+use crate::error::ErrorClass;
+use crate::monitor::data::{
+    calculate_inconsistent_pg_progress, calculate_osd_data_movement, calculate_osd_overview,
+    calculate_pg_list, calculate_pg_states, calculate_recovery_progress, calculate_slow_links,
+    compare_pgs, parse_pg_filter, sort_osd_movement_rows, summarize_slow_links_by_host,
+};
+use crate::monitor::events::Event;
+use crate::monitor::keymap::Keymap;
+use crate::monitor::mouse::MouseAreas;
+use crate::monitor::ui::Theme;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::time::Duration;
 
 #[derive(Debug, Clone)]
 pub struct RecoveryData {
@@ -13,15 +28,601 @@ pub struct CommandError {
     pub stdout: String,
     pub stderr: String,
     pub exit_code: i32,
+    pub class: ErrorClass,
     pub scroll_offset: u16,
 }
 
+/// The tabs of the monitor. A new view needs a variant here, an entry in
+/// `View::ALL`, a title and a render function in `render_view`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum View {
+    #[default]
+    Overview,
+    OsdMovement,
+    InconsistentPgs,
+    PgStates,
+    OsdOverview,
+    Network,
+    Events,
+    Pgs,
+}
+
+impl View {
+    /// The tabs in the order of the tab bar; the number keys follow this order
+    pub const ALL: [View; 8] = [
+        View::Overview,
+        View::OsdMovement,
+        View::InconsistentPgs,
+        View::PgStates,
+        View::OsdOverview,
+        View::Network,
+        View::Events,
+        View::Pgs,
+    ];
+
+    pub fn title(self) -> &'static str {
+        match self {
+            View::Overview => "Overview",
+            View::OsdMovement => "OSD Movement",
+            View::InconsistentPgs => "Inconsistent PGs",
+            View::PgStates => "PG States",
+            View::OsdOverview => "OSD Overview",
+            View::Network => "Network",
+            View::Events => "Events",
+            View::Pgs => "PGs",
+        }
+    }
+
+    pub fn index(self) -> usize {
+        View::ALL.iter().position(|view| *view == self).unwrap_or(0)
+    }
+
+    pub fn next(self) -> Self {
+        View::ALL[(self.index() + 1) % View::ALL.len()]
+    }
+
+    pub fn previous(self) -> Self {
+        View::ALL[(self.index() + View::ALL.len() - 1) % View::ALL.len()]
+    }
+}
+
+/// Selection of one tab; the table scrolls to keep the selected row visible
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct TabState {
+    pub selected: usize,
+}
+
+/// Events kept for the event log view; older ones are dropped
+pub const EVENT_LOG_SIZE: usize = 1000;
+
+/// The event log view: the events, oldest first, and how they are shown
+#[derive(Debug, Default)]
+pub struct EventLog {
+    pub events: VecDeque<Event>,
+    /// Words that must all occur in a shown event
+    pub filter: String,
+    /// The filter is being typed
+    pub editing: bool,
+    /// Shown events scrolled past from the newest one
+    pub scroll: usize,
+    /// Result of the last save, shown in the title
+    pub notice: Option<String>,
+}
+
+impl EventLog {
+    /// The events that match the filter, newest first
+    pub fn filtered(&self) -> Vec<&Event> {
+        self.events
+            .iter()
+            .rev()
+            .filter(|event| event.matches(&self.filter))
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PgSortColumn {
+    #[default]
+    Pgid,
+    State,
+    Up,
+    Acting,
+    Primary,
+    Objects,
+    Misplaced,
+    Degraded,
+    TimeInState,
+}
+
+impl PgSortColumn {
+    /// In the order of the columns of the PG browser
+    pub const ALL: [PgSortColumn; 9] = [
+        PgSortColumn::Pgid,
+        PgSortColumn::State,
+        PgSortColumn::Up,
+        PgSortColumn::Acting,
+        PgSortColumn::Primary,
+        PgSortColumn::Objects,
+        PgSortColumn::Misplaced,
+        PgSortColumn::Degraded,
+        PgSortColumn::TimeInState,
+    ];
+
+    pub fn next(self) -> Self {
+        match self {
+            PgSortColumn::Pgid => PgSortColumn::State,
+            PgSortColumn::State => PgSortColumn::Up,
+            PgSortColumn::Up => PgSortColumn::Acting,
+            PgSortColumn::Acting => PgSortColumn::Primary,
+            PgSortColumn::Primary => PgSortColumn::Objects,
+            PgSortColumn::Objects => PgSortColumn::Misplaced,
+            PgSortColumn::Misplaced => PgSortColumn::Degraded,
+            PgSortColumn::Degraded => PgSortColumn::TimeInState,
+            PgSortColumn::TimeInState => PgSortColumn::Pgid,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            PgSortColumn::Pgid => "PG ID",
+            PgSortColumn::State => "State",
+            PgSortColumn::Up => "Up",
+            PgSortColumn::Acting => "Acting",
+            PgSortColumn::Primary => "Primary",
+            PgSortColumn::Objects => "Objects",
+            PgSortColumn::Misplaced => "Misplaced",
+            PgSortColumn::Degraded => "Degraded",
+            PgSortColumn::TimeInState => "In State",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PgSort {
+    pub column: PgSortColumn,
+    pub descending: bool,
+}
+
+/// The PG browser: all PGs of the last sample and how they are shown. The
+/// shown rows are worked out when the PGs, the filter or the sort change, not
+/// on every frame.
+#[derive(Debug, Default)]
+pub struct PgBrowser {
+    pgs: Vec<PgListEntry>,
+    /// Filter terms like `state:backfill_wait osd:12 pool:3`
+    filter: String,
+    /// The filter is being typed
+    pub editing: bool,
+    sort: PgSort,
+    /// Indices into `pgs` of the PGs that match the filter, in sort order
+    rows: Vec<usize>,
+    /// Why the filter cannot be applied, if it cannot
+    filter_error: Option<String>,
+}
+
+impl PgBrowser {
+    pub fn pgs(&self) -> &[PgListEntry] {
+        &self.pgs
+    }
+
+    pub fn filter(&self) -> &str {
+        &self.filter
+    }
+
+    pub fn sort(&self) -> PgSort {
+        self.sort
+    }
+
+    pub fn set_pgs(&mut self, pgs: Vec<PgListEntry>) {
+        self.pgs = pgs;
+        self.refresh();
+    }
+
+    pub fn set_filter(&mut self, filter: String) {
+        if filter != self.filter {
+            self.filter = filter;
+            self.refresh();
+        }
+    }
+
+    pub fn set_sort(&mut self, sort: PgSort) {
+        if sort != self.sort {
+            self.sort = sort;
+            self.refresh();
+        }
+    }
+
+    /// The PGs that match the filter, in sort order; all of them while the
+    /// filter is invalid
+    pub fn filtered(&self) -> impl ExactSizeIterator<Item = &PgListEntry> + '_ {
+        self.rows.iter().map(|&index| &self.pgs[index])
+    }
+
+    /// Why the filter cannot be applied, if it cannot
+    pub fn filter_error(&self) -> Option<&str> {
+        self.filter_error.as_deref()
+    }
+
+    fn refresh(&mut self) {
+        let terms = parse_pg_filter(&self.filter);
+        self.filter_error = terms.as_ref().err().cloned();
+        let terms = terms.unwrap_or_default();
+        self.rows = (0..self.pgs.len())
+            .filter(|&index| terms.iter().all(|term| term.matches(&self.pgs[index])))
+            .collect();
+        let (pgs, sort) = (&self.pgs, self.sort);
+        self.rows
+            .sort_by(|&a, &b| compare_pgs(&pgs[a], &pgs[b], sort));
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OsdOverviewSortColumn {
+    Osd,
+    #[default]
+    Utilization,
+    Pgs,
+    CommitLatency,
+    ApplyLatency,
+    SnapTrimQueue,
+    Repaired,
+    Alerts,
+}
+
+impl OsdOverviewSortColumn {
+    /// What each column of the OSD overview table sorts by; used and total
+    /// bytes have no sort of their own
+    pub const COLUMNS: [Option<OsdOverviewSortColumn>; 10] = [
+        Some(OsdOverviewSortColumn::Osd),
+        Some(OsdOverviewSortColumn::Utilization),
+        None,
+        None,
+        Some(OsdOverviewSortColumn::Pgs),
+        Some(OsdOverviewSortColumn::CommitLatency),
+        Some(OsdOverviewSortColumn::ApplyLatency),
+        Some(OsdOverviewSortColumn::SnapTrimQueue),
+        Some(OsdOverviewSortColumn::Repaired),
+        Some(OsdOverviewSortColumn::Alerts),
+    ];
+
+    pub fn next(self) -> Self {
+        match self {
+            OsdOverviewSortColumn::Osd => OsdOverviewSortColumn::Utilization,
+            OsdOverviewSortColumn::Utilization => OsdOverviewSortColumn::Pgs,
+            OsdOverviewSortColumn::Pgs => OsdOverviewSortColumn::CommitLatency,
+            OsdOverviewSortColumn::CommitLatency => OsdOverviewSortColumn::ApplyLatency,
+            OsdOverviewSortColumn::ApplyLatency => OsdOverviewSortColumn::SnapTrimQueue,
+            OsdOverviewSortColumn::SnapTrimQueue => OsdOverviewSortColumn::Repaired,
+            OsdOverviewSortColumn::Repaired => OsdOverviewSortColumn::Alerts,
+            OsdOverviewSortColumn::Alerts => OsdOverviewSortColumn::Osd,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            OsdOverviewSortColumn::Osd => "OSD",
+            OsdOverviewSortColumn::Utilization => "Use%",
+            OsdOverviewSortColumn::Pgs => "PGs",
+            OsdOverviewSortColumn::CommitLatency => "Commit",
+            OsdOverviewSortColumn::ApplyLatency => "Apply",
+            OsdOverviewSortColumn::SnapTrimQueue => "SnapTrimQ",
+            OsdOverviewSortColumn::Repaired => "Repaired",
+            OsdOverviewSortColumn::Alerts => "Alerts",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OsdOverviewSort {
+    pub column: OsdOverviewSortColumn,
+    pub descending: bool,
+}
+
+impl Default for OsdOverviewSort {
+    fn default() -> Self {
+        Self {
+            column: OsdOverviewSortColumn::default(),
+            descending: true,
+        }
+    }
+}
+
+/// A column of the OSD data movement table, in table order
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OsdMovementColumn {
+    Osd,
+    Host,
+    Utilization,
+    MissingWaiting,
+    MissingActive,
+    MissingRate,
+    MissingEta,
+    MissingBytes,
+    ExcessWaiting,
+    ExcessActive,
+    ExcessRate,
+    ExcessEta,
+    ExcessBytes,
+}
+
+impl OsdMovementColumn {
+    pub const ALL: [OsdMovementColumn; 13] = [
+        OsdMovementColumn::Osd,
+        OsdMovementColumn::Host,
+        OsdMovementColumn::Utilization,
+        OsdMovementColumn::MissingWaiting,
+        OsdMovementColumn::MissingActive,
+        OsdMovementColumn::MissingRate,
+        OsdMovementColumn::MissingEta,
+        OsdMovementColumn::MissingBytes,
+        OsdMovementColumn::ExcessWaiting,
+        OsdMovementColumn::ExcessActive,
+        OsdMovementColumn::ExcessRate,
+        OsdMovementColumn::ExcessEta,
+        OsdMovementColumn::ExcessBytes,
+    ];
+
+    /// The columns shown until the config or the column chooser says otherwise
+    pub const DEFAULT: [OsdMovementColumn; 9] = [
+        OsdMovementColumn::Osd,
+        OsdMovementColumn::MissingWaiting,
+        OsdMovementColumn::MissingActive,
+        OsdMovementColumn::MissingRate,
+        OsdMovementColumn::MissingEta,
+        OsdMovementColumn::ExcessWaiting,
+        OsdMovementColumn::ExcessActive,
+        OsdMovementColumn::ExcessRate,
+        OsdMovementColumn::ExcessEta,
+    ];
+
+    /// What the table is sorted by when the header of the column is clicked
+    pub fn sort_column(self) -> OsdMovementSortColumn {
+        match self {
+            OsdMovementColumn::Osd => OsdMovementSortColumn::Osd,
+            OsdMovementColumn::Host => OsdMovementSortColumn::Host,
+            OsdMovementColumn::Utilization => OsdMovementSortColumn::Utilization,
+            OsdMovementColumn::MissingWaiting | OsdMovementColumn::MissingActive => {
+                OsdMovementSortColumn::Missing
+            }
+            OsdMovementColumn::MissingRate => OsdMovementSortColumn::MissingRate,
+            OsdMovementColumn::MissingEta => OsdMovementSortColumn::MissingEta,
+            OsdMovementColumn::MissingBytes => OsdMovementSortColumn::MissingBytes,
+            OsdMovementColumn::ExcessWaiting | OsdMovementColumn::ExcessActive => {
+                OsdMovementSortColumn::Excess
+            }
+            OsdMovementColumn::ExcessRate => OsdMovementSortColumn::ExcessRate,
+            OsdMovementColumn::ExcessEta => OsdMovementSortColumn::ExcessEta,
+            OsdMovementColumn::ExcessBytes => OsdMovementSortColumn::ExcessBytes,
+        }
+    }
+
+    /// The two header lines
+    pub fn label(self) -> (&'static str, &'static str) {
+        match self {
+            OsdMovementColumn::Osd => ("OSD", ""),
+            OsdMovementColumn::Host => ("Host", ""),
+            OsdMovementColumn::Utilization => ("Use%", ""),
+            OsdMovementColumn::MissingWaiting => ("Missing", "Waiting"),
+            OsdMovementColumn::MissingActive => ("Missing", "Active"),
+            OsdMovementColumn::MissingRate => ("Missing", "Rate/s"),
+            OsdMovementColumn::MissingEta => ("Missing", "ETA"),
+            OsdMovementColumn::MissingBytes => ("Missing", "Bytes"),
+            OsdMovementColumn::ExcessWaiting => ("Excess", "Waiting"),
+            OsdMovementColumn::ExcessActive => ("Excess", "Active"),
+            OsdMovementColumn::ExcessRate => ("Excess", "Rate/s"),
+            OsdMovementColumn::ExcessEta => ("Excess", "ETA"),
+            OsdMovementColumn::ExcessBytes => ("Excess", "Bytes"),
+        }
+    }
+}
+
+/// What the OSD data movement table is sorted by. `Missing` and `Excess`
+/// compare the waiting plus active objects, then the other direction.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OsdMovementSortColumn {
+    #[default]
+    Missing,
+    Excess,
+    Osd,
+    Host,
+    Utilization,
+    MissingRate,
+    MissingEta,
+    MissingBytes,
+    ExcessRate,
+    ExcessEta,
+    ExcessBytes,
+}
+
+impl OsdMovementSortColumn {
+    pub fn next(self) -> Self {
+        match self {
+            OsdMovementSortColumn::Missing => OsdMovementSortColumn::Excess,
+            OsdMovementSortColumn::Excess => OsdMovementSortColumn::Osd,
+            OsdMovementSortColumn::Osd => OsdMovementSortColumn::Host,
+            OsdMovementSortColumn::Host => OsdMovementSortColumn::Utilization,
+            OsdMovementSortColumn::Utilization => OsdMovementSortColumn::MissingRate,
+            OsdMovementSortColumn::MissingRate => OsdMovementSortColumn::MissingEta,
+            OsdMovementSortColumn::MissingEta => OsdMovementSortColumn::MissingBytes,
+            OsdMovementSortColumn::MissingBytes => OsdMovementSortColumn::ExcessRate,
+            OsdMovementSortColumn::ExcessRate => OsdMovementSortColumn::ExcessEta,
+            OsdMovementSortColumn::ExcessEta => OsdMovementSortColumn::ExcessBytes,
+            OsdMovementSortColumn::ExcessBytes => OsdMovementSortColumn::Missing,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            OsdMovementSortColumn::Missing => "Missing",
+            OsdMovementSortColumn::Excess => "Excess",
+            OsdMovementSortColumn::Osd => "OSD",
+            OsdMovementSortColumn::Host => "Host",
+            OsdMovementSortColumn::Utilization => "Use%",
+            OsdMovementSortColumn::MissingRate => "Missing Rate/s",
+            OsdMovementSortColumn::MissingEta => "Missing ETA",
+            OsdMovementSortColumn::MissingBytes => "Missing Bytes",
+            OsdMovementSortColumn::ExcessRate => "Excess Rate/s",
+            OsdMovementSortColumn::ExcessEta => "Excess ETA",
+            OsdMovementSortColumn::ExcessBytes => "Excess Bytes",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OsdMovementSort {
+    pub column: OsdMovementSortColumn,
+    pub descending: bool,
+}
+
+impl Default for OsdMovementSort {
+    fn default() -> Self {
+        Self {
+            column: OsdMovementSortColumn::default(),
+            descending: true,
+        }
+    }
+}
+
+/// The `[osd_movement]` table of the config file: how the OSD data movement
+/// table starts out
+#[derive(Debug, Default, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct OsdMovementSettings {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sort: Option<OsdMovementSortColumn>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub descending: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub columns: Option<Vec<OsdMovementColumn>>,
+}
+
+impl OsdMovementSettings {
+    /// Settings of `over` win
+    pub fn merge(self, over: OsdMovementSettings) -> OsdMovementSettings {
+        OsdMovementSettings {
+            sort: over.sort.or(self.sort),
+            descending: over.descending.or(self.descending),
+            columns: over.columns.or(self.columns),
+        }
+    }
+
+    pub fn with_defaults(self) -> OsdMovementSettings {
+        let table = OsdMovementTable::new(&self);
+        OsdMovementSettings {
+            sort: Some(table.sort.column),
+            descending: Some(table.sort.descending),
+            columns: Some(table.columns),
+        }
+    }
+}
+
+/// Sort order and shown columns of the OSD data movement table
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OsdMovementTable {
+    pub sort: OsdMovementSort,
+    /// The shown columns in table order; the OSD column is always shown
+    pub columns: Vec<OsdMovementColumn>,
+    /// Row of the column chooser while it is open
+    pub chooser: Option<usize>,
+    /// OSD of the selected row; the selection follows it when the order changes
+    pub selected_osd: Option<u32>,
+}
+
+impl Default for OsdMovementTable {
+    fn default() -> Self {
+        Self::new(&OsdMovementSettings::default())
+    }
+}
+
+impl OsdMovementTable {
+    pub fn new(settings: &OsdMovementSettings) -> Self {
+        let shown = settings
+            .columns
+            .as_deref()
+            .unwrap_or(&OsdMovementColumn::DEFAULT);
+        Self {
+            sort: OsdMovementSort {
+                column: settings.sort.unwrap_or_default(),
+                descending: settings.descending.unwrap_or(true),
+            },
+            columns: OsdMovementColumn::ALL
+                .into_iter()
+                .filter(|column| *column == OsdMovementColumn::Osd || shown.contains(column))
+                .collect(),
+            chooser: None,
+            selected_osd: None,
+        }
+    }
+
+    /// The columns the chooser can show or hide
+    pub fn choosable() -> &'static [OsdMovementColumn] {
+        &OsdMovementColumn::ALL[1..]
+    }
+
+    pub fn toggle_column(&mut self, column: OsdMovementColumn) {
+        if column == OsdMovementColumn::Osd {
+            return;
+        }
+        if self.columns.contains(&column) {
+            self.columns.retain(|shown| *shown != column);
+        } else {
+            self.columns = OsdMovementColumn::ALL
+                .into_iter()
+                .filter(|shown| *shown == column || self.columns.contains(shown))
+                .collect();
+        }
+    }
+}
+
+/// One row of the OSD data movement table, with what it shows from other sources
+#[derive(Debug, Clone)]
+pub struct OsdMovementRow {
+    pub movement: OsdDataMovement,
+    pub host: Option<String>,
+    pub utilization: Option<f64>,
+}
+
 #[derive(Debug, Default)]
 pub struct MonitorState {
     recovery_history: HashMap<String, Vec<RecoveryData>>,
+    recovery_progress: Vec<RecoveryProgress>,
     osd_movements: HashMap<u32, OsdDataMovement>,
     inconsistent_pg_progress: HashMap<String, InconsistentPgProgress>,
+    /// Number of PGs per state, most common first
+    pg_states: Vec<(String, usize)>,
+    osd_overview: Vec<OsdOverview>,
+    osd_overview_medians: OsdOverviewMedians,
     command_error_popup: Option<CommandError>,
+    active_view: View,
+    tabs: HashMap<View, TabState>,
+    osd_overview_sort: OsdOverviewSort,
+    osd_movement_table: OsdMovementTable,
+    osd_hosts: HashMap<u32, String>,
+    /// The OSDs with data to move, in the table's sort order
+    osd_movement_rows: Vec<OsdMovementRow>,
+    ping_threshold_ms: f64,
+    /// Heartbeat links slower than the threshold, slowest first
+    slow_links: Vec<SlowLink>,
+    /// The slow links per host pair, worst first
+    slow_link_hosts: Vec<HostLinkSummary>,
+    fetch_elapsed: Option<Duration>,
+    paused: bool,
+    keymap: Keymap,
+    /// The mouse is captured for clicks and the wheel
+    mouse: bool,
+    /// The help overlay is open
+    help: bool,
+    fetch_description: String,
+    cluster_name: Option<String>,
+    theme: Theme,
+    /// Where the last frame drew what can be clicked
+    mouse_areas: MouseAreas,
+    /// Result of the last screen export, shown in the footer
+    notice: Option<String>,
+    event_log: EventLog,
+    pg_browser: PgBrowser,
 }
 
 impl MonitorState {
@@ -29,6 +630,21 @@ impl MonitorState {
         Self::default()
     }
 
+    /// Take in a newly fetched pg dump: extend the histories and compute the
+    /// rates and ETAs the views show. Called once per sample; drawing only
+    /// reads the result, so redraws (e.g. on resize) do not add samples.
+    pub fn update(&mut self, data: &CephPgDump, interval: u64) {
+        // The OSD movement rows show the utilization of the OSD overview
+        (self.osd_overview, self.osd_overview_medians) = calculate_osd_overview(data);
+        calculate_recovery_progress(data, self, interval);
+        calculate_osd_data_movement(data, self, interval);
+        calculate_inconsistent_pg_progress(data, self, interval);
+        self.pg_states = calculate_pg_states(data);
+        self.pg_browser.set_pgs(calculate_pg_list(data));
+        self.slow_links = calculate_slow_links(data, &self.osd_hosts, self.ping_threshold_ms);
+        self.slow_link_hosts = summarize_slow_links_by_host(&self.slow_links);
+    }
+
     pub fn get_recovery_history(&self, category: &str) -> Option<&Vec<RecoveryData>> {
         self.recovery_history.get(category)
     }
@@ -47,6 +663,14 @@ impl MonitorState {
         }
     }
 
+    pub fn get_recovery_progress(&self) -> &[RecoveryProgress] {
+        &self.recovery_progress
+    }
+
+    pub fn set_recovery_progress(&mut self, progress: Vec<RecoveryProgress>) {
+        self.recovery_progress = progress;
+    }
+
     pub fn get_osd_movements(&self) -> &HashMap<u32, OsdDataMovement> {
         &self.osd_movements
     }
@@ -57,6 +681,7 @@ impl MonitorState {
 
     pub fn set_osd_movements(&mut self, movements: HashMap<u32, OsdDataMovement>) {
         self.osd_movements = movements;
+        self.refresh_osd_movement_rows();
     }
 
     pub fn get_inconsistent_pg_progress(&self) -> &HashMap<String, InconsistentPgProgress> {
@@ -76,6 +701,27 @@ impl MonitorState {
         self.inconsistent_pg_progress = progress;
     }
 
+    /// Forget the samples behind the rates and ETAs, keeping the current
+    /// counts. The rates assume one interval between samples, which no longer
+    /// holds after a pause or a change of the interval.
+    pub fn restart_rates(&mut self) {
+        self.recovery_history.clear();
+        for movement in self.osd_movements.values_mut() {
+            movement.missing_objects_history.clear();
+            movement.excess_objects_history.clear();
+            movement.incoming_rate = None;
+            movement.outgoing_rate = None;
+            movement.incoming_predicted_time_secs = None;
+            movement.outgoing_predicted_time_secs = None;
+        }
+        for pg in self.inconsistent_pg_progress.values_mut() {
+            pg.scrubbed_history.clear();
+            pg.scrub_rate = None;
+            pg.eta_seconds = None;
+        }
+        self.refresh_osd_movement_rows();
+    }
+
     pub fn clear_recovery_history(&mut self) {
         self.recovery_history.clear();
     }
@@ -88,6 +734,14 @@ impl MonitorState {
         self.inconsistent_pg_progress.clear();
     }
 
+    pub fn pg_states(&self) -> &[(String, usize)] {
+        &self.pg_states
+    }
+
+    pub fn osd_overview(&self) -> (&[OsdOverview], &OsdOverviewMedians) {
+        (&self.osd_overview, &self.osd_overview_medians)
+    }
+
     pub fn get_command_error_popup(&self) -> Option<&CommandError> {
         self.command_error_popup.as_ref()
     }
@@ -115,4 +769,421 @@ impl MonitorState {
             error.scroll_offset = error.scroll_offset.saturating_add(1);
         }
     }
+
+    pub fn active_view(&self) -> View {
+        self.active_view
+    }
+
+    pub fn next_view(&mut self) {
+        self.active_view = self.active_view.next();
+    }
+
+    pub fn previous_view(&mut self) {
+        self.active_view = self.active_view.previous();
+    }
+
+    pub fn select_view(&mut self, view: View) {
+        self.active_view = view;
+    }
+
+    pub fn tab(&self, view: View) -> TabState {
+        self.tabs.get(&view).copied().unwrap_or_default()
+    }
+
+    /// Rows the user can select in a tab; zero for tabs without a selection
+    pub fn tab_rows(&self, view: View) -> usize {
+        match view {
+            View::OsdMovement => self.osd_movement_rows().len(),
+            View::InconsistentPgs => self.inconsistent_pg_progress.len(),
+            View::PgStates => self.pg_states.len(),
+            View::OsdOverview => self.osd_overview.len(),
+            View::Pgs => self.pg_browser.filtered().len(),
+            View::Overview | View::Network | View::Events => 0,
+        }
+    }
+
+    /// Move the selection of the active tab by `delta` rows, within its rows
+    pub fn move_selection(&mut self, delta: isize) {
+        let view = self.active_view;
+        let last = self.tab_rows(view).saturating_sub(1);
+        let tab = self.tabs.entry(view).or_default();
+        tab.selected = tab
+            .selected
+            .min(last)
+            .saturating_add_signed(delta)
+            .min(last);
+        if view == View::OsdMovement {
+            self.remember_selected_osd();
+        }
+    }
+
+    /// Select a row of the active tab, e.g. a clicked one; false when there
+    /// is no such row
+    pub fn select_row(&mut self, row: usize) -> bool {
+        let view = self.active_view;
+        if row >= self.tab_rows(view) {
+            return false;
+        }
+        self.tabs.entry(view).or_default().selected = row;
+        if view == View::OsdMovement {
+            self.remember_selected_osd();
+        }
+        true
+    }
+
+    /// Back to the first row of the active tab, e.g. when its filter changes
+    pub fn reset_selection(&mut self) {
+        self.tabs.remove(&self.active_view);
+    }
+
+    pub fn osd_overview_sort(&self) -> OsdOverviewSort {
+        self.osd_overview_sort
+    }
+
+    /// Sort the table of the active tab by the next column: the PG browser,
+    /// the OSD data movement (also on the overview) or the OSD overview
+    pub fn cycle_sort(&mut self) {
+        match self.active_view {
+            View::Pgs => {
+                let mut sort = self.pg_browser.sort();
+                sort.column = sort.column.next();
+                self.pg_browser.set_sort(sort);
+            }
+            View::Overview | View::OsdMovement => {
+                let sort = &mut self.osd_movement_table.sort;
+                sort.column = sort.column.next();
+                self.refresh_osd_movement_rows();
+            }
+            View::OsdOverview => {
+                self.osd_overview_sort.column = self.osd_overview_sort.column.next()
+            }
+            // The other tabs have no sortable table
+            View::InconsistentPgs | View::PgStates | View::Network | View::Events => {}
+        }
+    }
+
+    pub fn reverse_sort(&mut self) {
+        match self.active_view {
+            View::Pgs => {
+                let mut sort = self.pg_browser.sort();
+                sort.descending = !sort.descending;
+                self.pg_browser.set_sort(sort);
+            }
+            View::Overview | View::OsdMovement => {
+                let sort = &mut self.osd_movement_table.sort;
+                sort.descending = !sort.descending;
+                self.refresh_osd_movement_rows();
+            }
+            View::OsdOverview => {
+                self.osd_overview_sort.descending = !self.osd_overview_sort.descending
+            }
+            View::InconsistentPgs | View::PgStates | View::Network | View::Events => {}
+        }
+    }
+
+    /// Sort the table of the active tab by a column, e.g. one whose header
+    /// was clicked; the column it is sorted by already reverses the order.
+    /// False for columns that cannot be sorted by.
+    pub fn sort_by_header(&mut self, column: usize) -> bool {
+        match self.active_view {
+            View::Pgs => {
+                let Some(column) = PgSortColumn::ALL.get(column) else {
+                    return false;
+                };
+                let mut sort = self.pg_browser.sort();
+                if sort.column == *column {
+                    sort.descending = !sort.descending;
+                }
+                sort.column = *column;
+                self.pg_browser.set_sort(sort);
+            }
+            View::Overview | View::OsdMovement => {
+                let Some(column) = self.osd_movement_table.columns.get(column) else {
+                    return false;
+                };
+                let column = column.sort_column();
+                let sort = &mut self.osd_movement_table.sort;
+                if sort.column == column {
+                    sort.descending = !sort.descending;
+                }
+                sort.column = column;
+                self.refresh_osd_movement_rows();
+            }
+            View::OsdOverview => {
+                let Some(Some(column)) = OsdOverviewSortColumn::COLUMNS.get(column) else {
+                    return false;
+                };
+                let sort = &mut self.osd_overview_sort;
+                if sort.column == *column {
+                    sort.descending = !sort.descending;
+                }
+                sort.column = *column;
+            }
+            _ => return false,
+        }
+        true
+    }
+
+    pub fn osd_movement_table(&self) -> &OsdMovementTable {
+        &self.osd_movement_table
+    }
+
+    pub fn osd_movement_table_mut(&mut self) -> &mut OsdMovementTable {
+        &mut self.osd_movement_table
+    }
+
+    pub fn set_osd_movement_table(&mut self, table: OsdMovementTable) {
+        self.osd_movement_table = table;
+        self.refresh_osd_movement_rows();
+    }
+
+    /// Move the OSD data movement selection to the row of the selected OSD
+    /// after the rows were sorted or refreshed. When that OSD has no more data
+    /// to move, the selection stays at its position and follows the OSD there.
+    fn follow_selected_osd(&mut self) {
+        let Some(osd) = self.osd_movement_table.selected_osd else {
+            return;
+        };
+        let position = self
+            .osd_movement_rows()
+            .iter()
+            .position(|row| row.movement.osd_id == osd);
+        match position {
+            Some(index) => self.tabs.entry(View::OsdMovement).or_default().selected = index,
+            None => self.remember_selected_osd(),
+        }
+    }
+
+    fn remember_selected_osd(&mut self) {
+        let rows = self.osd_movement_rows();
+        let selected = self
+            .tab(View::OsdMovement)
+            .selected
+            .min(rows.len().saturating_sub(1));
+        let osd = rows.get(selected).map(|row| row.movement.osd_id);
+        self.osd_movement_table.selected_osd = osd;
+    }
+
+    /// OSDs with data to move, with host and utilization, in the table's sort order
+    pub fn osd_movement_rows(&self) -> &[OsdMovementRow] {
+        &self.osd_movement_rows
+    }
+
+    /// Build the OSD movement rows again after the movements, the hosts or
+    /// the sort changed, and keep the selection on the same OSD
+    fn refresh_osd_movement_rows(&mut self) {
+        let utilization: HashMap<u32, f64> = self
+            .osd_overview
+            .iter()
+            .map(|osd| (osd.osd_id, osd.utilization))
+            .collect();
+        let mut rows: Vec<OsdMovementRow> = self
+            .osd_movements
+            .values()
+            .filter(|movement| movement.missing_objects > 0 || movement.excess_objects > 0)
+            .map(|movement| OsdMovementRow {
+                movement: movement.clone(),
+                host: self.osd_hosts.get(&movement.osd_id).cloned(),
+                utilization: utilization.get(&movement.osd_id).copied(),
+            })
+            .collect();
+        sort_osd_movement_rows(&mut rows, self.osd_movement_table.sort);
+        self.osd_movement_rows = rows;
+        self.follow_selected_osd();
+    }
+
+    pub fn get_osd_hosts(&self) -> &HashMap<u32, String> {
+        &self.osd_hosts
+    }
+
+    /// The host names arrive after the pg dump they belong to, so they are
+    /// filled into the rows and links already worked out from it
+    pub fn set_osd_hosts(&mut self, osd_hosts: HashMap<u32, String>) {
+        self.osd_hosts = osd_hosts;
+        for link in &mut self.slow_links {
+            link.from_host = self.osd_hosts.get(&link.from_osd).cloned();
+            link.to_host = self.osd_hosts.get(&link.to_osd).cloned();
+        }
+        self.slow_link_hosts = summarize_slow_links_by_host(&self.slow_links);
+        self.refresh_osd_movement_rows();
+    }
+
+    /// Heartbeat links slower than the threshold and their summary per host pair
+    pub fn slow_links(&self) -> (&[SlowLink], &[HostLinkSummary]) {
+        (&self.slow_links, &self.slow_link_hosts)
+    }
+
+    pub fn ping_threshold_ms(&self) -> f64 {
+        self.ping_threshold_ms
+    }
+
+    pub fn set_ping_threshold_ms(&mut self, threshold_ms: f64) {
+        self.ping_threshold_ms = threshold_ms;
+    }
+
+    /// Time the currently running fetch has been going, if any
+    pub fn fetch_elapsed(&self) -> Option<Duration> {
+        self.fetch_elapsed
+    }
+
+    pub fn set_fetch_elapsed(&mut self, elapsed: Option<Duration>) {
+        self.fetch_elapsed = elapsed;
+    }
+
+    /// Polling is paused; the shown data is the last snapshot and goes stale
+    pub fn paused(&self) -> bool {
+        self.paused
+    }
+
+    pub fn set_paused(&mut self, paused: bool) {
+        self.paused = paused;
+    }
+
+    /// The keys, for the hints on screen and the help overlay
+    pub fn keymap(&self) -> &Keymap {
+        &self.keymap
+    }
+
+    pub fn set_keymap(&mut self, keymap: Keymap) {
+        self.keymap = keymap;
+    }
+
+    pub fn mouse(&self) -> bool {
+        self.mouse
+    }
+
+    pub fn set_mouse(&mut self, mouse: bool) {
+        self.mouse = mouse;
+    }
+
+    pub fn help(&self) -> bool {
+        self.help
+    }
+
+    pub fn toggle_help(&mut self) {
+        self.help = !self.help;
+    }
+
+    /// What is being fetched, shown on the loading screen
+    pub fn fetch_description(&self) -> &str {
+        &self.fetch_description
+    }
+
+    pub fn set_fetch_description(&mut self, description: String) {
+        self.fetch_description = description;
+    }
+
+    pub fn theme(&self) -> &Theme {
+        &self.theme
+    }
+
+    pub fn set_theme(&mut self, theme: Theme) {
+        self.theme = theme;
+    }
+
+    pub fn notice(&self) -> Option<&str> {
+        self.notice.as_deref()
+    }
+
+    pub fn set_notice(&mut self, notice: String) {
+        self.notice = Some(notice);
+    }
+
+    pub fn mouse_areas(&self) -> &MouseAreas {
+        &self.mouse_areas
+    }
+
+    pub fn set_mouse_areas(&mut self, areas: MouseAreas) {
+        self.mouse_areas = areas;
+    }
+
+    /// Name of the cluster when it is one of several in the multi-cluster view
+    pub fn cluster_name(&self) -> Option<&str> {
+        self.cluster_name.as_deref()
+    }
+
+    pub fn set_cluster_name(&mut self, name: String) {
+        self.cluster_name = Some(name);
+    }
+
+    pub fn event_log(&self) -> &EventLog {
+        &self.event_log
+    }
+
+    pub fn event_log_mut(&mut self) -> &mut EventLog {
+        &mut self.event_log
+    }
+
+    pub fn pg_browser(&self) -> &PgBrowser {
+        &self.pg_browser
+    }
+
+    pub fn pg_browser_mut(&mut self) -> &mut PgBrowser {
+        &mut self.pg_browser
+    }
+
+    pub fn add_events(&mut self, events: impl IntoIterator<Item = Event>) {
+        let log = &mut self.event_log;
+        for event in events {
+            // Keep the view where it is while new events come in above it
+            if log.scroll > 0 && event.matches(&log.filter) {
+                log.scroll += 1;
+            }
+            log.events.push_back(event);
+        }
+        while log.events.len() > EVENT_LOG_SIZE {
+            log.events.pop_front();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pg(pgid: &str, state: &str, objects: i64) -> PgListEntry {
+        PgListEntry {
+            pgid: pgid.to_string(),
+            state: state.to_string(),
+            objects,
+            ..Default::default()
+        }
+    }
+
+    fn pgids(browser: &PgBrowser) -> Vec<&str> {
+        browser.filtered().map(|pg| pg.pgid.as_str()).collect()
+    }
+
+    #[test]
+    fn test_pg_browser_rows() {
+        let mut browser = PgBrowser::default();
+        browser.set_pgs(vec![
+            pg("1.2", "active+clean", 30),
+            pg("1.0", "active+remapped+backfill_wait", 10),
+            pg("1.1", "active+remapped+backfilling", 20),
+        ]);
+        assert_eq!(pgids(&browser), ["1.0", "1.1", "1.2"]);
+
+        browser.set_filter("state:remapped".to_string());
+        assert_eq!(pgids(&browser), ["1.0", "1.1"]);
+
+        browser.set_sort(PgSort {
+            column: PgSortColumn::Objects,
+            descending: true,
+        });
+        assert_eq!(pgids(&browser), ["1.1", "1.0"]);
+
+        // New PGs keep the filter and the sort
+        browser.set_pgs(vec![
+            pg("1.0", "active+clean", 10),
+            pg("1.1", "active+remapped+backfilling", 20),
+            pg("1.3", "active+remapped+backfill_wait", 40),
+        ]);
+        assert_eq!(pgids(&browser), ["1.3", "1.1"]);
+
+        // An invalid filter shows all PGs
+        browser.set_filter("nope:1".to_string());
+        assert!(browser.filter_error().is_some());
+        assert_eq!(browser.filtered().len(), 3);
+    }
 }
@@ -1,6 +1,9 @@
+use crate::monitor::keymap::{Action, Keymap};
+use crate::monitor::state::View;
 use crate::Result;
-use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyModifiers};
+use crossterm::event::{self, Event};
 use crossterm::{
+    event::{DisableMouseCapture, EnableMouseCapture},
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
@@ -10,17 +13,34 @@ use std::time::Duration;
 
 pub struct TerminalManager {
     terminal: Terminal<CrosstermBackend<io::Stdout>>,
+    keymap: Keymap,
+    mouse: bool,
 }
 
 impl TerminalManager {
-    pub fn new() -> Result<Self> {
+    /// Take over the terminal; with `mouse` the clicks and the wheel come to
+    /// the monitor instead of the terminal
+    pub fn new(mouse: bool) -> Result<Self> {
         enable_raw_mode()?;
         let mut stdout = io::stdout();
         execute!(stdout, EnterAlternateScreen)?;
+        if mouse {
+            execute!(stdout, EnableMouseCapture)?;
+        }
         let backend = CrosstermBackend::new(stdout);
         let terminal = Terminal::new(backend)?;
 
-        Ok(TerminalManager { terminal })
+        Ok(TerminalManager {
+            terminal,
+            keymap: Keymap::default(),
+            mouse,
+        })
+    }
+
+    /// Use these keys instead of the defaults
+    pub fn with_keymap(mut self, keymap: Keymap) -> Self {
+        self.keymap = keymap;
+        self
     }
 
     pub fn terminal(&mut self) -> &mut Terminal<CrosstermBackend<io::Stdout>> {
@@ -29,6 +49,9 @@ impl TerminalManager {
 
     pub fn cleanup(&mut self) -> Result<()> {
         disable_raw_mode()?;
+        if self.mouse {
+            execute!(self.terminal.backend_mut(), DisableMouseCapture)?;
+        }
         execute!(self.terminal.backend_mut(), LeaveAlternateScreen)?;
         Ok(())
     }
@@ -41,67 +64,13 @@ impl TerminalManager {
         Ok(event::read()?)
     }
 
-    pub fn should_quit(&self, event: &Event) -> bool {
-        match event {
-            Event::Key(key) => self.is_quit_key(key),
-            _ => false,
-        }
+    /// Whether the event is one of the keys of the action
+    pub fn is(&self, action: Action, event: &Event) -> bool {
+        self.keymap.matches(action, event)
     }
 
-    pub fn should_close_popup(&self, event: &Event) -> bool {
-        match event {
-            Event::Key(key) => matches!(
-                key,
-                KeyEvent {
-                    code: KeyCode::Esc | KeyCode::Enter | KeyCode::Char(' '),
-                    ..
-                }
-            ),
-            _ => false,
-        }
-    }
-
-    pub fn is_scroll_up(&self, event: &Event) -> bool {
-        match event {
-            Event::Key(key) => matches!(
-                key,
-                KeyEvent {
-                    code: KeyCode::Up | KeyCode::Char('k'),
-                    ..
-                }
-            ),
-            _ => false,
-        }
-    }
-
-    pub fn is_scroll_down(&self, event: &Event) -> bool {
-        match event {
-            Event::Key(key) => matches!(
-                key,
-                KeyEvent {
-                    code: KeyCode::Down | KeyCode::Char('j'),
-                    ..
-                }
-            ),
-            _ => false,
-        }
-    }
-
-    pub fn is_quit_key(&self, key: &KeyEvent) -> bool {
-        matches!(
-            key,
-            KeyEvent {
-                code: KeyCode::Char('c'),
-                modifiers: KeyModifiers::CONTROL,
-                ..
-            } | KeyEvent {
-                code: KeyCode::Char('q'),
-                ..
-            } | KeyEvent {
-                code: KeyCode::Esc,
-                ..
-            }
-        )
+    pub fn keymap(&self) -> &Keymap {
+        &self.keymap
     }
 }
 
@@ -111,45 +80,31 @@ impl Drop for TerminalManager {
     }
 }
 
-pub async fn sleep_with_event_check(
-    duration_secs: u64,
-    terminal_manager: &TerminalManager,
-) -> Result<SleepResult> {
-    let mut remaining_time = duration_secs as f32;
-    while remaining_time > 0.0 {
-        let sleep_duration = remaining_time.min(0.25);
-        tokio::time::sleep(Duration::from_secs_f32(sleep_duration)).await;
-        remaining_time -= sleep_duration;
+/// Switching tabs and sorting the table of the active one
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ViewAction {
+    NextView,
+    PreviousView,
+    SelectView(View),
+    SortNext,
+    SortReverse,
+}
 
-        if terminal_manager.poll_event(Duration::from_millis(0))? {
-            let event = terminal_manager.read_event()?;
-            if terminal_manager.should_quit(&event) {
-                return Ok(SleepResult::Quit);
-            }
-            if matches!(event, Event::Resize(_, _)) {
-                return Ok(SleepResult::Resize);
-            }
-            // Handle popup events during sleep
-            if terminal_manager.should_close_popup(&event) {
-                return Ok(SleepResult::PopupClose);
-            }
-            if terminal_manager.is_scroll_up(&event) {
-                return Ok(SleepResult::PopupScrollUp);
-            }
-            if terminal_manager.is_scroll_down(&event) {
-                return Ok(SleepResult::PopupScrollDown);
-            }
-        }
-    }
-    Ok(SleepResult::Continue)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TextInput {
+    Char(char),
+    Backspace,
+    Done,
+    Cancel,
 }
 
-#[derive(Debug, PartialEq)]
-pub enum SleepResult {
-    Continue,
-    Quit,
-    Resize,
-    PopupClose,
-    PopupScrollUp,
-    PopupScrollDown,
+/// Changes to the polling of a running monitor
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RuntimeControl {
+    TogglePause,
+    RefreshNow,
+    /// Step the interval up, polling less often
+    SlowerInterval,
+    /// Step the interval down, polling more often
+    FasterInterval,
 }
@@ -0,0 +1,118 @@
+use crate::common::ClusterSummary;
+use crate::monitor::data::formatter::*;
+use crate::monitor::mouse::TableArea;
+use crate::monitor::ui::Theme;
+use ratatui::{
+    layout::{Constraint, Rect},
+    style::{Modifier, Style},
+    widgets::{Block, BorderType, Borders, Row, Table, TableState},
+    Frame,
+};
+
+pub fn render_cluster_summary(
+    f: &mut Frame,
+    area: Rect,
+    clusters: &[ClusterSummary],
+    selected: usize,
+    theme: &Theme,
+) -> TableArea {
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_type(BorderType::Rounded)
+        .title("Clusters")
+        .title_style(Style::default().add_modifier(Modifier::BOLD));
+
+    let rows: Vec<Row> = clusters
+        .iter()
+        .map(|cluster| {
+            let health = cluster.health.as_deref().unwrap_or("-");
+            let style = cluster_style(cluster, theme);
+
+            let (degraded, misplaced, eta, inconsistent) = if cluster.has_data {
+                let eta = match cluster.eta_seconds {
+                    Some(seconds) => format_time(seconds),
+                    None if cluster.degraded_objects + cluster.misplaced_objects > 0 => {
+                        "calculating...".to_string()
+                    }
+                    None => "-".to_string(),
+                };
+                (
+                    format_number(cluster.degraded_objects),
+                    format_number(cluster.misplaced_objects),
+                    eta,
+                    cluster.inconsistent_pgs.to_string(),
+                )
+            } else {
+                Default::default()
+            };
+
+            Row::new(vec![
+                cluster.name.clone(),
+                health.to_string(),
+                degraded,
+                misplaced,
+                eta,
+                inconsistent,
+                cluster_status(cluster),
+            ])
+            .style(style)
+        })
+        .collect();
+
+    let header = Row::new(vec![
+        "Cluster",
+        "Health",
+        "Degraded",
+        "Misplaced",
+        "ETA",
+        "Inconsistent",
+        "Status",
+    ])
+    .style(Style::default().add_modifier(Modifier::BOLD))
+    .height(1);
+
+    let widths = [
+        Constraint::Length(20), // Cluster
+        Constraint::Length(12), // Health
+        Constraint::Length(12), // Degraded
+        Constraint::Length(12), // Misplaced
+        Constraint::Length(14), // ETA
+        Constraint::Length(12), // Inconsistent
+        Constraint::Min(0),     // Status
+    ];
+    let inner = block.inner(area);
+    let table = Table::new(rows, widths)
+        .header(header)
+        .block(block)
+        .row_highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+
+    let mut table_state = TableState::default().with_selected(Some(selected));
+    f.render_stateful_widget(table, area, &mut table_state);
+    // No highlight symbol, so the columns do not move with the selection
+    TableArea::new(inner, &widths, 1, "", &table_state)
+}
+
+fn cluster_style(cluster: &ClusterSummary, theme: &Theme) -> Style {
+    if cluster.error.is_some() || cluster.inconsistent_pgs > 0 {
+        return theme.critical;
+    }
+    match cluster.health.as_deref() {
+        Some("HEALTH_OK") => theme.good,
+        Some("HEALTH_WARN") => theme.warning,
+        Some("HEALTH_ERR") => theme.critical,
+        _ => Style::default(),
+    }
+}
+
+/// What the fetcher is up to, or why there is no data
+fn cluster_status(cluster: &ClusterSummary) -> String {
+    if let Some(error) = &cluster.error {
+        error.clone()
+    } else if let Some(elapsed) = cluster.fetch_elapsed {
+        format!("Fetching… {}s", elapsed.as_secs())
+    } else if cluster.has_data {
+        "OK".to_string()
+    } else {
+        "Waiting".to_string()
+    }
+}
@@ -0,0 +1,124 @@
+use crate::monitor::events::Severity;
+use crate::monitor::keymap::{Action, Keymap};
+use crate::monitor::state::EventLog;
+use crate::monitor::ui::Theme;
+use ratatui::{
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Modifier, Style},
+    widgets::{Block, BorderType, Borders, Paragraph, Row, Table},
+    Frame,
+};
+
+pub fn render_event_log(f: &mut Frame, area: Rect, log: &EventLog, keymap: &Keymap, theme: &Theme) {
+    let events = log.filtered();
+    let mut title = if log.filter.is_empty() {
+        format!("Events ({})", events.len())
+    } else {
+        format!("Events ({} of {})", events.len(), log.events.len())
+    };
+    if let Some(notice) = &log.notice {
+        title.push_str(&format!(" - {notice}"));
+    }
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_type(BorderType::Rounded)
+        .title(title)
+        .title_style(Style::default().add_modifier(Modifier::BOLD));
+    let inner = block.inner(area);
+    f.render_widget(block, area);
+
+    // The filter line, while typing it or when one is set
+    let show_filter = log.editing || !log.filter.is_empty();
+    let layout = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(u16::from(show_filter)), // Filter
+            Constraint::Min(0),                         // Events
+        ])
+        .split(inner);
+
+    if show_filter {
+        let cursor = if log.editing { "_" } else { "" };
+        let hint = if log.editing {
+            "  [Enter] Apply [Esc] Clear".to_string()
+        } else {
+            format!("  {}", keymap.hint(&[Action::Search], "Edit"))
+        };
+        let style = if log.editing {
+            Style::default().add_modifier(Modifier::BOLD)
+        } else {
+            Style::default()
+        };
+        f.render_widget(
+            Paragraph::new(format!("Filter: {}{cursor}{hint}", log.filter)).style(style),
+            layout[0],
+        );
+    }
+
+    if events.is_empty() {
+        let text = if log.events.is_empty() {
+            format!(
+                "No changes seen yet. PG state changes, remapped PGs, OSDs going down or up and \
+                 recovery events show up here as they happen.\n\n{} • {} • {}",
+                keymap.hint(&[Action::Search], "Filter"),
+                keymap.hint(&[Action::Save], "Save to file"),
+                keymap.hint(&[Action::Up, Action::Down], "Scroll")
+            )
+        } else {
+            "No events match the filter.".to_string()
+        };
+        f.render_widget(
+            Paragraph::new(text).wrap(ratatui::widgets::Wrap { trim: true }),
+            layout[1],
+        );
+        return;
+    }
+
+    let header = Row::new(vec![
+        "Time (UTC)",
+        "Severity",
+        "Event",
+        "Subject",
+        "Message",
+    ])
+    .style(Style::default().add_modifier(Modifier::BOLD))
+    .height(1);
+
+    let scroll = log.scroll.min(events.len().saturating_sub(1));
+    let rows: Vec<Row> = events
+        .iter()
+        .skip(scroll)
+        .map(|event| {
+            let time = chrono::DateTime::from_timestamp(event.time, 0)
+                .map_or_else(String::new, |t| t.format("%Y-%m-%d %H:%M:%S").to_string());
+            let style = match event.severity {
+                Severity::Critical => theme.critical,
+                Severity::Warning => theme.warning,
+                Severity::Info => Style::default(),
+            };
+            Row::new(vec![
+                time,
+                event.severity.label().to_string(),
+                event.kind.label().to_string(),
+                event.subject.clone().unwrap_or_default(),
+                event.message.clone(),
+            ])
+            .style(style)
+        })
+        .collect();
+
+    let table = Table::new(
+        rows,
+        [
+            Constraint::Length(19), // Time
+            Constraint::Length(8),  // Severity
+            Constraint::Length(17), // Event
+            Constraint::Length(8),  // Subject
+            Constraint::Min(0),     // Message
+        ],
+    )
+    .header(header);
+
+    f.render_widget(table, layout[1]);
+}
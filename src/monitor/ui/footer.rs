@@ -1,22 +1,58 @@
+use crate::monitor::keymap::{Action, Keymap};
 use ratatui::{
     layout::Rect,
     style::{Modifier, Style},
-    text::Text,
+    text::{Line, Text},
     widgets::{Block, BorderType, Borders, Paragraph, Wrap},
     Frame,
 };
 
-pub fn render_footer(f: &mut Frame, area: Rect) {
-    let block = Block::default()
-        .borders(Borders::ALL)
-        .border_type(BorderType::Rounded)
-        .title("Controls")
-        .title_style(Style::default().add_modifier(Modifier::BOLD));
+/// Controls of the single cluster views; `in_cluster_list` adds the way back
+/// to the summary. A notice, like where the screen was saved, goes on the right.
+pub fn render_footer(
+    f: &mut Frame,
+    area: Rect,
+    in_cluster_list: bool,
+    paused: bool,
+    notice: Option<&str>,
+    keymap: &Keymap,
+) {
+    let block = footer_block(notice);
 
-    let text = Paragraph::new(Text::from("Press 'q', 'Esc', or 'Ctrl+C' to exit"))
+    let mut hints = vec![keymap.hint(&[Action::Quit], "Quit")];
+    if in_cluster_list {
+        hints.push(keymap.hint(&[Action::Back], "Back"));
+    }
+    hints.extend([
+        keymap.hint(&[Action::Help], "Help"),
+        keymap.hint(&[Action::NextTab], "Tab"),
+        keymap.hint(&[Action::Up, Action::Down], "Select"),
+        keymap.hint(&[Action::SortNext, Action::SortReverse], "Sort"),
+        keymap.hint(&[Action::Pause], if paused { "Resume" } else { "Pause" }),
+        keymap.hint(&[Action::Refresh], "Refresh"),
+        keymap.hint(
+            &[Action::SlowerInterval, Action::FasterInterval],
+            "Interval",
+        ),
+    ]);
+    let text = hints.join(" • ");
+    let text = Paragraph::new(Text::from(text))
         .block(block)
         .style(Style::default())
         .wrap(Wrap { trim: true });
 
     f.render_widget(text, area);
 }
+
+/// The "Controls" box around the hints, with the notice if there is one
+pub fn footer_block(notice: Option<&str>) -> Block<'static> {
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_type(BorderType::Rounded)
+        .title("Controls")
+        .title_style(Style::default().add_modifier(Modifier::BOLD));
+    match notice {
+        Some(notice) => block.title(Line::from(format!(" {notice} ")).right_aligned()),
+        None => block,
+    }
+}
@@ -1,4 +1,6 @@
 use crate::common::CephPgDump;
+use crate::monitor::keymap::Action;
+use crate::monitor::state::MonitorState;
 use chrono::{DateTime, Utc};
 use ratatui::{
     layout::Rect,
@@ -8,14 +10,44 @@ use ratatui::{
     Frame,
 };
 
-pub fn render_header(f: &mut Frame, area: Rect, data: &CephPgDump, interval: u64) {
+/// Title with the cluster and the polling, the time of the data and the running fetch
+pub fn render_header(
+    f: &mut Frame,
+    area: Rect,
+    data: &CephPgDump,
+    interval: u64,
+    state: &MonitorState,
+) {
+    let paused = state.paused();
+    let keymap = state.keymap();
     let now = Utc::now();
-    let timestamp = DateTime::parse_from_rfc3339(&data.pg_map.stamp)
-        .unwrap_or_else(|_| now.into())
+    // Ceph writes the offset without a colon, e.g. +0000
+    let timestamp = DateTime::parse_from_str(&data.pg_map.stamp, "%Y-%m-%dT%H:%M:%S%.f%z")
+        .or_else(|_| DateTime::parse_from_rfc3339(&data.pg_map.stamp))
+        .map(|stamp| stamp.with_timezone(&Utc))
+        .unwrap_or(now)
         .format("%Y-%m-%d %H:%M:%S UTC");
 
-    let title = format!("CEPH DOCTOR - Cluster Monitor ({interval}s interval)");
-    let subtitle = format!("Last Update: {timestamp}");
+    let polling = if paused {
+        format!("PAUSED, {interval}s interval")
+    } else {
+        format!("{interval}s interval")
+    };
+    let title = match state.cluster_name() {
+        Some(name) => format!("CEPH DOCTOR - Cluster Monitor: {name} ({polling})"),
+        None => format!("CEPH DOCTOR - Cluster Monitor ({polling})"),
+    };
+    let mut subtitle = format!("Last Update: {timestamp}");
+    if paused {
+        subtitle.push_str(&format!(
+            " (stale) • {} • {}",
+            keymap.hint(&[Action::Pause], "Resume"),
+            keymap.hint(&[Action::Refresh], "Fetch once")
+        ));
+    }
+    if let Some(elapsed) = state.fetch_elapsed() {
+        subtitle.push_str(&format!(" • Fetching… {}s", elapsed.as_secs()));
+    }
 
     let header_block = Block::default()
         .borders(Borders::ALL)
@@ -0,0 +1,68 @@
+use crate::monitor::keymap::{Action, Keymap};
+use ratatui::{
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Modifier, Style},
+    text::Line,
+    widgets::{Block, BorderType, Borders, Clear, Paragraph, Row, Table},
+    Frame,
+};
+
+/// Every action with its keys in two columns, centered over the screen. With
+/// `mouse` captured, a line tells what the mouse does and how to select text.
+pub fn render_help(f: &mut Frame, keymap: &Keymap, mouse: bool) {
+    let area = f.area();
+    let half = Action::ALL.len().div_ceil(2);
+    // Two tables plus lines for the fixed keys of the filter fields and the mouse
+    let notes = if mouse { 2 } else { 1 };
+    let width = 104.min(area.width);
+    let height = (half as u16 + 3 + notes).min(area.height);
+    let popup_area = Rect::new(
+        area.x + (area.width - width) / 2,
+        area.y + (area.height - height) / 2,
+        width,
+        height,
+    );
+    f.render_widget(Clear, popup_area);
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_type(BorderType::Rounded)
+        .title(" Keys ")
+        .title_bottom(format!(
+            " {} ",
+            keymap.hint(&[Action::Help, Action::ClosePopup], "Close")
+        ))
+        .title_style(Style::default().add_modifier(Modifier::BOLD));
+    let inner = block.inner(popup_area);
+    f.render_widget(block, popup_area);
+
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(0), Constraint::Length(notes)])
+        .split(inner);
+    let columns = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+        .split(rows[0]);
+
+    for (actions, area) in Action::ALL.chunks(half).zip(columns.iter()) {
+        let rows = actions.iter().map(|action| {
+            Row::new(vec![
+                keymap.describe(*action),
+                action.description().to_string(),
+            ])
+        });
+        let table = Table::new(rows, [Constraint::Length(16), Constraint::Min(0)]);
+        f.render_widget(table, *area);
+    }
+
+    let mut lines = vec![Line::from(
+        "In a filter field: type the filter, [Enter] Apply, [Esc] Clear",
+    )];
+    if mouse {
+        lines.push(Line::from(
+            "Mouse: click tabs, rows and headers, wheel to scroll; Shift+drag selects text",
+        ));
+    }
+    f.render_widget(Paragraph::new(lines), rows[1]);
+}
@@ -1,13 +1,32 @@
+pub mod clusters;
 pub mod error;
+pub mod events;
 pub mod footer;
 pub mod header;
+pub mod help;
+pub mod network;
+pub mod osd_overview;
 pub mod osd_table;
 pub mod pg_table;
+pub mod pgs;
 pub mod recovery;
+pub mod tabs;
+pub mod theme;
 
+pub use clusters::*;
 pub use error::*;
+pub use events::*;
 pub use footer::*;
 pub use header::*;
+pub use help::*;
+pub use network::*;
+pub use osd_overview::*;
 pub use osd_table::*;
 pub use pg_table::*;
+pub use pgs::*;
 pub use recovery::*;
+pub use tabs::*;
+pub use theme::*;
+
+/// Marks the selected row of the tables
+pub const HIGHLIGHT_SYMBOL: &str = "» ";
@@ -0,0 +1,190 @@
+use crate::common::{HostLinkSummary, SlowLink};
+use crate::monitor::ui::Theme;
+use ratatui::{
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Modifier, Style},
+    widgets::{Block, BorderType, Borders, Cell, Paragraph, Row, Table, Wrap},
+    Frame,
+};
+
+pub fn render_network_view(
+    f: &mut Frame,
+    area: Rect,
+    slow_links: &[SlowLink],
+    host_summaries: &[HostLinkSummary],
+    threshold_ms: f64,
+    theme: &Theme,
+) {
+    if slow_links.is_empty() {
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .border_type(BorderType::Rounded)
+            .title("Network Heartbeat Latency")
+            .title_style(Style::default().add_modifier(Modifier::BOLD));
+        let text = Paragraph::new(format!(
+            "No OSD heartbeat links slower than {threshold_ms:.0}ms"
+        ))
+        .block(block)
+        .style(Style::default())
+        .wrap(Wrap { trim: true });
+        f.render_widget(text, area);
+        return;
+    }
+
+    let host_table_height = (host_summaries.len() + 3).clamp(4, 12) as u16;
+    let layout = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(host_table_height), // Host pairs
+            Constraint::Min(0),                    // Slowest links
+        ])
+        .split(area);
+
+    render_host_summary_table(f, layout[0], host_summaries, threshold_ms, theme);
+    render_slow_links_table(f, layout[1], slow_links, threshold_ms, theme);
+}
+
+fn render_host_summary_table(
+    f: &mut Frame,
+    area: Rect,
+    host_summaries: &[HostLinkSummary],
+    threshold_ms: f64,
+    theme: &Theme,
+) {
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_type(BorderType::Rounded)
+        .title(format!("Slow Host Pairs (> {threshold_ms:.0}ms)"))
+        .title_style(Style::default().add_modifier(Modifier::BOLD));
+
+    let header = Row::new([
+        Cell::from("From Host"),
+        Cell::from("To Host"),
+        Cell::from("Links"),
+        Cell::from("Worst"),
+        Cell::from("Average"),
+    ])
+    .style(Style::default().add_modifier(Modifier::BOLD))
+    .height(1);
+
+    let rows = host_summaries.iter().map(|summary| {
+        Row::new(vec![
+            Cell::from(summary.from_host.clone()),
+            Cell::from(summary.to_host.clone()),
+            Cell::from(format!("{:>5}", summary.slow_links)),
+            Cell::from(format_ping(summary.worst_ms)),
+            Cell::from(format_ping(summary.average_ms)),
+        ])
+        .style(latency_style(summary.worst_ms, threshold_ms, theme))
+    });
+
+    let table = Table::new(
+        rows,
+        [
+            Constraint::Length(20), // From Host
+            Constraint::Length(20), // To Host
+            Constraint::Length(7),  // Links
+            Constraint::Length(11), // Worst
+            Constraint::Length(11), // Average
+        ],
+    )
+    .header(header)
+    .block(block);
+
+    f.render_widget(table, area);
+}
+
+fn render_slow_links_table(
+    f: &mut Frame,
+    area: Rect,
+    slow_links: &[SlowLink],
+    threshold_ms: f64,
+    theme: &Theme,
+) {
+    // Only show as many links as fit: borders and header take 3 lines
+    let visible_rows = area.height.saturating_sub(3) as usize;
+    let title = if slow_links.len() > visible_rows {
+        format!(
+            "Slowest OSD Links (top {visible_rows} of {})",
+            slow_links.len()
+        )
+    } else {
+        format!("Slowest OSD Links ({})", slow_links.len())
+    };
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_type(BorderType::Rounded)
+        .title(title)
+        .title_style(Style::default().add_modifier(Modifier::BOLD));
+
+    let header = Row::new([
+        Cell::from("From"),
+        Cell::from("To"),
+        Cell::from("From Host"),
+        Cell::from("To Host"),
+        Cell::from("Iface"),
+        Cell::from("1min"),
+        Cell::from("5min"),
+        Cell::from("15min"),
+    ])
+    .style(Style::default().add_modifier(Modifier::BOLD))
+    .height(1);
+
+    let rows = slow_links.iter().take(visible_rows).map(|link| {
+        Row::new(vec![
+            Cell::from(format!("osd.{}", link.from_osd)),
+            Cell::from(format!("osd.{}", link.to_osd)),
+            Cell::from(link.from_host.clone().unwrap_or_default()),
+            Cell::from(link.to_host.clone().unwrap_or_default()),
+            Cell::from(link.interface.clone()),
+            Cell::from(format_ping(link.one_min_ms)).style(latency_style(
+                link.one_min_ms,
+                threshold_ms,
+                theme,
+            )),
+            Cell::from(format_ping(link.five_min_ms)).style(latency_style(
+                link.five_min_ms,
+                threshold_ms,
+                theme,
+            )),
+            Cell::from(format_ping(link.fifteen_min_ms)).style(latency_style(
+                link.fifteen_min_ms,
+                threshold_ms,
+                theme,
+            )),
+        ])
+    });
+
+    let table = Table::new(
+        rows,
+        [
+            Constraint::Length(9),  // From
+            Constraint::Length(9),  // To
+            Constraint::Length(20), // From Host
+            Constraint::Length(20), // To Host
+            Constraint::Length(6),  // Interface
+            Constraint::Length(11), // 1min
+            Constraint::Length(11), // 5min
+            Constraint::Length(11), // 15min
+        ],
+    )
+    .header(header)
+    .block(block);
+
+    f.render_widget(table, area);
+}
+
+fn latency_style(latency_ms: f64, threshold_ms: f64, theme: &Theme) -> Style {
+    if latency_ms <= threshold_ms {
+        Style::default()
+    } else if latency_ms > threshold_ms * 5.0 {
+        theme.critical
+    } else {
+        theme.warning
+    }
+}
+
+fn format_ping(latency_ms: f64) -> String {
+    format!("{latency_ms:>8.1}ms")
+}
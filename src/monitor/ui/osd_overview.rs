@@ -0,0 +1,150 @@
+use crate::common::{OsdOverview, OsdOverviewMedians};
+use crate::monitor::data::formatter::*;
+use crate::monitor::mouse::TableArea;
+use crate::monitor::state::{OsdOverviewSort, OsdOverviewSortColumn};
+use crate::monitor::ui::{Theme, HIGHLIGHT_SYMBOL};
+use ratatui::{
+    layout::{Constraint, Rect},
+    style::{Modifier, Style},
+    widgets::{Block, BorderType, Borders, Cell, Paragraph, Row, Table, TableState, Wrap},
+    Frame,
+};
+
+pub fn render_osd_overview_table(
+    f: &mut Frame,
+    area: Rect,
+    mut osd_overviews: Vec<OsdOverview>,
+    medians: &OsdOverviewMedians,
+    sort: OsdOverviewSort,
+    selected: Option<usize>,
+    theme: &Theme,
+) -> TableArea {
+    let direction = if sort.descending { "↓" } else { "↑" };
+    let title = format!(
+        "OSD Overview - sorted by {} {direction} - median use {:.1}%, {:.0} PGs, commit {:.0}ms, apply {:.0}ms",
+        sort.column.label(),
+        medians.utilization,
+        medians.num_pgs,
+        medians.commit_latency_ms,
+        medians.apply_latency_ms
+    );
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_type(BorderType::Rounded)
+        .title(title)
+        .title_style(Style::default().add_modifier(Modifier::BOLD));
+
+    if osd_overviews.is_empty() {
+        let text = Paragraph::new("No OSD statistics available")
+            .block(block)
+            .style(Style::default())
+            .wrap(Wrap { trim: true });
+        f.render_widget(text, area);
+        return TableArea::default();
+    }
+
+    sort_osd_overviews(&mut osd_overviews, sort);
+
+    let header = Row::new([
+        Cell::from("OSD"),
+        Cell::from("Use%"),
+        Cell::from("Used"),
+        Cell::from("Size"),
+        Cell::from("PGs"),
+        Cell::from("Commit"),
+        Cell::from("Apply"),
+        Cell::from("SnapTrimQ"),
+        Cell::from("Repaired"),
+        Cell::from("Alerts"),
+    ])
+    .style(Style::default().add_modifier(Modifier::BOLD))
+    .height(1);
+
+    let outlier_style = theme.warning.add_modifier(Modifier::BOLD);
+    let flag = |text: String, outlier: bool| {
+        if outlier {
+            Cell::from(format!("{text}!")).style(outlier_style)
+        } else {
+            Cell::from(text)
+        }
+    };
+
+    let rows = osd_overviews.into_iter().map(|osd| {
+        let alerts_cell = if osd.alerts.is_empty() {
+            Cell::from("")
+        } else {
+            Cell::from(osd.alerts.join(", ")).style(theme.critical)
+        };
+
+        Row::new(vec![
+            Cell::from(osd.osd_id.to_string()),
+            flag(format!("{:>5.1}", osd.utilization), osd.utilization_outlier),
+            Cell::from(format!("{:>7}", format_bytes(osd.used_bytes))),
+            Cell::from(format!("{:>7}", format_bytes(osd.total_bytes))),
+            flag(format!("{:>4}", osd.num_pgs), osd.pgs_outlier),
+            flag(
+                format!("{:>4.0}ms", osd.commit_latency_ms),
+                osd.commit_latency_outlier,
+            ),
+            flag(
+                format!("{:>4.0}ms", osd.apply_latency_ms),
+                osd.apply_latency_outlier,
+            ),
+            Cell::from(format!("{:>6}", osd.snap_trim_queue_len)),
+            Cell::from(format!("{:>6}", osd.num_shards_repaired)),
+            alerts_cell,
+        ])
+    });
+
+    let widths = [
+        Constraint::Length(5),  // OSD
+        Constraint::Length(7),  // Use%
+        Constraint::Length(8),  // Used
+        Constraint::Length(8),  // Size
+        Constraint::Length(6),  // PGs
+        Constraint::Length(8),  // Commit
+        Constraint::Length(8),  // Apply
+        Constraint::Length(10), // SnapTrimQ
+        Constraint::Length(9),  // Repaired
+        Constraint::Min(0),     // Alerts
+    ];
+    let inner = block.inner(area);
+    let table = Table::new(rows, widths)
+        .header(header)
+        .block(block)
+        .row_highlight_style(Style::default().add_modifier(Modifier::REVERSED))
+        .highlight_symbol(HIGHLIGHT_SYMBOL);
+
+    let mut table_state = TableState::default().with_selected(selected);
+    f.render_stateful_widget(table, area, &mut table_state);
+    TableArea::new(inner, &widths, 1, HIGHLIGHT_SYMBOL, &table_state)
+}
+
+fn sort_osd_overviews(osd_overviews: &mut [OsdOverview], sort: OsdOverviewSort) {
+    osd_overviews.sort_by(|a, b| {
+        let ordering = match sort.column {
+            OsdOverviewSortColumn::Osd => a.osd_id.cmp(&b.osd_id),
+            OsdOverviewSortColumn::Utilization => a.utilization.total_cmp(&b.utilization),
+            OsdOverviewSortColumn::Pgs => a.num_pgs.cmp(&b.num_pgs),
+            OsdOverviewSortColumn::CommitLatency => {
+                a.commit_latency_ms.total_cmp(&b.commit_latency_ms)
+            }
+            OsdOverviewSortColumn::ApplyLatency => {
+                a.apply_latency_ms.total_cmp(&b.apply_latency_ms)
+            }
+            OsdOverviewSortColumn::SnapTrimQueue => {
+                a.snap_trim_queue_len.cmp(&b.snap_trim_queue_len)
+            }
+            OsdOverviewSortColumn::Repaired => a.num_shards_repaired.cmp(&b.num_shards_repaired),
+            OsdOverviewSortColumn::Alerts => a.alerts.len().cmp(&b.alerts.len()),
+        };
+        let ordering = if sort.descending {
+            ordering.reverse()
+        } else {
+            ordering
+        };
+        // Keep a stable order by OSD ID for equal values
+        ordering.then_with(|| a.osd_id.cmp(&b.osd_id))
+    });
+}
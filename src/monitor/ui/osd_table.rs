@@ -1,10 +1,16 @@
-use crate::common::OsdDataMovement;
 use crate::monitor::data::formatter::*;
+use crate::monitor::keymap::{Action, Keymap};
+use crate::monitor::mouse::TableArea;
+use crate::monitor::state::{OsdMovementColumn, OsdMovementRow, OsdMovementTable};
+use crate::monitor::ui::{Theme, HIGHLIGHT_SYMBOL};
 use ratatui::{
-    layout::{Constraint, Rect},
+    layout::{Constraint, Margin, Rect},
     style::{Modifier, Style},
     text::{Line, Text},
-    widgets::{Block, BorderType, Borders, Cell, Row, Table},
+    widgets::{
+        Block, BorderType, Borders, Cell, Clear, List, ListItem, ListState, Row, Scrollbar,
+        ScrollbarOrientation, ScrollbarState, Table, TableState,
+    },
     Frame,
 };
 use std::collections::HashMap;
@@ -12,146 +18,188 @@ use std::collections::HashMap;
 pub fn render_osd_data_movement_table(
     f: &mut Frame,
     area: Rect,
-    osd_movements: HashMap<u32, OsdDataMovement>,
-    _use_colors: bool,
-) {
+    rows: &[OsdMovementRow],
+    table: &OsdMovementTable,
+    selected: Option<usize>,
+    theme: &Theme,
+) -> TableArea {
+    let direction = if table.sort.descending { "↓" } else { "↑" };
+    let widths: Vec<Constraint> = table
+        .columns
+        .iter()
+        .map(|column| match column {
+            OsdMovementColumn::Osd => Constraint::Length(5),
+            OsdMovementColumn::Host => Constraint::Length(14),
+            OsdMovementColumn::Utilization => Constraint::Length(6),
+            OsdMovementColumn::MissingEta | OsdMovementColumn::ExcessEta => Constraint::Length(11),
+            OsdMovementColumn::MissingBytes | OsdMovementColumn::ExcessBytes => {
+                Constraint::Length(9)
+            }
+            _ => Constraint::Length(8),
+        })
+        .collect();
     let block = Block::default()
         .borders(Borders::ALL)
         .border_type(BorderType::Rounded)
-        .title("OSD Data Movement")
+        .title(format!(
+            "OSD Data Movement - sorted by {} {direction}",
+            table.sort.column.label()
+        ))
         .title_style(Style::default().add_modifier(Modifier::BOLD));
+    let inner = block.inner(area);
 
-    // Filter to only show OSDs with actual data movement and sort by missing objects (descending)
-    let mut sorted_osds: Vec<u32> = osd_movements
-        .keys()
-        .filter(|&&osd_id| {
-            let movement = osd_movements.get(&osd_id).unwrap();
-            movement.missing_objects > 0 || movement.excess_objects > 0
-        })
-        .cloned()
-        .collect();
-    sorted_osds.sort_by(|&a, &b| {
-        let movement_a = osd_movements.get(&a).unwrap();
-        let movement_b = osd_movements.get(&b).unwrap();
-        // Sort by total missing objects (active + waiting), then by total excess objects, then by OSD ID
-        let total_missing_a =
-            movement_a.missing_objects_active + movement_a.missing_objects_waiting;
-        let total_missing_b =
-            movement_b.missing_objects_active + movement_b.missing_objects_waiting;
-        let total_excess_a = movement_a.excess_objects_active + movement_a.excess_objects_waiting;
-        let total_excess_b = movement_b.excess_objects_active + movement_b.excess_objects_waiting;
-
-        total_missing_b
-            .cmp(&total_missing_a)
-            .then_with(|| total_excess_b.cmp(&total_excess_a))
-            .then_with(|| a.cmp(&b))
+    let header = Row::new(table.columns.iter().map(|column| {
+        let (first, second) = column.label();
+        Cell::from(Text::from(vec![Line::from(first), Line::from(second)]))
+            .style(Style::default().add_modifier(Modifier::BOLD))
+    }))
+    .height(2);
+
+    let body = rows.iter().map(|row| {
+        Row::new(
+            table
+                .columns
+                .iter()
+                .map(|column| osd_movement_cell(row, *column, theme)),
+        )
     });
 
-    let header = Row::new([
-        Cell::from(Text::from(vec![Line::from("OSD")]))
-            .style(Style::default().add_modifier(Modifier::BOLD)),
-        Cell::from(Text::from(vec![
-            Line::from("Missing"),
-            Line::from("Waiting"),
-        ]))
-        .style(Style::default().add_modifier(Modifier::BOLD)),
-        Cell::from(Text::from(vec![
-            Line::from("Missing"),
-            Line::from("Active"),
-        ]))
-        .style(Style::default().add_modifier(Modifier::BOLD)),
-        Cell::from(Text::from(vec![
-            Line::from("Missing"),
-            Line::from("Rate/s"),
-        ]))
-        .style(Style::default().add_modifier(Modifier::BOLD)),
-        Cell::from(Text::from(vec![Line::from("Missing"), Line::from("ETA")]))
-            .style(Style::default().add_modifier(Modifier::BOLD)),
-        Cell::from(Text::from(vec![
-            Line::from("Excess"),
-            Line::from("Waiting"),
-        ]))
-        .style(Style::default().add_modifier(Modifier::BOLD)),
-        Cell::from(Text::from(vec![Line::from("Excess"), Line::from("Active")]))
-            .style(Style::default().add_modifier(Modifier::BOLD)),
-        Cell::from(Text::from(vec![Line::from("Excess"), Line::from("Rate/s")]))
-            .style(Style::default().add_modifier(Modifier::BOLD)),
-        Cell::from(Text::from(vec![Line::from("Excess"), Line::from("ETA")]))
-            .style(Style::default().add_modifier(Modifier::BOLD)),
-    ])
-    .height(2);
+    let table = Table::new(body, widths.iter())
+        .header(header)
+        .block(block)
+        .row_highlight_style(Style::default().add_modifier(Modifier::REVERSED))
+        .highlight_symbol(HIGHLIGHT_SYMBOL);
 
-    let rows = sorted_osds.into_iter().map(|osd_id| {
-        let movement = osd_movements.get(&osd_id).unwrap();
-        let missing_active = movement.missing_objects_active;
-        let missing_waiting = movement.missing_objects_waiting;
-        let excess_active = movement.excess_objects_active;
-        let excess_waiting = movement.excess_objects_waiting;
-        let time_in = movement
-            .incoming_predicted_time_secs
-            .map_or("N/A".to_string(), format_time);
-        let time_out = movement
-            .outgoing_predicted_time_secs
-            .map_or("N/A".to_string(), format_time);
-        let rate_in = movement
-            .incoming_rate
-            .map_or("N/A".to_string(), |r| format!("{r:.1}"));
-        let rate_out = movement
-            .outgoing_rate
-            .map_or("N/A".to_string(), |r| format!("{r:.1}"));
+    let mut table_state = TableState::default().with_selected(selected);
+    f.render_stateful_widget(table, area, &mut table_state);
+    let table_area = TableArea::new(inner, &widths, 2, HIGHLIGHT_SYMBOL, &table_state);
 
-        let cells = vec![
-            Cell::from(osd_id.to_string()),
-            Cell::from(format!("{:>6}", format_number(missing_waiting))),
-            if missing_active > 0 {
-                Cell::from(format!("{:>6}", format_number(missing_active)))
-                    .style(Style::default().add_modifier(Modifier::BOLD))
-            } else {
-                Cell::from(format!("{:>6}", format_number(missing_active)))
-            },
-            Cell::from(format!("{rate_in:>7}")),
-            Cell::from(format!("{time_in:>9}")),
-            Cell::from(format!("{:>6}", format_number(excess_waiting))),
-            if excess_active > 0 {
-                Cell::from(format!("{:>6}", format_number(excess_active)))
-                    .style(Style::default().add_modifier(Modifier::BOLD))
+    // The rows below the screen, on the bottom border
+    let visible = table_area.rows.height as usize;
+    let offset = table_area.offset;
+    let more = rows.len().saturating_sub(offset + visible);
+    if more > 0 {
+        let rows_word = if more == 1 { "row" } else { "rows" };
+        let border = Block::new()
+            .title_bottom(Line::from(format!(" ▼ {more} more {rows_word} ")).right_aligned());
+        f.render_widget(border, area.inner(Margin::new(1, 0)));
+    }
+
+    if rows.len() > visible {
+        let scrollbar = Scrollbar::new(ScrollbarOrientation::VerticalRight)
+            .begin_symbol(None)
+            .end_symbol(None);
+        let mut scrollbar_state = ScrollbarState::new(rows.len() - visible).position(offset);
+        // On the right border, along the rows below the header
+        let track = Rect {
+            y: area.y + 3,
+            height: visible as u16,
+            ..area
+        };
+        f.render_stateful_widget(scrollbar, track, &mut scrollbar_state);
+    }
+    table_area
+}
+
+fn osd_movement_cell(
+    row: &OsdMovementRow,
+    column: OsdMovementColumn,
+    theme: &Theme,
+) -> Cell<'static> {
+    let movement = &row.movement;
+    let count = |objects: i64| Cell::from(format!("{:>6}", format_number(objects)));
+    // Objects that are being moved right now stand out
+    let active = |objects: i64| {
+        if objects > 0 {
+            count(objects).style(theme.active)
+        } else {
+            count(objects)
+        }
+    };
+    let rate = |rate: Option<f64>| {
+        let rate = rate.map_or("N/A".to_string(), |r| format!("{r:.1}"));
+        Cell::from(format!("{rate:>7}"))
+    };
+    let eta = |secs: Option<u64>| {
+        let eta = secs.map_or("N/A".to_string(), format_time);
+        Cell::from(format!("{eta:>9}"))
+    };
+    let bytes = |bytes: i64| Cell::from(format!("{:>8}", format_bytes(bytes.max(0) as u64)));
+
+    match column {
+        OsdMovementColumn::Osd => Cell::from(movement.osd_id.to_string()),
+        OsdMovementColumn::Host => Cell::from(row.host.as_deref().unwrap_or("?").to_string()),
+        OsdMovementColumn::Utilization => Cell::from(
+            row.utilization
+                .map_or("N/A".to_string(), |u| format!("{u:>5.1}")),
+        ),
+        OsdMovementColumn::MissingWaiting => count(movement.missing_objects_waiting),
+        OsdMovementColumn::MissingActive => active(movement.missing_objects_active),
+        OsdMovementColumn::MissingRate => rate(movement.incoming_rate),
+        OsdMovementColumn::MissingEta => eta(movement.incoming_predicted_time_secs),
+        OsdMovementColumn::MissingBytes => bytes(movement.missing_bytes),
+        OsdMovementColumn::ExcessWaiting => count(movement.excess_objects_waiting),
+        OsdMovementColumn::ExcessActive => active(movement.excess_objects_active),
+        OsdMovementColumn::ExcessRate => rate(movement.outgoing_rate),
+        OsdMovementColumn::ExcessEta => eta(movement.outgoing_predicted_time_secs),
+        OsdMovementColumn::ExcessBytes => bytes(movement.excess_bytes),
+    }
+}
+
+/// Popup to show or hide the columns of the OSD data movement table
+pub fn render_column_chooser(f: &mut Frame, table: &OsdMovementTable, keymap: &Keymap) {
+    let choosable = OsdMovementTable::choosable();
+    let area = f.area();
+    let width = 36.min(area.width);
+    let height = (choosable.len() as u16 + 2).min(area.height);
+    let popup_area = Rect::new(
+        (area.width - width) / 2,
+        (area.height - height) / 2,
+        width,
+        height,
+    );
+    f.render_widget(Clear, popup_area);
+
+    let items: Vec<ListItem> = choosable
+        .iter()
+        .map(|column| {
+            let mark = if table.columns.contains(column) {
+                "[x]"
             } else {
-                Cell::from(format!("{:>6}", format_number(excess_active)))
-            },
-            Cell::from(format!("{rate_out:>7}")),
-            Cell::from(format!("{time_out:>9}")),
-        ];
-        Row::new(cells)
-    });
+                "[ ]"
+            };
+            let (first, second) = column.label();
+            ListItem::new(format!("{mark} {first} {second}"))
+        })
+        .collect();
 
-    let table = Table::new(
-        rows,
-        [
-            Constraint::Length(5),  // OSD
-            Constraint::Length(8),  // Missing Waiting
-            Constraint::Length(8),  // Missing Active
-            Constraint::Length(8),  // Missing Rate/s
-            Constraint::Length(11), // Missing ETA
-            Constraint::Length(8),  // Excess Waiting
-            Constraint::Length(8),  // Excess Active
-            Constraint::Length(8),  // Excess Rate/s
-            Constraint::Length(11), // Excess ETA
-        ],
-    )
-    .header(header)
-    .block(block)
-    .row_highlight_style(Style::default().add_modifier(Modifier::REVERSED))
-    .highlight_symbol("» ");
-
-    f.render_widget(table, area);
+    let list = List::new(items)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_type(BorderType::Rounded)
+                .title(" Columns ")
+                .title_bottom(format!(
+                    " {} • {} ",
+                    keymap.hint(&[Action::Toggle], "Toggle"),
+                    keymap.hint(&[Action::Back, Action::ColumnChooser], "Close")
+                ))
+                .title_style(Style::default().add_modifier(Modifier::BOLD)),
+        )
+        .highlight_style(Style::default().add_modifier(Modifier::REVERSED))
+        .highlight_symbol("» ");
+
+    let mut list_state = ListState::default().with_selected(table.chooser);
+    f.render_stateful_widget(list, popup_area, &mut list_state);
 }
 
 pub fn render_inconsistent_pgs_table(
     f: &mut Frame,
     area: Rect,
-    inconsistent_pgs: HashMap<String, crate::common::InconsistentPgProgress>,
-    use_colors: bool,
-) {
+    inconsistent_pgs: &HashMap<String, crate::common::InconsistentPgProgress>,
+    selected: Option<usize>,
+    theme: &Theme,
+) -> TableArea {
     let block = Block::default()
         .borders(Borders::ALL)
         .border_type(BorderType::Rounded)
@@ -164,7 +212,7 @@ pub fn render_inconsistent_pgs_table(
             .style(Style::default())
             .wrap(ratatui::widgets::Wrap { trim: true });
         f.render_widget(text, area);
-        return;
+        return TableArea::default();
     }
 
     // Sort PGs by PGID for consistent display
@@ -193,14 +241,10 @@ pub fn render_inconsistent_pgs_table(
             "0.0%".to_string()
         };
 
-        let row_style = if use_colors {
-            if pg.state.contains("repair") {
-                Style::default().fg(ratatui::style::Color::Red)
-            } else {
-                Style::default().fg(ratatui::style::Color::Yellow)
-            }
+        let row_style = if pg.state.contains("repair") {
+            theme.critical
         } else {
-            Style::default()
+            theme.warning
         };
 
         // Create OSD list with primary in bold
@@ -233,19 +277,22 @@ pub fn render_inconsistent_pgs_table(
         .style(row_style)
     });
 
-    let table = Table::new(
-        rows,
-        [
-            Constraint::Length(8),  // PG ID
-            Constraint::Length(8),  // Objects
-            Constraint::Length(12), // Primary OSD
-            Constraint::Length(12), // Scrubbed
-            Constraint::Length(10), // Rate/sec
-            Constraint::Length(12), // ETA
-        ],
-    )
-    .header(header)
-    .block(block);
-
-    f.render_widget(table, area);
+    let widths = [
+        Constraint::Length(8),  // PG ID
+        Constraint::Length(8),  // Objects
+        Constraint::Length(12), // Primary OSD
+        Constraint::Length(12), // Scrubbed
+        Constraint::Length(10), // Rate/sec
+        Constraint::Length(12), // ETA
+    ];
+    let inner = block.inner(area);
+    let table = Table::new(rows, widths)
+        .header(header)
+        .block(block)
+        .row_highlight_style(Style::default().add_modifier(Modifier::REVERSED))
+        .highlight_symbol(HIGHLIGHT_SYMBOL);
+
+    let mut table_state = TableState::default().with_selected(selected);
+    f.render_stateful_widget(table, area, &mut table_state);
+    TableArea::new(inner, &widths, 1, HIGHLIGHT_SYMBOL, &table_state)
 }
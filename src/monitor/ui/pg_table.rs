@@ -1,72 +1,61 @@
-use crate::common::CephPgDump;
+use crate::monitor::mouse::TableArea;
+use crate::monitor::ui::{Theme, HIGHLIGHT_SYMBOL};
 use ratatui::{
-    layout::Rect,
-    style::{Color, Modifier, Style},
-    widgets::{Block, BorderType, Borders, Row, Table},
+    layout::{Constraint, Rect},
+    style::{Modifier, Style},
+    widgets::{Block, BorderType, Borders, Row, Table, TableState},
     Frame,
 };
-use std::collections::HashMap;
 
-pub fn render_pg_states(f: &mut Frame, area: Rect, data: &CephPgDump, use_colors: bool) {
+/// The PG states, most common first. `limit` caps the rows for the overview;
+/// the PG states tab shows them all.
+pub fn render_pg_states(
+    f: &mut Frame,
+    area: Rect,
+    pg_states: &[(String, usize)],
+    limit: Option<usize>,
+    selected: Option<usize>,
+    theme: &Theme,
+) -> TableArea {
     let block = Block::default()
         .borders(Borders::ALL)
         .border_type(BorderType::Rounded)
         .title("Placement Group States")
         .title_style(Style::default().add_modifier(Modifier::BOLD));
 
-    let pg_states = count_pg_states(&data.pg_map.pg_stats);
-    let mut sorted_states: Vec<_> = pg_states.iter().collect();
-    sorted_states.sort_by(|a, b| b.1.cmp(a.1));
-
-    let rows: Vec<Row> = sorted_states
+    let rows: Vec<Row> = pg_states
         .iter()
-        .take(10) // Show top 10 states
+        .take(limit.unwrap_or(usize::MAX))
         .map(|(state, count)| {
-            let color = if use_colors {
-                match state.as_str() {
-                    s if s.contains("inconsistent")
-                        || s.contains("incomplete")
-                        || s.contains("down") =>
-                    {
-                        Color::Red
-                    }
-                    "active+clean" => Color::Green,
-                    s if s.contains("backfilling")
-                        || s.contains("recovery")
-                        || s.contains("remapped") =>
-                    {
-                        Color::Yellow
-                    }
-                    _ => Color::Reset,
-                }
-            } else {
-                Color::Reset
-            };
-
-            Row::new(vec![count.to_string(), state.to_string()]).style(Style::default().fg(color))
+            Row::new(vec![count.to_string(), state.to_string()]).style(pg_state_style(state, theme))
         })
         .collect();
 
-    let table = Table::new(
-        rows,
-        [
-            ratatui::layout::Constraint::Length(8),
-            ratatui::layout::Constraint::Min(0),
-        ],
-    )
-    .block(block)
-    .header(Row::new(vec!["Count", "State"]).style(Style::default().add_modifier(Modifier::BOLD)));
-
-    f.render_widget(table, area);
+    let widths = [Constraint::Length(8), Constraint::Min(0)];
+    let inner = block.inner(area);
+    let table = Table::new(rows, widths)
+        .block(block)
+        .header(
+            Row::new(vec!["Count", "State"]).style(Style::default().add_modifier(Modifier::BOLD)),
+        )
+        .row_highlight_style(Style::default().add_modifier(Modifier::REVERSED))
+        .highlight_symbol(HIGHLIGHT_SYMBOL);
+
+    let mut table_state = TableState::default().with_selected(selected);
+    f.render_stateful_widget(table, area, &mut table_state);
+    TableArea::new(inner, &widths, 1, HIGHLIGHT_SYMBOL, &table_state)
 }
 
-fn count_pg_states(pg_stats: &[crate::common::PgStats]) -> HashMap<String, usize> {
-    let mut state_counts = HashMap::new();
-
-    for pg in pg_stats {
-        let state = pg.state.clone();
-        *state_counts.entry(state).or_insert(0) += 1;
+/// Critical for PGs that need attention, a warning while they move, good when clean
+pub fn pg_state_style(state: &str, theme: &Theme) -> Style {
+    match state {
+        s if s.contains("inconsistent") || s.contains("incomplete") || s.contains("down") => {
+            theme.critical
+        }
+        "active+clean" => theme.good,
+        s if s.contains("backfilling") || s.contains("recovery") || s.contains("remapped") => {
+            theme.warning
+        }
+        _ => Style::default(),
     }
-
-    state_counts
 }
@@ -0,0 +1,151 @@
+use crate::monitor::data::formatter::*;
+use crate::monitor::keymap::{Action, Keymap};
+use crate::monitor::mouse::TableArea;
+use crate::monitor::state::PgBrowser;
+use crate::monitor::ui::{pg_state_style, Theme, HIGHLIGHT_SYMBOL};
+use ratatui::{
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Modifier, Style},
+    widgets::{Block, BorderType, Borders, Paragraph, Row, Table, TableState, Wrap},
+    Frame,
+};
+
+pub fn render_pg_list(
+    f: &mut Frame,
+    area: Rect,
+    browser: &PgBrowser,
+    keymap: &Keymap,
+    selected: Option<usize>,
+    theme: &Theme,
+) -> TableArea {
+    let pgs = browser.filtered();
+    let direction = if browser.sort().descending {
+        "↓"
+    } else {
+        "↑"
+    };
+    let count = if pgs.len() == browser.pgs().len() {
+        pgs.len().to_string()
+    } else {
+        format!("{} of {}", pgs.len(), browser.pgs().len())
+    };
+    let title = format!(
+        "PGs ({count}) - sorted by {} {direction}",
+        browser.sort().column.label()
+    );
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_type(BorderType::Rounded)
+        .title(title)
+        .title_style(Style::default().add_modifier(Modifier::BOLD));
+    let inner = block.inner(area);
+    f.render_widget(block, area);
+
+    let layout = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(1), // Filter
+            Constraint::Min(0),    // PGs
+        ])
+        .split(inner);
+
+    let filter_error = browser.filter_error();
+    let filter_line = if browser.editing {
+        format!("Filter: {}_  [Enter] Apply [Esc] Clear", browser.filter())
+    } else if browser.filter().is_empty() {
+        format!(
+            "{}, e.g. state:backfill_wait osd:12 pool:3 • {}",
+            keymap.hint(&[Action::Search], "Filter"),
+            keymap.hint(
+                &[Action::SortNext, Action::SortReverse],
+                "Sort column/direction"
+            )
+        )
+    } else {
+        format!(
+            "Filter: {}  {}",
+            browser.filter(),
+            keymap.hint(&[Action::Search], "Edit")
+        )
+    };
+    let filter_line = match &filter_error {
+        Some(error) => format!("{filter_line}  ({error})"),
+        None => filter_line,
+    };
+    let filter_style = if filter_error.is_some() {
+        theme.critical
+    } else if browser.editing {
+        Style::default().add_modifier(Modifier::BOLD)
+    } else {
+        Style::default()
+    };
+    f.render_widget(Paragraph::new(filter_line).style(filter_style), layout[0]);
+
+    if pgs.len() == 0 {
+        let text = if browser.pgs().is_empty() {
+            "No PG statistics available"
+        } else {
+            "No PGs match the filter."
+        };
+        f.render_widget(Paragraph::new(text).wrap(Wrap { trim: true }), layout[1]);
+        return TableArea::default();
+    }
+
+    let header = Row::new(vec![
+        "PG ID",
+        "State",
+        "Up",
+        "Acting",
+        "Primary",
+        "Objects",
+        "Misplaced",
+        "Degraded",
+        "In State",
+    ])
+    .style(Style::default().add_modifier(Modifier::BOLD))
+    .height(1);
+
+    let osd_set = |osds: &[u32]| {
+        let osds: Vec<String> = osds.iter().map(u32::to_string).collect();
+        format!("[{}]", osds.join(","))
+    };
+
+    let rows = pgs.into_iter().map(|pg| {
+        Row::new(vec![
+            pg.pgid.clone(),
+            pg.state.clone(),
+            osd_set(&pg.up),
+            osd_set(&pg.acting),
+            format!("{:>7}", pg.primary),
+            format!("{:>7}", format_number(pg.objects)),
+            format!("{:>9}", format_number(pg.misplaced)),
+            format!("{:>8}", format_number(pg.degraded)),
+            format!(
+                "{:>9}",
+                pg.state_secs.map_or("N/A".to_string(), format_time)
+            ),
+        ])
+        .style(pg_state_style(&pg.state, theme))
+    });
+
+    let widths = [
+        Constraint::Length(8),  // PG ID
+        Constraint::Min(30),    // State
+        Constraint::Length(12), // Up
+        Constraint::Length(12), // Acting
+        Constraint::Length(8),  // Primary
+        Constraint::Length(8),  // Objects
+        Constraint::Length(10), // Misplaced
+        Constraint::Length(9),  // Degraded
+        Constraint::Length(10), // In State
+    ];
+    let table = Table::new(rows, widths)
+        .header(header)
+        .row_highlight_style(Style::default().add_modifier(Modifier::REVERSED))
+        .highlight_symbol(HIGHLIGHT_SYMBOL);
+
+    let mut table_state = TableState::default().with_selected(selected);
+    f.render_stateful_widget(table, layout[1], &mut table_state);
+    TableArea::new(layout[1], &widths, 1, HIGHLIGHT_SYMBOL, &table_state)
+}
@@ -1,6 +1,5 @@
-use crate::common::CephPgDump;
+use crate::common::RecoveryProgress;
 use crate::monitor::data::formatter::*;
-use crate::monitor::state::{MonitorState, RecoveryData};
 use ratatui::{
     layout::{Constraint, Rect},
     style::{Modifier, Style},
@@ -8,46 +7,15 @@ use ratatui::{
     Frame,
 };
 
-pub fn render_recovery_progress(
-    f: &mut Frame,
-    area: Rect,
-    data: &CephPgDump,
-    state: &mut MonitorState,
-    interval: u64,
-) {
-    const HISTORY_SIZE: usize = 20;
-
+pub fn render_recovery_progress(f: &mut Frame, area: Rect, progress: &[RecoveryProgress]) {
     let block = Block::default()
         .borders(Borders::ALL)
         .border_type(BorderType::Rounded)
         .title("Recovery Progress")
         .title_style(Style::default().add_modifier(Modifier::BOLD));
 
-    let stats_sum = &data.pg_map.pg_stats_sum.stat_sum;
-
-    // Calculate average object size for estimation
-    let avg_object_size = if stats_sum.num_objects > 0 {
-        stats_sum.num_bytes as f64 / stats_sum.num_objects as f64
-    } else {
-        0.0
-    };
-
-    // Define recovery categories with current values
-    let categories = vec![
-        ("Missing", stats_sum.num_objects_missing),
-        ("Unfound", stats_sum.num_objects_unfound),
-        ("Misplaced", stats_sum.num_objects_misplaced),
-        ("Degraded", stats_sum.num_objects_degraded),
-    ];
-
-    // Filter categories with values > 0
-    let active_categories: Vec<_> = categories
-        .into_iter()
-        .filter(|(_, value)| *value > 0)
-        .collect();
-
     // If no active categories, show a message
-    if active_categories.is_empty() {
+    if progress.is_empty() {
         let text = Paragraph::new("No recovery operations in progress")
             .block(block)
             .style(Style::default())
@@ -56,75 +24,36 @@ pub fn render_recovery_progress(
         return;
     }
 
-    // Calculate rates for each category
-    let mut rows = Vec::new();
-
-    for (category, current_objects) in active_categories {
-        // Calculate estimated bytes for this category
-        let estimated_bytes = (current_objects as f64 * avg_object_size) as i64;
-
-        // Add current data to history
-        let current_data = RecoveryData {
-            objects: current_objects,
-            bytes: estimated_bytes,
-        };
-        state.add_recovery_data(category, current_data, HISTORY_SIZE);
+    let rows: Vec<Row> = progress
+        .iter()
+        .map(|row| {
+            let eta = row
+                .eta_seconds
+                .map_or_else(|| "calculating...".to_string(), format_time);
 
-        // Calculate rates (objects per second and bytes per second)
-        let (object_rate, data_rate) = if let Some(history) = state.get_recovery_history(category) {
-            if history.len() >= 2 {
-                let oldest_data = &history[0];
-                let time_elapsed = (history.len() - 1) as f64 * interval as f64;
-
-                if time_elapsed > 0.0 {
-                    let object_change = current_objects - oldest_data.objects;
-                    let byte_change = estimated_bytes - oldest_data.bytes;
-                    (
-                        object_change as f64 / time_elapsed,
-                        byte_change as f64 / time_elapsed,
-                    )
-                } else {
-                    (0.0, 0.0)
-                }
+            // Format rate displays (show positive values for recovery progress)
+            let object_rate_display = if row.object_rate.abs() < 0.01 {
+                "0.0/s".to_string()
             } else {
-                (0.0, 0.0)
-            }
-        } else {
-            (0.0, 0.0)
-        };
-
-        // Calculate ETA
-        let eta = if object_rate < 0.0 && current_objects > 0 {
-            let remaining_time = (current_objects as f64 / -object_rate) as u64;
-            format_time(remaining_time)
-        } else if current_objects > 0 {
-            "calculating...".to_string()
-        } else {
-            "complete".to_string()
-        };
-
-        // Format rate displays (show positive values for recovery progress)
-        let object_rate_display = if object_rate.abs() < 0.01 {
-            "0.0/s".to_string()
-        } else {
-            let rate = object_rate.abs();
-            format!("{rate:.1}/s")
-        };
-
-        let data_rate_display = if data_rate.abs() < 1024.0 {
-            "0B/s".to_string()
-        } else {
-            format_bytes_per_second(data_rate.abs())
-        };
+                let rate = row.object_rate.abs();
+                format!("{rate:.1}/s")
+            };
 
-        rows.push(Row::new(vec![
-            category.to_string(),
-            format_number(current_objects),
-            object_rate_display,
-            data_rate_display,
-            eta,
-        ]));
-    }
+            let data_rate_display = if row.data_rate.abs() < 1024.0 {
+                "0B/s".to_string()
+            } else {
+                format_bytes_per_second(row.data_rate.abs())
+            };
+
+            Row::new(vec![
+                row.category.to_string(),
+                format_number(row.objects),
+                object_rate_display,
+                data_rate_display,
+                eta,
+            ])
+        })
+        .collect();
 
     let header = Row::new(vec!["Category", "Count", "Obj/s", "Data/s", "ETA"])
         .style(Style::default().add_modifier(Modifier::BOLD))
@@ -0,0 +1,36 @@
+use crate::monitor::keymap::{Action, Keymap};
+use crate::monitor::state::View;
+use crate::monitor::ui::Theme;
+use ratatui::{layout::Rect, text::Line, widgets::Tabs, Frame};
+
+/// One line with the tabs and their keys, the active one highlighted.
+/// Returns where each tab was drawn, with its padding.
+pub fn render_tab_bar(
+    f: &mut Frame,
+    area: Rect,
+    active: View,
+    keymap: &Keymap,
+    theme: &Theme,
+) -> Vec<(Rect, View)> {
+    let titles: Vec<String> = View::ALL
+        .iter()
+        .map(|view| format!("{} {}", keymap.key(Action::for_view(*view)), view.title()))
+        .collect();
+
+    // A space on either side of each title and a divider between them
+    let mut x = area.x;
+    let mut areas = Vec::new();
+    for (title, view) in titles.iter().zip(View::ALL) {
+        let width = (Line::from(title.as_str()).width() as u16 + 2).min(area.right() - x);
+        areas.push((Rect::new(x, area.y, width, area.height.min(1)), view));
+        x = (x + width + 1).min(area.right());
+    }
+
+    let tabs = Tabs::new(titles)
+        .select(active.index())
+        .highlight_style(theme.tab)
+        .divider("│");
+
+    f.render_widget(tabs, area);
+    areas
+}
@@ -0,0 +1,120 @@
+use ratatui::style::{Color, Modifier, Style};
+use serde::{Deserialize, Serialize};
+
+/// The colour themes, picked with `--theme` or `theme` in the config file
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize, clap::ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum ThemeName {
+    /// The classic green, yellow and red on a dark background
+    #[default]
+    Dark,
+    /// Darker shades that stay readable on a light background
+    Light,
+    /// Bold, bright colours; critical rows are inverted
+    HighContrast,
+    /// Blue, orange and vermilion, told apart with any kind of colour blindness
+    ColorBlind,
+}
+
+/// The styles of the monitor by meaning, so every view colours the same things alike
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Theme {
+    /// Healthy: active+clean PGs, HEALTH_OK
+    pub good: Style,
+    /// Needs attention: PGs waiting for recovery, HEALTH_WARN, outliers, slow links
+    pub warning: Style,
+    /// Broken: down or inconsistent PGs, HEALTH_ERR, errors, repairs
+    pub critical: Style,
+    /// Being worked on right now, like objects that are moving
+    pub active: Style,
+    /// The selected tab
+    pub tab: Style,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Theme::new(ThemeName::default(), true)
+    }
+}
+
+impl Theme {
+    /// The theme, or the plain one without colours when colours are off
+    pub fn new(name: ThemeName, use_colors: bool) -> Theme {
+        if !use_colors {
+            return Theme::monochrome();
+        }
+        let fg = |color| Style::default().fg(color);
+        let bold = Style::default().add_modifier(Modifier::BOLD);
+        match name {
+            ThemeName::Dark => Theme {
+                good: fg(Color::Green),
+                warning: fg(Color::Yellow),
+                critical: fg(Color::Red),
+                active: fg(Color::Cyan).add_modifier(Modifier::BOLD),
+                tab: fg(Color::Yellow).add_modifier(Modifier::BOLD),
+            },
+            ThemeName::Light => Theme {
+                good: fg(Color::Indexed(28)),
+                warning: fg(Color::Indexed(130)),
+                critical: fg(Color::Indexed(124)),
+                active: fg(Color::Indexed(25)).add_modifier(Modifier::BOLD),
+                tab: fg(Color::Indexed(25)).add_modifier(Modifier::BOLD),
+            },
+            ThemeName::HighContrast => Theme {
+                good: fg(Color::LightGreen).patch(bold),
+                warning: fg(Color::LightYellow).patch(bold),
+                critical: fg(Color::White).bg(Color::Red).patch(bold),
+                active: fg(Color::LightCyan).patch(bold),
+                tab: fg(Color::Black).bg(Color::LightYellow).patch(bold),
+            },
+            // Okabe-Ito colours; critical is bold as well, apart from its hue
+            ThemeName::ColorBlind => Theme {
+                good: fg(Color::Indexed(32)),
+                warning: fg(Color::Indexed(214)),
+                critical: fg(Color::Indexed(166)).patch(bold),
+                active: fg(Color::Indexed(74)).patch(bold),
+                tab: fg(Color::Indexed(214)).patch(bold),
+            },
+        }
+    }
+
+    /// No colours at all (`NO_COLOR` or `--color never`); what needs attention is bold
+    pub fn monochrome() -> Theme {
+        let bold = Style::default().add_modifier(Modifier::BOLD);
+        Theme {
+            good: Style::default(),
+            warning: bold,
+            critical: bold,
+            active: bold,
+            tab: Style::default().add_modifier(Modifier::REVERSED),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_theme() {
+        for name in [
+            ThemeName::Dark,
+            ThemeName::Light,
+            ThemeName::HighContrast,
+            ThemeName::ColorBlind,
+        ] {
+            assert_eq!(Theme::new(name, false), Theme::monochrome());
+            let theme = Theme::new(name, true);
+            assert_ne!(theme.good, theme.warning);
+            assert_ne!(theme.warning, theme.critical);
+        }
+
+        let theme = Theme::new(ThemeName::ColorBlind, true);
+        for style in [theme.good, theme.warning, theme.critical, theme.active] {
+            assert!(!matches!(
+                style.fg,
+                Some(Color::Red | Color::Green | Color::LightRed | Color::LightGreen)
+            ));
+        }
+    }
+}
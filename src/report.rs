@@ -0,0 +1,876 @@
+//! Post-mortem report of a recovery from a directory of saved JSON files:
+//! `ceph pg dump --format json` for the object counts, and optionally
+//! `ceph status --format json` for the health checks.
+
+use crate::common::{CephPgDump, CephStatus, HealthCheck};
+use crate::monitor::data::{
+    count_osd_misplaced_objects, format_bytes_per_second, format_number, format_time,
+};
+use crate::monitor::history::{format_timestamp, Sample};
+use crate::Result;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write;
+use std::path::Path;
+
+/// Rows of the throughput table; longer recoveries are grouped into buckets
+const MAX_THROUGHPUT_ROWS: usize = 48;
+const SLOWEST_OSDS: usize = 10;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum ReportFormat {
+    Markdown,
+    Html,
+}
+
+impl ReportFormat {
+    /// HTML for `.html` and `.htm` files, Markdown otherwise
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some(ext) if ext.eq_ignore_ascii_case("html") || ext.eq_ignore_ascii_case("htm") => {
+                Self::Html
+            }
+            _ => Self::Markdown,
+        }
+    }
+}
+
+/// What the report needs of one pg dump
+#[derive(Debug, Clone, Default)]
+struct PgDumpSnapshot {
+    sample: Sample,
+    /// Misplaced objects to move to or from each OSD
+    osds: HashMap<u32, i64>,
+    inconsistent: Vec<String>,
+}
+
+impl PgDumpSnapshot {
+    fn new(ts: i64, data: &CephPgDump) -> Self {
+        Self {
+            sample: Sample::from_pg_dump(ts, data),
+            osds: count_osd_misplaced_objects(data)
+                .into_values()
+                .map(|osd| (osd.osd_id, osd.missing_objects + osd.excess_objects))
+                .collect(),
+            inconsistent: data
+                .pg_map
+                .pg_stats
+                .iter()
+                .filter(|pg| pg.state.contains("inconsistent"))
+                .map(|pg| pg.pgid.clone())
+                .collect(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+struct StatusSnapshot {
+    ts: i64,
+    checks: BTreeMap<String, HealthCheck>,
+}
+
+/// The files of a report directory, oldest first
+#[derive(Debug, Default)]
+pub struct Snapshots {
+    pg_dumps: Vec<PgDumpSnapshot>,
+    statuses: Vec<StatusSnapshot>,
+}
+
+impl Snapshots {
+    /// Read all `*.json` files of `dir`. The time of a file is taken from its
+    /// name (`20250720-090000.json`, `pg-2025-07-20T09:00:00.json` or Unix
+    /// seconds), else from the pg dump stamp, else from its modification time.
+    pub fn load_dir(dir: &Path) -> Result<Self> {
+        let entries =
+            std::fs::read_dir(dir).map_err(|e| format!("Cannot read {}: {e}", dir.display()))?;
+        let mut snapshots = Self::default();
+        for entry in entries {
+            let path = entry?.path();
+            if path.extension().and_then(|ext| ext.to_str()) != Some("json") {
+                continue;
+            }
+            let text = std::fs::read_to_string(&path)
+                .map_err(|e| format!("Cannot read {}: {e}", path.display()))?;
+            let name_ts = path
+                .file_stem()
+                .and_then(|stem| stem.to_str())
+                .and_then(timestamp_from_name);
+            let mtime = || -> Option<i64> {
+                let modified = std::fs::metadata(&path).ok()?.modified().ok()?;
+                Some(chrono::DateTime::<chrono::Utc>::from(modified).timestamp())
+            };
+            snapshots
+                .add_json(&text, || name_ts.or_else(mtime))
+                .map_err(|e| format!("{}: {e}", path.display()))?;
+        }
+        if snapshots.pg_dumps.is_empty() {
+            return Err(format!("No pg dump JSON files in {}", dir.display()).into());
+        }
+        snapshots.sort();
+        Ok(snapshots)
+    }
+
+    /// Add a pg dump or status file; `ts` is asked for when the file name or
+    /// contents do not tell the time
+    fn add_json(&mut self, text: &str, ts: impl FnOnce() -> Option<i64>) -> Result<()> {
+        let value: serde_json::Value =
+            serde_json::from_str(text).map_err(|e| format!("Invalid JSON: {e}"))?;
+        if value.get("pg_map").is_some() {
+            let data: CephPgDump = serde_json::from_value(value)
+                .map_err(|e| format!("Unexpected pg dump format: {e}"))?;
+            let ts = ts()
+                .or_else(|| parse_stamp(&data.pg_map.stamp))
+                .ok_or("Cannot tell the time of the pg dump")?;
+            self.pg_dumps.push(PgDumpSnapshot::new(ts, &data));
+        } else if value.get("health").is_some() {
+            let status: CephStatus = serde_json::from_value(value)
+                .map_err(|e| format!("Unexpected status format: {e}"))?;
+            let ts = ts().ok_or("Cannot tell the time of the status")?;
+            self.statuses.push(StatusSnapshot {
+                ts,
+                checks: status.health.checks,
+            });
+        } else {
+            return Err("Neither a pg dump nor a status".into());
+        }
+        Ok(())
+    }
+
+    fn sort(&mut self) {
+        self.pg_dumps.sort_by_key(|snapshot| snapshot.sample.ts);
+        self.statuses.sort_by_key(|snapshot| snapshot.ts);
+    }
+}
+
+/// The time in a file name: the first 14 digits as `YYYYMMDDhhmmss` UTC, or
+/// a run of 10 digits as Unix seconds
+fn timestamp_from_name(name: &str) -> Option<i64> {
+    let digits: String = name.chars().filter(char::is_ascii_digit).collect();
+    if digits.len() >= 14 {
+        if let Ok(t) = chrono::NaiveDateTime::parse_from_str(&digits[..14], "%Y%m%d%H%M%S") {
+            return Some(t.and_utc().timestamp());
+        }
+    }
+    name.split(|c: char| !c.is_ascii_digit())
+        .find(|run| run.len() == 10)
+        .and_then(|run| run.parse().ok())
+}
+
+/// `pg_map.stamp`, e.g. `2025-07-20T09:00:00.000000+0000`
+fn parse_stamp(stamp: &str) -> Option<i64> {
+    chrono::DateTime::parse_from_str(stamp, "%Y-%m-%dT%H:%M:%S%.f%z")
+        .ok()
+        .map(|t| t.timestamp())
+}
+
+/// Recovery speed between two pg dumps
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Throughput {
+    pub ts: i64,
+    /// Objects still to recover at `ts`
+    pub remaining: i64,
+    pub objects_per_sec: f64,
+    pub bytes_per_sec: f64,
+}
+
+/// How long the misplaced objects of an OSD took to move
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OsdDrain {
+    pub osd: u32,
+    pub peak_objects: i64,
+    pub first: i64,
+    /// First pg dump without misplaced objects on the OSD, None if there was none
+    pub drained: Option<i64>,
+    pub duration: i64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PgRepair {
+    pub pgid: String,
+    pub first: i64,
+    /// First pg dump without the PG inconsistent, None if it still was at the end
+    pub repaired: Option<i64>,
+    pub duration: i64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HealthCheckSeen {
+    pub name: String,
+    /// The worst severity seen
+    pub severity: String,
+    /// The last message seen
+    pub message: String,
+    pub first: i64,
+    pub last: i64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Report {
+    pub first: i64,
+    pub last: i64,
+    pub pg_dumps: usize,
+    /// First pg dump with objects to recover, None if there were none
+    pub start: Option<i64>,
+    /// First pg dump without objects to recover after the recovery
+    pub end: Option<i64>,
+    /// Peak value and when it was first reached
+    pub peak_degraded: (i64, i64),
+    pub peak_misplaced: (i64, i64),
+    pub throughput: Vec<Throughput>,
+    pub slowest_osds: Vec<OsdDrain>,
+    pub inconsistent_pgs: Vec<PgRepair>,
+    pub health_checks: Vec<HealthCheckSeen>,
+    /// Whether there were status files, so no health checks means none were seen
+    pub has_status: bool,
+}
+
+impl Report {
+    pub fn new(snapshots: &Snapshots) -> Self {
+        let dumps = &snapshots.pg_dumps;
+        let samples: Vec<Sample> = dumps.iter().map(|snapshot| snapshot.sample).collect();
+        let first = samples.first().map_or(0, |s| s.ts);
+        let last = samples.last().map_or(0, |s| s.ts);
+
+        let start = samples.iter().position(|s| s.remaining() > 0);
+        let last_active = samples.iter().rposition(|s| s.remaining() > 0);
+        let end = last_active.and_then(|index| samples.get(index + 1));
+
+        let peak = |value: fn(&Sample) -> i64| {
+            samples.iter().fold((0, first), |peak, s| {
+                if value(s) > peak.0 {
+                    (value(s), s.ts)
+                } else {
+                    peak
+                }
+            })
+        };
+
+        // Throughput from the start of the recovery until it ended
+        let span = match (start, last_active) {
+            (Some(start), Some(last_active)) => {
+                &samples[start..(last_active + 2).min(samples.len())]
+            }
+            _ => &[],
+        };
+        let throughput = span
+            .windows(2)
+            .filter(|pair| pair[1].ts > pair[0].ts)
+            .map(|pair| {
+                let (previous, current) = (pair[0], pair[1]);
+                let elapsed = (current.ts - previous.ts) as f64;
+                let objects = (previous.remaining() - current.remaining()).max(0) as f64;
+                let object_size = if current.num_objects > 0 {
+                    current.num_bytes as f64 / current.num_objects as f64
+                } else {
+                    0.0
+                };
+                Throughput {
+                    ts: current.ts,
+                    remaining: current.remaining(),
+                    objects_per_sec: objects / elapsed,
+                    bytes_per_sec: objects * object_size / elapsed,
+                }
+            })
+            .collect();
+
+        Self {
+            first,
+            last,
+            pg_dumps: samples.len(),
+            start: start.map(|index| samples[index].ts),
+            end: end.map(|s| s.ts),
+            peak_degraded: peak(|s| s.degraded),
+            peak_misplaced: peak(|s| s.misplaced),
+            throughput,
+            slowest_osds: slowest_osds(dumps, last),
+            inconsistent_pgs: inconsistent_pgs(dumps, last),
+            health_checks: health_checks(&snapshots.statuses),
+            has_status: !snapshots.statuses.is_empty(),
+        }
+    }
+
+    pub fn render(&self, format: ReportFormat) -> String {
+        let sections = self.sections();
+        match format {
+            ReportFormat::Markdown => render_markdown(&sections),
+            ReportFormat::Html => render_html(&sections, &self.throughput),
+        }
+    }
+
+    fn sections(&self) -> Vec<Section> {
+        let mut summary = vec![
+            vec![
+                "Data".to_string(),
+                format!(
+                    "{} pg dumps, {} - {} UTC",
+                    self.pg_dumps,
+                    format_timestamp(self.first),
+                    format_timestamp(self.last)
+                ),
+            ],
+            vec![
+                "Recovery start".to_string(),
+                self.start
+                    .map_or("no objects to recover".to_string(), format_timestamp),
+            ],
+        ];
+        if let Some(start) = self.start {
+            let (end, duration) = match self.end {
+                Some(end) => (format_timestamp(end), end - start),
+                None => ("not finished".to_string(), self.last - start),
+            };
+            summary.push(vec!["Recovery end".to_string(), end]);
+            summary.push(vec!["Duration".to_string(), format_duration(duration)]);
+        }
+        for (label, (peak, ts)) in [
+            ("Peak degraded", self.peak_degraded),
+            ("Peak misplaced", self.peak_misplaced),
+        ] {
+            summary.push(vec![
+                label.to_string(),
+                if peak > 0 {
+                    format!(
+                        "{} objects at {}",
+                        format_number(peak),
+                        format_timestamp(ts)
+                    )
+                } else {
+                    "0".to_string()
+                },
+            ]);
+        }
+
+        vec![
+            Section {
+                title: "Summary",
+                headers: vec![],
+                rows: summary,
+                empty: "",
+            },
+            Section {
+                title: "Throughput",
+                headers: vec!["Until (UTC)", "Objects left", "Objects/s", "Throughput"],
+                rows: bucket_throughput(&self.throughput, MAX_THROUGHPUT_ROWS)
+                    .into_iter()
+                    .map(|t| {
+                        vec![
+                            format_timestamp(t.ts),
+                            format_number(t.remaining),
+                            format!("{:.1}", t.objects_per_sec),
+                            format_bytes_per_second(t.bytes_per_sec),
+                        ]
+                    })
+                    .collect(),
+                empty: "No recovery in the pg dumps.",
+            },
+            Section {
+                title: "Slowest OSDs",
+                headers: vec![
+                    "OSD",
+                    "Misplaced objects",
+                    "First seen (UTC)",
+                    "Drained (UTC)",
+                    "Duration",
+                ],
+                rows: self
+                    .slowest_osds
+                    .iter()
+                    .map(|osd| {
+                        vec![
+                            format!("osd.{}", osd.osd),
+                            format_number(osd.peak_objects),
+                            format_timestamp(osd.first),
+                            osd.drained
+                                .map_or("not finished".to_string(), format_timestamp),
+                            format_duration(osd.duration),
+                        ]
+                    })
+                    .collect(),
+                empty: "No misplaced objects.",
+            },
+            Section {
+                title: "Inconsistent PGs",
+                headers: vec!["PG", "First seen (UTC)", "Repaired (UTC)", "Duration"],
+                rows: self
+                    .inconsistent_pgs
+                    .iter()
+                    .map(|pg| {
+                        vec![
+                            pg.pgid.clone(),
+                            format_timestamp(pg.first),
+                            pg.repaired
+                                .map_or("not repaired".to_string(), format_timestamp),
+                            format_duration(pg.duration),
+                        ]
+                    })
+                    .collect(),
+                empty: "No inconsistent PGs.",
+            },
+            Section {
+                title: "Health checks",
+                headers: vec![
+                    "Check",
+                    "Severity",
+                    "First seen (UTC)",
+                    "Last seen (UTC)",
+                    "Message",
+                ],
+                rows: self
+                    .health_checks
+                    .iter()
+                    .map(|check| {
+                        vec![
+                            check.name.clone(),
+                            check.severity.clone(),
+                            format_timestamp(check.first),
+                            format_timestamp(check.last),
+                            check.message.clone(),
+                        ]
+                    })
+                    .collect(),
+                empty: if self.has_status {
+                    "No health checks."
+                } else {
+                    "No `ceph status` JSON files in the directory."
+                },
+            },
+        ]
+    }
+}
+
+fn format_duration(seconds: i64) -> String {
+    format_time(seconds.max(0) as u64)
+}
+
+/// OSDs by how long their misplaced objects took to move, unfinished ones first
+fn slowest_osds(dumps: &[PgDumpSnapshot], last: i64) -> Vec<OsdDrain> {
+    let mut drains: HashMap<u32, OsdDrain> = HashMap::new();
+    for (index, snapshot) in dumps.iter().enumerate() {
+        for (&osd, &objects) in &snapshot.osds {
+            let drain = drains.entry(osd).or_insert(OsdDrain {
+                osd,
+                peak_objects: 0,
+                first: snapshot.sample.ts,
+                drained: None,
+                duration: 0,
+            });
+            drain.peak_objects = drain.peak_objects.max(objects);
+            // Drained at the next pg dump without objects on this OSD
+            drain.drained = dumps[index + 1..]
+                .first()
+                .filter(|next| !next.osds.contains_key(&osd))
+                .map(|next| next.sample.ts);
+        }
+    }
+
+    let mut drains: Vec<OsdDrain> = drains
+        .into_values()
+        .map(|mut drain| {
+            drain.duration = drain.drained.unwrap_or(last) - drain.first;
+            drain
+        })
+        .collect();
+    drains.sort_by(|a, b| {
+        (b.drained.is_none(), b.duration, b.peak_objects, a.osd).cmp(&(
+            a.drained.is_none(),
+            a.duration,
+            a.peak_objects,
+            b.osd,
+        ))
+    });
+    drains.truncate(SLOWEST_OSDS);
+    drains
+}
+
+/// Inconsistent PGs in the order they showed up
+fn inconsistent_pgs(dumps: &[PgDumpSnapshot], last: i64) -> Vec<PgRepair> {
+    let mut repairs: Vec<PgRepair> = Vec::new();
+    for (index, snapshot) in dumps.iter().enumerate() {
+        for pgid in &snapshot.inconsistent {
+            let position = repairs.iter().position(|repair| &repair.pgid == pgid);
+            let entry = position.unwrap_or_else(|| {
+                repairs.push(PgRepair {
+                    pgid: pgid.clone(),
+                    first: snapshot.sample.ts,
+                    repaired: None,
+                    duration: 0,
+                });
+                repairs.len() - 1
+            });
+            repairs[entry].repaired = dumps[index + 1..]
+                .first()
+                .filter(|next| !next.inconsistent.contains(pgid))
+                .map(|next| next.sample.ts);
+        }
+    }
+    for repair in &mut repairs {
+        repair.duration = repair.repaired.unwrap_or(last) - repair.first;
+    }
+    repairs
+}
+
+fn severity_rank(severity: &str) -> u8 {
+    match severity {
+        "HEALTH_ERR" => 2,
+        "HEALTH_WARN" => 1,
+        _ => 0,
+    }
+}
+
+/// Health checks in the order they showed up
+fn health_checks(statuses: &[StatusSnapshot]) -> Vec<HealthCheckSeen> {
+    let mut seen: Vec<HealthCheckSeen> = Vec::new();
+    for status in statuses {
+        for (name, check) in &status.checks {
+            match seen.iter_mut().find(|seen| &seen.name == name) {
+                Some(seen) => {
+                    if severity_rank(&check.severity) > severity_rank(&seen.severity) {
+                        seen.severity = check.severity.clone();
+                    }
+                    seen.message = check.summary.message.clone();
+                    seen.last = status.ts;
+                }
+                None => seen.push(HealthCheckSeen {
+                    name: name.clone(),
+                    severity: check.severity.clone(),
+                    message: check.summary.message.clone(),
+                    first: status.ts,
+                    last: status.ts,
+                }),
+            }
+        }
+    }
+    seen
+}
+
+/// Merge consecutive throughput points into at most `rows` points
+fn bucket_throughput(points: &[Throughput], rows: usize) -> Vec<Throughput> {
+    if points.len() <= rows {
+        return points.to_vec();
+    }
+    let size = points.len().div_ceil(rows);
+    let mut start = points.first().map_or(0, |p| p.ts);
+    let mut buckets = Vec::new();
+    for chunk in points.chunks(size) {
+        let end = chunk[chunk.len() - 1];
+        // Weight each rate by the time it applied
+        let mut previous = start;
+        let (mut objects, mut bytes) = (0.0, 0.0);
+        for point in chunk {
+            let elapsed = (point.ts - previous) as f64;
+            objects += point.objects_per_sec * elapsed;
+            bytes += point.bytes_per_sec * elapsed;
+            previous = point.ts;
+        }
+        let elapsed = (end.ts - start).max(1) as f64;
+        buckets.push(Throughput {
+            ts: end.ts,
+            remaining: end.remaining,
+            objects_per_sec: objects / elapsed,
+            bytes_per_sec: bytes / elapsed,
+        });
+        start = end.ts;
+    }
+    buckets
+}
+
+/// A titled table of the report; without headers it is a list of key/value rows
+struct Section {
+    title: &'static str,
+    headers: Vec<&'static str>,
+    rows: Vec<Vec<String>>,
+    /// Shown instead of an empty table
+    empty: &'static str,
+}
+
+fn markdown_cell(text: &str) -> String {
+    text.replace('|', "\\|")
+}
+
+fn render_markdown(sections: &[Section]) -> String {
+    let mut out = String::from("# Recovery Report\n");
+    for section in sections {
+        let _ = write!(out, "\n## {}\n\n", section.title);
+        if section.rows.is_empty() {
+            let _ = writeln!(out, "{}", section.empty);
+            continue;
+        }
+        let headers: Vec<&str> = if section.headers.is_empty() {
+            vec!["", ""]
+        } else {
+            section.headers.clone()
+        };
+        let _ = writeln!(out, "| {} |", headers.join(" | "));
+        let _ = writeln!(out, "|{}", "---|".repeat(headers.len()));
+        for row in &section.rows {
+            let cells: Vec<String> = row.iter().map(|cell| markdown_cell(cell)).collect();
+            let _ = writeln!(out, "| {} |", cells.join(" | "));
+        }
+    }
+    out
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+const HTML_STYLE: &str = "
+body { font-family: sans-serif; margin: 2em; color: #222; }
+table { border-collapse: collapse; margin-bottom: 1em; }
+th, td { border: 1px solid #ccc; padding: 0.3em 0.8em; text-align: left; }
+th { background: #eee; }
+svg { border: 1px solid #ccc; margin-bottom: 1em; }
+.chart-title { font-weight: bold; margin: 0.5em 0; }
+";
+
+fn render_html(sections: &[Section], throughput: &[Throughput]) -> String {
+    let mut out = String::from(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>Recovery Report</title>\n",
+    );
+    let _ = writeln!(out, "<style>{HTML_STYLE}</style>\n</head>\n<body>");
+    out.push_str("<h1>Recovery Report</h1>\n");
+    for section in sections {
+        let _ = writeln!(out, "<h2>{}</h2>", escape_html(section.title));
+        if section.title == "Throughput" && throughput.len() > 1 {
+            out.push_str("<div class=\"chart-title\">Objects left</div>\n");
+            out.push_str(&svg_chart(
+                throughput,
+                |t| t.remaining as f64,
+                |max| format_number(max as i64),
+                "#c0392b",
+            ));
+            out.push_str("<div class=\"chart-title\">Throughput</div>\n");
+            out.push_str(&svg_chart(
+                throughput,
+                |t| t.bytes_per_sec,
+                format_bytes_per_second,
+                "#2471a3",
+            ));
+        }
+        if section.rows.is_empty() {
+            let _ = writeln!(out, "<p>{}</p>", escape_html(section.empty));
+            continue;
+        }
+        out.push_str("<table>\n");
+        if !section.headers.is_empty() {
+            out.push_str("<tr>");
+            for header in &section.headers {
+                let _ = write!(out, "<th>{}</th>", escape_html(header));
+            }
+            out.push_str("</tr>\n");
+        }
+        for row in &section.rows {
+            out.push_str("<tr>");
+            for (index, cell) in row.iter().enumerate() {
+                // Key/value sections have the key in the first column
+                let tag = if section.headers.is_empty() && index == 0 {
+                    "th"
+                } else {
+                    "td"
+                };
+                let _ = write!(out, "<{tag}>{}</{tag}>", escape_html(cell));
+            }
+            out.push_str("</tr>\n");
+        }
+        out.push_str("</table>\n");
+    }
+    out.push_str("</body>\n</html>\n");
+    out
+}
+
+/// A line chart of `value` over time as inline SVG, labelled with its maximum
+fn svg_chart(
+    points: &[Throughput],
+    value: fn(&Throughput) -> f64,
+    format_label: fn(f64) -> String,
+    color: &str,
+) -> String {
+    const WIDTH: f64 = 800.0;
+    const HEIGHT: f64 = 200.0;
+    const MARGIN: f64 = 10.0;
+
+    let first = points.first().map_or(0, |p| p.ts);
+    let span = (points.last().map_or(0, |p| p.ts) - first).max(1) as f64;
+    let max = points.iter().map(value).fold(0.0, f64::max);
+    let coordinates: Vec<String> = points
+        .iter()
+        .map(|point| {
+            let x = MARGIN + (point.ts - first) as f64 / span * (WIDTH - 2.0 * MARGIN);
+            let y = if max > 0.0 {
+                HEIGHT - MARGIN - value(point) / max * (HEIGHT - 2.0 * MARGIN)
+            } else {
+                HEIGHT - MARGIN
+            };
+            format!("{x:.1},{y:.1}")
+        })
+        .collect();
+    let label = format_label(max);
+    format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{WIDTH}\" height=\"{HEIGHT}\">\
+         <polyline fill=\"none\" stroke=\"{color}\" stroke-width=\"2\" points=\"{}\"/>\
+         <text x=\"{MARGIN}\" y=\"{}\" font-size=\"12\">max {}</text></svg>\n",
+        coordinates.join(" "),
+        MARGIN + 12.0,
+        escape_html(&label)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pg_dump() -> CephPgDump {
+        serde_json::from_str(include_str!(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/tests/fixtures/pg_dump.json"
+        )))
+        .unwrap()
+    }
+
+    /// The fixture, then with PG 1.0 backfilled, then with everything clean
+    fn recovery() -> Snapshots {
+        let mut data = pg_dump();
+        let mut snapshots = Snapshots::default();
+        snapshots.pg_dumps.push(PgDumpSnapshot::new(0, &data));
+
+        let pg = &mut data.pg_map.pg_stats[0];
+        pg.acting = pg.up.clone();
+        pg.stat_sum.num_objects_misplaced = 0;
+        data.pg_map.pg_stats_sum.stat_sum.num_objects_misplaced -= 200;
+        snapshots.pg_dumps.push(PgDumpSnapshot::new(60, &data));
+
+        for pg in &mut data.pg_map.pg_stats {
+            pg.acting = pg.up.clone();
+            pg.state = "active+clean".to_string();
+            pg.stat_sum.num_objects_misplaced = 0;
+        }
+        data.pg_map.pg_stats_sum.stat_sum.num_objects_misplaced = 0;
+        snapshots.pg_dumps.push(PgDumpSnapshot::new(120, &data));
+        snapshots
+    }
+
+    #[test]
+    fn test_timestamp_from_name() {
+        assert_eq!(timestamp_from_name("20250720-090000"), Some(1753002000));
+        assert_eq!(
+            timestamp_from_name("pg-2025-07-20T09:00:00"),
+            Some(1753002000)
+        );
+        assert_eq!(timestamp_from_name("pg_dump.1753002000"), Some(1753002000));
+        assert_eq!(timestamp_from_name("pg_dump"), None);
+        assert_eq!(
+            parse_stamp("2025-07-20T09:00:00.000000+0000"),
+            Some(1753002000)
+        );
+    }
+
+    #[test]
+    fn test_report() {
+        let report = Report::new(&recovery());
+        assert_eq!(report.start, Some(0));
+        assert_eq!(report.end, Some(120));
+        assert_eq!(report.peak_misplaced, (1955, 0));
+        assert_eq!(report.peak_degraded, (0, 0));
+
+        assert_eq!(report.throughput.len(), 2);
+        assert_eq!(report.throughput[0].remaining, 1755);
+        assert!((report.throughput[0].objects_per_sec - 200.0 / 60.0).abs() < 1e-9);
+        // 4 MiB objects
+        assert!((report.throughput[1].bytes_per_sec - 1755.0 * 4194304.0 / 60.0).abs() < 1e-3);
+
+        assert_eq!(report.slowest_osds.len(), 8);
+        assert!(report
+            .slowest_osds
+            .iter()
+            .all(|osd| osd.drained == Some(120) && osd.duration == 120));
+
+        assert_eq!(
+            report.inconsistent_pgs,
+            vec![PgRepair {
+                pgid: "1.14".to_string(),
+                first: 0,
+                repaired: Some(120),
+                duration: 120,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_unfinished_recovery() {
+        let mut snapshots = recovery();
+        snapshots.pg_dumps.pop();
+        let report = Report::new(&snapshots);
+        assert_eq!(report.end, None);
+        assert_eq!(report.inconsistent_pgs[0].repaired, None);
+        assert_eq!(report.inconsistent_pgs[0].duration, 60);
+        // OSDs that still have objects to move come first
+        assert!(report.slowest_osds.iter().all(|osd| osd.drained.is_none()));
+    }
+
+    #[test]
+    fn test_health_checks() {
+        let mut snapshots = Snapshots::default();
+        let status = include_str!(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/tests/fixtures/status.json"
+        ));
+        snapshots.add_json(status, || Some(10)).unwrap();
+        snapshots
+            .add_json(&status.replace("HEALTH_WARN", "HEALTH_ERR"), || Some(20))
+            .unwrap();
+        snapshots.add_json(status, || Some(30)).unwrap();
+        assert!(snapshots.add_json(status, || None).is_err());
+        assert!(snapshots.add_json("{}", || Some(40)).is_err());
+
+        let checks = health_checks(&snapshots.statuses);
+        assert_eq!(checks.len(), 1);
+        assert_eq!(checks[0].name, "PG_DEGRADED");
+        assert_eq!(checks[0].severity, "HEALTH_ERR");
+        assert_eq!((checks[0].first, checks[0].last), (10, 30));
+    }
+
+    #[test]
+    fn test_bucket_throughput() {
+        let points: Vec<Throughput> = (1..=4)
+            .map(|i| Throughput {
+                ts: i * 10,
+                remaining: 100 - i * 10,
+                objects_per_sec: i as f64,
+                bytes_per_sec: 0.0,
+            })
+            .collect();
+        assert_eq!(bucket_throughput(&points, 4), points);
+        let buckets = bucket_throughput(&points, 2);
+        assert_eq!(buckets.len(), 2);
+        assert_eq!((buckets[0].ts, buckets[0].remaining), (20, 80));
+        // From 10 to 20 at 2 objects/s; the first point only marks the start
+        assert!((buckets[0].objects_per_sec - 2.0).abs() < 1e-9);
+        assert!((buckets[1].objects_per_sec - 3.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_render() {
+        let report = Report::new(&recovery());
+        let markdown = report.render(ReportFormat::Markdown);
+        assert!(markdown.starts_with("# Recovery Report\n"));
+        assert!(markdown.contains("| Recovery end | 1970-01-01 00:02:00 |"));
+        assert!(markdown.contains("| 1.14 | 1970-01-01 00:00:00 | 1970-01-01 00:02:00 | 2m00s |"));
+        assert!(markdown.contains("No `ceph status` JSON files in the directory."));
+
+        let html = report.render(ReportFormat::Html);
+        assert!(html.contains("<polyline"));
+        assert!(html.contains("<td>1.14</td>"));
+        assert!(html.ends_with("</html>\n"));
+
+        assert_eq!(
+            ReportFormat::from_path(Path::new("report.HTML")),
+            ReportFormat::Html
+        );
+        assert_eq!(
+            ReportFormat::from_path(Path::new("report.md")),
+            ReportFormat::Markdown
+        );
+    }
+}
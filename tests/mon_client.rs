@@ -0,0 +1,386 @@
+//! Runs the native monitor client against a stand-in monitor that plays the
+//! monitor's side of the scripted conversation in
+//! `tests/fixtures/mon/conversation.json`.
+//!
+//! The stand-in is built from the client's own framing and cephx code, so
+//! this covers the session logic: the order of frames, retries, fallback and
+//! error reporting. The bytes on the wire are pinned separately by the
+//! known-answer tests in `src/monitor/mon`, which were computed without this
+//! crate; none of them comes from a capture of a real monitor yet.
+
+use ceph_doctor::common::{CephOsdTree, CephPgDump, CephStatus};
+use ceph_doctor::error::{CephError, ErrorClass};
+use ceph_doctor::monitor::mon::cephx::{self, CryptoKey, EntityName};
+use ceph_doctor::monitor::mon::encoding::{Decoder, Encoder, ADDR_TYPE_ANY};
+use ceph_doctor::monitor::mon::msgr2::{self, Connection, Frame};
+use ceph_doctor::monitor::mon::{self, config, MonClient, MonClientConfig};
+use ceph_doctor::monitor::source::{CephQuery, DataSource};
+use serde::Deserialize;
+use std::time::Duration;
+use tokio::net::{TcpListener, TcpStream};
+
+const TIMEOUT: Duration = Duration::from_secs(5);
+const EACCES: i32 = -13;
+
+#[derive(Debug, Clone, Deserialize)]
+struct Conversation {
+    client: String,
+    key: String,
+    fsid: String,
+    global_id: u64,
+    server_challenge: u64,
+    session_key: String,
+    commands: Vec<RecordedCommand>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct RecordedCommand {
+    request: serde_json::Value,
+    result: i32,
+    status: String,
+    output_file: Option<String>,
+}
+
+fn fixtures() -> String {
+    format!("{}/tests/fixtures", env!("CARGO_MANIFEST_DIR"))
+}
+
+fn conversation() -> Conversation {
+    let text = std::fs::read_to_string(format!("{}/mon/conversation.json", fixtures())).unwrap();
+    serde_json::from_str(&text).unwrap()
+}
+
+/// Start a stand-in monitor and return its address
+async fn start_monitor(conversation: Conversation) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap().to_string();
+    tokio::spawn(async move {
+        while let Ok((stream, _)) = listener.accept().await {
+            let conversation = conversation.clone();
+            tokio::spawn(async move {
+                // Errors just end the session; the client reports what it saw
+                let _ = serve(stream, &conversation).await;
+            });
+        }
+    });
+    addr
+}
+
+async fn serve(stream: TcpStream, conversation: &Conversation) -> Result<(), String> {
+    let client_addr = stream.peer_addr().unwrap();
+    let mut conn = Connection::new(stream);
+    let io = |e: std::io::Error| e.to_string();
+
+    conn.exchange_banner(msgr2::FEATURE_REVISION_1, 0)
+        .await
+        .map_err(io)?;
+
+    let hello = conn.recv().await.map_err(io)?;
+    assert_eq!(hello.tag, msgr2::TAG_HELLO);
+    assert_eq!(hello.payload()[0] as u32, cephx::ENTITY_TYPE_CLIENT);
+    let mut reply = Encoder::new();
+    reply
+        .u8(cephx::ENTITY_TYPE_MON as u8)
+        .entity_addr(ADDR_TYPE_ANY, 0, &client_addr);
+    conn.send(&Frame::control(msgr2::TAG_HELLO, reply.into_inner()))
+        .await
+        .map_err(io)?;
+
+    // AUTH_REQUEST: method, modes and who is asking
+    let request = conn.recv().await.map_err(io)?;
+    assert_eq!(request.tag, msgr2::TAG_AUTH_REQUEST);
+    let mut decoder = Decoder::new(request.payload());
+    assert_eq!(decoder.u32()?, cephx::AUTH_CEPHX);
+    let modes: Vec<u32> = (0..decoder.u32()?)
+        .map(|_| decoder.u32().unwrap())
+        .collect();
+    assert_eq!(modes, vec![cephx::CON_MODE_CRC]);
+    let mut auth = Decoder::new(decoder.bytes()?);
+    let _version = auth.u8()?;
+    let entity_type = auth.u32()?;
+    let id = auth.string()?;
+    assert_eq!(entity_type, cephx::ENTITY_TYPE_CLIENT);
+    assert_eq!(format!("client.{id}"), conversation.client);
+
+    let mut challenge = Encoder::new();
+    challenge.u8(1).u64(conversation.server_challenge);
+    let mut more = Encoder::new();
+    more.bytes(challenge.as_slice());
+    conn.send(&Frame::control(
+        msgr2::TAG_AUTH_REPLY_MORE,
+        more.into_inner(),
+    ))
+    .await
+    .map_err(io)?;
+
+    // AUTH_REQUEST_MORE: the client proves it knows the key
+    let request = conn.recv().await.map_err(io)?;
+    assert_eq!(request.tag, msgr2::TAG_AUTH_REQUEST_MORE);
+    let mut decoder = Decoder::new(request.payload());
+    let mut authenticate = Decoder::new(decoder.bytes()?);
+    assert_eq!(authenticate.u16()?, cephx::CEPHX_GET_AUTH_SESSION_KEY);
+    let _version = authenticate.u8()?;
+    let client_challenge = authenticate.u64()?;
+    let proof = authenticate.u64()?;
+
+    let key = CryptoKey::from_base64(&conversation.key)?;
+    if proof
+        != cephx::client_server_challenge(&key, conversation.server_challenge, client_challenge)
+    {
+        let mut bad = Encoder::new();
+        bad.u32(cephx::AUTH_CEPHX)
+            .i32(EACCES)
+            .u32(1)
+            .u32(cephx::AUTH_CEPHX)
+            .u32(1)
+            .u32(cephx::CON_MODE_CRC);
+        conn.send(&Frame::control(
+            msgr2::TAG_AUTH_BAD_METHOD,
+            bad.into_inner(),
+        ))
+        .await
+        .map_err(io)?;
+        return Ok(());
+    }
+
+    let session_key = CryptoKey::from_base64(&conversation.session_key)?;
+    conn.send(&Frame::control(
+        msgr2::TAG_AUTH_DONE,
+        auth_done(conversation, &key, &session_key),
+    ))
+    .await
+    .map_err(io)?;
+
+    let (received, sent) = conn.take_pre_auth();
+    conn.send(&Frame::control(
+        msgr2::TAG_AUTH_SIGNATURE,
+        session_key.hmac_sha256(&received).to_vec(),
+    ))
+    .await
+    .map_err(io)?;
+    let signature = conn.recv().await.map_err(io)?;
+    assert_eq!(signature.tag, msgr2::TAG_AUTH_SIGNATURE);
+    assert_eq!(signature.payload(), session_key.hmac_sha256(&sent));
+
+    let ident = conn.recv().await.map_err(io)?;
+    assert_eq!(ident.tag, msgr2::TAG_CLIENT_IDENT);
+    let mut server_ident = Encoder::new();
+    server_ident
+        .entity_addrvec(ADDR_TYPE_ANY, 0, &client_addr)
+        .i64(0)
+        .u64(1)
+        .u64(u64::MAX)
+        .u64(0)
+        .u64(0)
+        .u64(0);
+    conn.send(&Frame::control(
+        msgr2::TAG_SERVER_IDENT,
+        server_ident.into_inner(),
+    ))
+    .await
+    .map_err(io)?;
+
+    let fsid = config::parse_fsid(&conversation.fsid)?;
+    let mut seq = 0;
+    loop {
+        let frame = conn.recv().await.map_err(io)?;
+        assert_eq!(frame.tag, msgr2::TAG_MESSAGE);
+        let (msg_type, tid) = mon::decode_message_header(&frame)?;
+        seq += 1;
+
+        let reply = match msg_type {
+            mon::MSG_MON_GET_MAP => {
+                let mut monmap = Encoder::new();
+                monmap.u8(9).u8(6).u32(16).raw(&fsid);
+                let mut front = Encoder::new();
+                front.bytes(monmap.as_slice());
+                mon::message_frame(seq, 0, mon::MSG_MON_MAP, front.into_inner())
+            }
+            mon::MSG_MON_COMMAND => {
+                let mut decoder = Decoder::new(&frame.segments[1]);
+                decoder.raw(18)?;
+                assert_eq!(decoder.raw(16)?, fsid);
+                let command = decoder.strings()?;
+                let request: serde_json::Value = serde_json::from_str(&command[0]).unwrap();
+                let recorded = conversation
+                    .commands
+                    .iter()
+                    .find(|c| c.request == request)
+                    .unwrap_or_else(|| panic!("no recorded reply for {request}"));
+                let output = recorded
+                    .output_file
+                    .as_ref()
+                    .map(|file| std::fs::read(format!("{}/{file}", fixtures())).unwrap())
+                    .unwrap_or_default();
+
+                let mut front = Encoder::new();
+                front
+                    .u64(0)
+                    .i16(-1)
+                    .u64(0)
+                    .i32(recorded.result)
+                    .string(&recorded.status)
+                    .strings(&command);
+                mon::message_frame_with_data(
+                    seq,
+                    tid,
+                    mon::MSG_MON_COMMAND_ACK,
+                    front.into_inner(),
+                    output,
+                )
+            }
+            other => panic!("unexpected message type {other}"),
+        };
+        conn.send(&reply).await.map_err(io)?;
+    }
+}
+
+/// AUTH_DONE with a cephx reply holding the auth ticket, encrypted with the client key
+fn auth_done(conversation: &Conversation, key: &CryptoKey, session_key: &CryptoKey) -> Vec<u8> {
+    let mut ticket = Encoder::new();
+    ticket.u8(1);
+    session_key.encode(&mut ticket);
+    ticket.u32(3600).u32(0); // validity
+
+    let mut blob = Encoder::new();
+    blob.u8(1)
+        .u64(1)
+        .bytes(b"opaque ticket for the auth service");
+
+    let mut reply = Encoder::new();
+    reply
+        .u16(cephx::CEPHX_GET_AUTH_SESSION_KEY)
+        .i32(0)
+        .u8(1)
+        .u32(1)
+        .u32(cephx::ENTITY_TYPE_AUTH)
+        .u8(1)
+        .raw(&cephx::encode_encrypt(key, ticket.as_slice()))
+        .u8(0)
+        .bytes(blob.as_slice())
+        // No connection secret in crc mode, no extra service tickets
+        .bytes(&[])
+        .bytes(&[]);
+
+    let mut done = Encoder::new();
+    done.u64(conversation.global_id)
+        .u32(cephx::CON_MODE_CRC)
+        .bytes(reply.as_slice());
+    done.into_inner()
+}
+
+fn client(mons: Vec<String>, key: &str, fsid: Option<&str>) -> DataSource {
+    DataSource::Mon(MonClient::from_parts(
+        mons,
+        EntityName::parse("client.admin").unwrap(),
+        CryptoKey::from_base64(key).unwrap(),
+        fsid.map(|fsid| config::parse_fsid(fsid).unwrap()),
+    ))
+}
+
+#[tokio::test]
+async fn test_runs_recorded_commands() {
+    let conversation = conversation();
+    let addr = start_monitor(conversation.clone()).await;
+
+    // Without a fsid in ceph.conf the client asks for the monmap first
+    let source = client(vec![addr.clone()], &conversation.key, None);
+    let pg_dump: CephPgDump = source.fetch(CephQuery::PgDump, TIMEOUT).await.unwrap();
+    assert_eq!(pg_dump.pg_map.pg_stats.len(), 40);
+
+    let source = client(vec![addr], &conversation.key, Some(&conversation.fsid));
+    let tree: CephOsdTree = source.fetch(CephQuery::OsdTree, TIMEOUT).await.unwrap();
+    assert_eq!(tree.nodes.len(), 11);
+}
+
+#[tokio::test]
+async fn test_failed_command() {
+    let conversation = conversation();
+    let addr = start_monitor(conversation.clone()).await;
+
+    let error = client(vec![addr], &conversation.key, Some(&conversation.fsid))
+        .fetch::<CephStatus>(CephQuery::Status, TIMEOUT)
+        .await
+        .unwrap_err();
+    match error {
+        CephError::CommandFailed {
+            exit_code, stderr, ..
+        } => {
+            assert_eq!(exit_code, EACCES);
+            assert!(stderr.contains("access denied"));
+        }
+        other => panic!("expected a command failure, got {other:?}"),
+    }
+}
+
+#[tokio::test]
+async fn test_wrong_key_is_an_auth_failure() {
+    let conversation = conversation();
+    let addr = start_monitor(conversation.clone()).await;
+
+    let error = client(vec![addr], &conversation.session_key, None)
+        .fetch::<CephPgDump>(CephQuery::PgDump, TIMEOUT)
+        .await
+        .unwrap_err();
+    assert_eq!(error.class(), ErrorClass::AuthFailure);
+}
+
+#[tokio::test]
+async fn test_falls_back_to_next_monitor() {
+    let conversation = conversation();
+    let addr = start_monitor(conversation.clone()).await;
+
+    // Nothing listens on this port any more
+    let dead = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let dead_addr = dead.local_addr().unwrap().to_string();
+    drop(dead);
+
+    let source = client(vec![dead_addr.clone(), addr], &conversation.key, None);
+    let pg_dump: CephPgDump = source.fetch(CephQuery::PgDump, TIMEOUT).await.unwrap();
+    assert_eq!(pg_dump.pg_map.osd_stats.len(), 8);
+
+    let error = client(vec![dead_addr], &conversation.key, None)
+        .fetch::<CephPgDump>(CephQuery::PgDump, TIMEOUT)
+        .await
+        .unwrap_err();
+    assert_eq!(error.class(), ErrorClass::MonUnreachable);
+}
+
+#[tokio::test]
+async fn test_client_from_ceph_conf_and_keyring() {
+    let conversation = conversation();
+    let addr = start_monitor(conversation.clone()).await;
+
+    let dir = std::env::temp_dir().join(format!("ceph-doctor-mon-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let keyring = dir.join("ceph.client.admin.keyring");
+    std::fs::write(
+        &keyring,
+        format!(
+            "[client.admin]\n\tkey = {}\n\tcaps mon = \"allow r\"\n",
+            conversation.key
+        ),
+    )
+    .unwrap();
+    let conf = dir.join("ceph.conf");
+    std::fs::write(
+        &conf,
+        format!(
+            "[global]\n\tfsid = {}\n\tmon_host = [v2:{addr}/0]\n\n[client]\n\tkeyring = {}\n",
+            conversation.fsid,
+            keyring.display()
+        ),
+    )
+    .unwrap();
+
+    let client = MonClient::new(&MonClientConfig {
+        conf: Some(conf),
+        name: "client.admin".to_string(),
+        ..Default::default()
+    })
+    .unwrap();
+    let pg_dump: CephPgDump = client.fetch(CephQuery::PgDump, TIMEOUT).await.unwrap();
+    assert_eq!(pg_dump.pg_map.pg_stats.len(), 40);
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,197 @@
+//! Runs the REST data source against a local mock of the ceph-mgr restful module
+//! that serves recorded responses from `tests/fixtures/rest`.
+
+use ceph_doctor::common::{CephOsdTree, CephPgDump, CephStatus};
+use ceph_doctor::error::{CephError, ErrorClass};
+use ceph_doctor::monitor::rest::{RestClient, RestConfig};
+use ceph_doctor::monitor::source::{CephQuery, DataSource};
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::TcpListener;
+
+const USER: &str = "doctor";
+const KEY: &str = "6f1b2c3d-4e5f-4a6b-8c7d-9e0f1a2b3c4d";
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+fn fixture(name: &str) -> String {
+    let path = format!("{}/tests/fixtures/rest/{name}", env!("CARGO_MANIFEST_DIR"));
+    std::fs::read_to_string(&path).unwrap_or_else(|e| panic!("reading {path}: {e}"))
+}
+
+/// Start a mock restful module; `fail_pg_dump` replays a failed `pg dump` request.
+async fn start_server(fail_pg_dump: bool) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    let expected_auth = format!(
+        "Basic {}",
+        base64_encode(format!("{USER}:{KEY}").as_bytes())
+    );
+
+    tokio::spawn(async move {
+        loop {
+            let Ok((stream, _)) = listener.accept().await else {
+                return;
+            };
+            let expected_auth = expected_auth.clone();
+            tokio::spawn(async move {
+                let mut reader = BufReader::new(stream);
+                let mut request_line = String::new();
+                reader.read_line(&mut request_line).await.unwrap();
+
+                let mut content_length = 0;
+                let mut authorization = String::new();
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).await.unwrap();
+                    let line = line.trim_end();
+                    if line.is_empty() {
+                        break;
+                    }
+                    let (name, value) = line.split_once(':').unwrap();
+                    match name.to_ascii_lowercase().as_str() {
+                        "content-length" => content_length = value.trim().parse().unwrap(),
+                        "authorization" => authorization = value.trim().to_string(),
+                        _ => {}
+                    }
+                }
+                let mut body = vec![0; content_length];
+                reader.read_exact(&mut body).await.unwrap();
+                let command: serde_json::Value = serde_json::from_slice(&body).unwrap();
+
+                let (status, response) = if authorization != expected_auth {
+                    (
+                        "401 Unauthorized",
+                        r#"{"message": "Unauthorized"}"#.to_string(),
+                    )
+                } else if !request_line.starts_with("POST /request?wait=1 ") {
+                    ("404 Not Found", r#"{"message": "Not Found"}"#.to_string())
+                } else {
+                    assert_eq!(command["format"], "json");
+                    match command["prefix"].as_str().unwrap() {
+                        "pg dump" if fail_pg_dump => ("200 OK", fixture("pg_dump_failed.json")),
+                        "pg dump" => ("200 OK", fixture("pg_dump.json")),
+                        "osd tree" => ("200 OK", fixture("osd_tree.json")),
+                        "status" => ("200 OK", fixture("status.json")),
+                        _ => (
+                            "400 Bad Request",
+                            r#"{"message": "Bad Request"}"#.to_string(),
+                        ),
+                    }
+                };
+
+                let reply = format!(
+                    "HTTP/1.1 {status}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{response}",
+                    response.len()
+                );
+                let mut stream = reader.into_inner();
+                stream.write_all(reply.as_bytes()).await.unwrap();
+                stream.shutdown().await.ok();
+            });
+        }
+    });
+
+    url
+}
+
+fn base64_encode(input: &[u8]) -> String {
+    const ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut out = String::new();
+    for chunk in input.chunks(3) {
+        let b = [
+            chunk[0],
+            *chunk.get(1).unwrap_or(&0),
+            *chunk.get(2).unwrap_or(&0),
+        ];
+        let n = (u32::from(b[0]) << 16) | (u32::from(b[1]) << 8) | u32::from(b[2]);
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(ALPHABET[(n >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+fn source(url: &str, key: &str) -> DataSource {
+    DataSource::Rest(
+        RestClient::new(&RestConfig {
+            url: url.to_string(),
+            user: Some(USER.to_string()),
+            key: Some(key.to_string()),
+            ..Default::default()
+        })
+        .unwrap(),
+    )
+}
+
+#[tokio::test]
+async fn test_fetches_and_maps_recorded_responses() {
+    let url = start_server(false).await;
+    let source = source(&url, KEY);
+
+    let pg_dump: CephPgDump = source.fetch(CephQuery::PgDump, TIMEOUT).await.unwrap();
+    assert_eq!(pg_dump.pg_map.pg_stats.len(), 40);
+    assert_eq!(pg_dump.pg_map.osd_stats.len(), 8);
+
+    let tree: CephOsdTree = source.fetch(CephQuery::OsdTree, TIMEOUT).await.unwrap();
+    let hosts: Vec<_> = tree
+        .nodes
+        .iter()
+        .filter(|node| node.node_type == "host")
+        .map(|node| node.name.as_str())
+        .collect();
+    assert_eq!(hosts, vec!["node-a", "node-b"]);
+
+    let status: CephStatus = source.fetch(CephQuery::Status, TIMEOUT).await.unwrap();
+    assert_eq!(status.fsid, "8b3c1f2e-5a47-4d8e-9c61-2f0e7b9a4d13");
+    assert_eq!(status.health.status, "HEALTH_WARN");
+    assert_eq!(status.pgmap.misplaced_objects, 18230);
+}
+
+#[tokio::test]
+async fn test_wrong_key_is_an_auth_failure() {
+    let url = start_server(false).await;
+    let error = source(&url, "wrong")
+        .fetch::<CephPgDump>(CephQuery::PgDump, TIMEOUT)
+        .await
+        .unwrap_err();
+
+    assert!(matches!(
+        error,
+        CephError::Http {
+            status: Some(401),
+            ..
+        }
+    ));
+    assert_eq!(error.class(), ErrorClass::AuthFailure);
+}
+
+#[tokio::test]
+async fn test_failed_request_is_a_command_failure() {
+    let url = start_server(true).await;
+    let error = source(&url, KEY)
+        .fetch::<CephPgDump>(CephQuery::PgDump, TIMEOUT)
+        .await
+        .unwrap_err();
+
+    match &error {
+        CephError::CommandFailed { stderr, .. } => assert!(stderr.contains("EACCES")),
+        other => panic!("expected a command failure, got {other:?}"),
+    }
+}
+
+#[tokio::test]
+async fn test_unreachable_server() {
+    // Grab a free port and close it again so nothing is listening there
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    drop(listener);
+
+    let error = source(&url, KEY)
+        .fetch::<CephPgDump>(CephQuery::PgDump, TIMEOUT)
+        .await
+        .unwrap_err();
+    assert_eq!(error.class(), ErrorClass::MgrUnreachable);
+}